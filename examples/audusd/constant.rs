@@ -2,9 +2,11 @@ pub const PROFIT_TAKING: f64 = 0.4 / 100.;
 pub const STOP_LOSS: f64 = -0.2 / 100.;
 pub const FAST_TUNNEL_PERIOD: usize = 144;
 pub const SLOW_TUNNEL_PERIOD: usize = 169;
-// pub const FAST_TUNNEL_PERIOD4H: usize = 144 * 4;
-// pub const SLOW_TUNNEL_PERIOD4H: usize = 169 * 4;
 pub const FILTER_PERIOD: usize = 12;
 pub const SLOPE_PERIOD: usize = 50;
 pub const SLOPE_THRESHOLD: f64 = 1e-6;
 pub const SECOND_ORDER_SLOPE_PERIOD: usize = 4;
+/// 收盘（纽约时间 17:00）前一小时临时平仓的定时器
+pub const PRE_MIDNIGHT_TIMER: &str = "pre_midnight";
+/// 开盘后恢复隔夜仓位的定时器
+pub const MIDNIGHT_TIMER: &str = "midnight";
@@ -1,5 +1,4 @@
 mod constant;
-mod lines;
 mod overnight;
 mod signal_indicator;
 mod sizer;
@@ -8,35 +7,62 @@ mod strat;
 mod trade;
 mod util;
 
-use std::{fs::read_to_string, time::Instant};
+use std::time::Instant;
 
-use rushtrader::{CsvBroker, CsvDataSource, CsvTimeType, Engine};
+use chrono::{Duration, NaiveTime};
+use rushtrader::{
+  CsvBroker, CsvDataSource, CsvResampler, CsvTimeType, Engine, IndicatorGraph, Timeframe, Timer,
+  TradingCalendar,
+};
 
-use crate::{lines::VLines, strat::VegasStrategy};
+use crate::{
+  constant::{MIDNIGHT_TIMER, PRE_MIDNIGHT_TIMER},
+  strat::VegasStrategy,
+};
 
 fn main() {
   let st = Instant::now();
   let data = CsvDataSource::builder()
     .time_field("date")
     .time_type(CsvTimeType::Datetime("%m/%d/%Y %H:%M"))
-    .load_from_lines(VLines::new(
-      read_to_string(
-        &std::env::current_dir()
-          .unwrap()
-          .join("examples/audusd/audusd.csv"),
-      )
-      .unwrap()
-      .lines(),
-    ))
+    .load_from_file(
+      &std::env::current_dir()
+        .unwrap()
+        .join("examples/audusd/audusd.csv"),
+    )
     .unwrap_or_else(|e| {
       eprintln!("{}", e);
       panic!("error");
     });
 
-  let strat = VegasStrategy::new();
+  let mut graph = IndicatorGraph::new();
+  let strat = VegasStrategy::new(&mut graph);
   let broker = CsvBroker::new(1_000_000_000.0);
 
   let mut engine = Engine::new(data, strat, broker);
+  // 4 小时线按纽约时间 17:00 的外汇日线收盘对齐
+  engine.add_timeframe(
+    CsvResampler::new(Timeframe::Hours(4))
+      .timezone(chrono_tz::America::New_York)
+      .day_close(NaiveTime::from_hms_opt(17, 0, 0).unwrap()),
+  );
+  // 收盘前一小时临时平仓，开盘后恢复隔夜仓位
+  engine
+    .add_timer(
+      PRE_MIDNIGHT_TIMER,
+      Timer::SessionClose {
+        calendar: TradingCalendar::fx(),
+        offset: Duration::hours(-1),
+      },
+    )
+    .add_timer(
+      MIDNIGHT_TIMER,
+      Timer::SessionOpen {
+        calendar: TradingCalendar::fx(),
+        offset: Duration::zero(),
+      },
+    );
+  engine.set_indicators(graph).unwrap();
   engine.run();
   let st = Instant::now().duration_since(st);
   println!(
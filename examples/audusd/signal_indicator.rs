@@ -1,78 +1,78 @@
-use rushtrader::{
-  util::get_vec_at, CrossOverIndicator, DataLine, DataLineFeed, LinearregSlopeIndicator,
-};
+use rushtrader::{check_inputs, check_update, DataLine, DataLineFeed, Indicator, IndicatorLine};
 
 use crate::constant::SLOPE_THRESHOLD;
 
+/// 和原来的实现一致，slope 取所有输入都有效的第一个 bar 上的值
 pub struct SignalIndicator {
-  pub(crate) start_pos: usize,
-  pub(crate) data: Vec<f64>,
+  line: IndicatorLine,
+  slope: Option<f64>,
 }
 
 impl DataLineFeed for SignalIndicator {
   #[inline(always)]
   fn inner(&self) -> (&[f64], usize) {
-    (&self.data, self.start_pos)
+    self.line.inner()
   }
 }
 impl DataLine for SignalIndicator {
   #[inline(always)]
   fn at(&self, index: usize) -> Option<f64> {
-    get_vec_at(&self.data, self.start_pos, index)
+    self.line.at(index)
   }
 }
 impl SignalIndicator {
   pub fn new() -> Self {
     Self {
-      data: Vec::new(),
-      start_pos: 0,
+      line: IndicatorLine::new(),
+      slope: None,
     }
   }
-  pub fn feed(
-    &mut self,
-    cross_max: &CrossOverIndicator,
-    cross_min: &CrossOverIndicator,
-    slope: &LinearregSlopeIndicator,
-  ) {
-    let (buf_a, start_pos_a) = cross_max.inner();
-    let (buf_b, start_pos_b) = cross_min.inner();
-    let (buf_c, start_pos_c) = slope.inner();
-    let buf_a_len = buf_a.len();
-    assert_eq!(buf_a_len, buf_b.len());
-    assert_eq!(buf_a_len, buf_c.len());
-    let data_len = self.data.len();
-    if data_len == 0 {
-      self.data = Vec::with_capacity(buf_a_len)
+}
+#[inline]
+fn calc_signal(cross_max: f64, cross_min: f64, slope: f64) -> f64 {
+  if slope.abs() > SLOPE_THRESHOLD {
+    if cross_max > 0. {
+      1.
+    } else if cross_min < 0. {
+      -1.
     } else {
-      assert_eq!(buf_a_len, data_len);
+      0.
     }
-    let start_pos = start_pos_a.max(start_pos_b).max(start_pos_c);
-    self.start_pos = start_pos;
-    unsafe {
-      let mut pa = buf_a.as_ptr().add(start_pos);
-      let mut pb = buf_b.as_ptr().add(start_pos);
-      let pc = buf_c.as_ptr().add(start_pos);
-      let mut pd = self.data.as_mut_ptr().add(start_pos);
-      for _ in start_pos..buf_a_len {
-        let cross_max = *pa;
-        let cross_min = *pb;
-        let slope = *pc;
-        *pd = if slope.abs() > SLOPE_THRESHOLD {
-          if cross_max > 0. {
-            1.
-          } else if cross_min < 0. {
-            -1.
-          } else {
-            0.
-          }
-        } else {
-          0.
-        };
-        pa = pa.add(1);
-        pb = pb.add(1);
-        pd = pd.add(1);
-      }
-      self.data.set_len(buf_a_len);
+  } else {
+    0.
+  }
+}
+
+impl Indicator for SignalIndicator {
+  fn name(&self) -> &'static str {
+    "SIGNAL"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["cross_max", "cross_min", "slope"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("slope_threshold", SLOPE_THRESHOLD)]
+  }
+  fn lookback(&self) -> usize {
+    0
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let [cross_max, cross_min, slope] = [0, 1, 2].map(|i| inputs[i].inner().0);
+    self.slope = slope.get(start_pos).copied();
+    let data = self.line.reset(len, start_pos);
+    for i in start_pos..len {
+      data[i] = calc_signal(cross_max[i], cross_min[i], slope[start_pos]);
     }
   }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let value = check_update(self, inputs).map(|[cross_max, cross_min, slope]| {
+      let slope = *self.slope.get_or_insert(slope);
+      calc_signal(cross_max, cross_min, slope)
+    });
+    self.line.push(value);
+  }
 }
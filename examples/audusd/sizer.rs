@@ -1,4 +1,4 @@
-use rushtrader::{Broker, CsvBroker};
+use rushtrader::{CsvBroker, SingleBroker};
 
 use crate::{overnight::OvernightType, strat::VegasStrategy};
 
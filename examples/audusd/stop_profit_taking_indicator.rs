@@ -1,61 +1,65 @@
-use rushtrader::{util::get_vec_at, DataLine, DataLineFeed, LinearregSlopeIndicator, MOMIndicator};
+use rushtrader::{check_inputs, check_update, DataLine, DataLineFeed, Indicator, IndicatorLine};
 
 pub struct StopProfitTakingIndicator {
-  start_pos: usize,
-  data: Vec<f64>,
+  line: IndicatorLine,
 }
 
 impl DataLineFeed for StopProfitTakingIndicator {
   #[inline(always)]
   fn inner(&self) -> (&[f64], usize) {
-    (&self.data, self.start_pos)
+    self.line.inner()
   }
 }
 impl DataLine for StopProfitTakingIndicator {
   #[inline(always)]
   fn at(&self, index: usize) -> Option<f64> {
-    get_vec_at(&self.data, self.start_pos, index)
+    self.line.at(index)
   }
 }
 impl StopProfitTakingIndicator {
   pub fn new() -> Self {
     Self {
-      data: Vec::new(),
-      start_pos: 0,
+      line: IndicatorLine::new(),
     }
   }
-  pub fn feed(&mut self, second_order_slope: &MOMIndicator, slope4h: &LinearregSlopeIndicator) {
-    let (buf_a, start_pos_a) = second_order_slope.inner();
-    let (buf_b, start_pos_b) = slope4h.inner();
-    let buf_a_len = buf_a.len();
-    assert_eq!(buf_a_len, buf_b.len());
-    let data_len = self.data.len();
-    if data_len == 0 {
-      self.data = Vec::with_capacity(buf_a_len)
-    } else {
-      assert_eq!(buf_a_len, data_len);
-    }
-    let start_pos = start_pos_a.max(start_pos_b);
-    self.start_pos = start_pos;
-    unsafe {
-      let mut pa = buf_a.as_ptr().add(start_pos);
-      let mut pb = buf_b.as_ptr().add(start_pos);
-      let mut pd = self.data.as_mut_ptr().add(start_pos);
-      for _ in start_pos..buf_a_len {
-        let second_order_slope = *pa;
-        let slope4h = *pb;
-        *pd = if second_order_slope < 0. && slope4h < 0. {
-          1.
-        } else if second_order_slope > 0. && slope4h > 0. {
-          -1.
-        } else {
-          0.
-        };
-        pa = pa.add(1);
-        pb = pb.add(1);
-        pd = pd.add(1);
-      }
-      self.data.set_len(buf_a_len);
+}
+#[inline]
+fn calc_stop_profit_taking(second_order_slope: f64, slope4h: f64) -> f64 {
+  if second_order_slope < 0. && slope4h < 0. {
+    1.
+  } else if second_order_slope > 0. && slope4h > 0. {
+    -1.
+  } else {
+    0.
+  }
+}
+
+impl Indicator for StopProfitTakingIndicator {
+  fn name(&self) -> &'static str {
+    "STOP_PROFIT_TAKING"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["second_order_slope", "slope4h"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    Vec::new()
+  }
+  fn lookback(&self) -> usize {
+    0
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let (second_order_slope, slope4h) = (inputs[0].inner().0, inputs[1].inner().0);
+    let data = self.line.reset(len, start_pos);
+    for i in start_pos..len {
+      data[i] = calc_stop_profit_taking(second_order_slope[i], slope4h[i]);
     }
   }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let value = check_update(self, inputs).map(|[a, b]| calc_stop_profit_taking(a, b));
+    self.line.push(value);
+  }
 }
@@ -1,39 +1,30 @@
-use chrono::{DateTime, Timelike, Utc};
 use rushtrader::{
-  Broker, CrossOverIndicator, CsvBroker, CsvDataSource, DataLine, DataSource, EMAIndicator,
-  LinearregSlopeIndicator, MOMIndicator, MaxIndicator, MinIndicator, Order, OrderStatus, Strategy,
+  Broker, CrossOverIndicator, CsvBroker, CsvDataSource, DataLine, EMAIndicator, IndicatorGraph,
+  IndicatorRef, LinearregSlopeIndicator, MOMIndicator, MaxIndicator, MinIndicator, Order,
+  OrderStatus, Series, SingleBroker, Strategy, Timeframe, TimerEvent,
 };
 
 use crate::{
   bool_map,
   constant::{
-    FAST_TUNNEL_PERIOD, FILTER_PERIOD, PROFIT_TAKING, SECOND_ORDER_SLOPE_PERIOD, SLOPE_PERIOD,
-    SLOW_TUNNEL_PERIOD, STOP_LOSS,
+    FAST_TUNNEL_PERIOD, FILTER_PERIOD, MIDNIGHT_TIMER, PRE_MIDNIGHT_TIMER, PROFIT_TAKING,
+    SECOND_ORDER_SLOPE_PERIOD, SLOPE_PERIOD, SLOW_TUNNEL_PERIOD, STOP_LOSS,
   },
   overnight::{Overnight, OvernightSignal, OvernightType},
   signal_indicator::SignalIndicator,
   stop_profit_taking_indicator::StopProfitTakingIndicator,
   trade::{complete_trade, VegasTradeInfo, VegasTradeOrderType},
-  util::get_market_close_utc_hours,
 };
 
 pub(super) struct VegasStrategy {
   // indicators begin
-  pub fast_tunnel: EMAIndicator,
-  pub slow_tunnel: EMAIndicator,
-  pub filter_tunnel: EMAIndicator,
-  pub max_tunnel: MaxIndicator,
-  pub min_tunnel: MinIndicator,
-  pub slope: LinearregSlopeIndicator,
-  pub tunnel4h: EMAIndicator,
-  pub slope4h: LinearregSlopeIndicator,
-  pub second_order_slope: MOMIndicator,
-  pub cross_max: CrossOverIndicator,
-  pub cross_min: CrossOverIndicator,
-  pub signal: SignalIndicator,
-  pub stop_profit_taking: StopProfitTakingIndicator,
+  pub signal: IndicatorRef,
+  pub stop_profit_taking: IndicatorRef,
   // indicator end
   pub overnight: Overnight,
+  /// 最近一次收盘前一小时和开盘的定时器触发时的 bar 下标
+  pub pre_midnight_index: Option<usize>,
+  pub midnight_index: Option<usize>,
   // pub trade: VegasTradeInfo,
   pub p_trade: *mut VegasTradeInfo,
 }
@@ -46,39 +37,47 @@ impl Drop for VegasStrategy {
   }
 }
 impl VegasStrategy {
-  pub(super) fn new() -> Self {
+  /// 在 graph 中声明策略使用的指标，指标在 feed 之前由 engine 按依赖关系计算
+  pub(super) fn new(graph: &mut IndicatorGraph) -> Self {
+    let fast_tunnel = graph.add(EMAIndicator::new(FAST_TUNNEL_PERIOD), Series::Close);
+    let slow_tunnel = graph.add(EMAIndicator::new(SLOW_TUNNEL_PERIOD), Series::Close);
+    let filter_tunnel = graph.add(EMAIndicator::new(FILTER_PERIOD), Series::Close);
+    let max_tunnel = graph.add(MaxIndicator::new(), (fast_tunnel, slow_tunnel));
+    let min_tunnel = graph.add(MinIndicator::new(), (fast_tunnel, slow_tunnel));
+    let slope = graph.add(LinearregSlopeIndicator::new(SLOPE_PERIOD), slow_tunnel);
+    let h4_close = Series::Timeframe(Timeframe::Hours(4), Box::new(Series::Close));
+    let tunnel4h = graph.add(EMAIndicator::new(FAST_TUNNEL_PERIOD), h4_close);
+    let slope4h = graph.add(LinearregSlopeIndicator::new(SLOPE_PERIOD), tunnel4h);
+    let second_order_slope = graph.add(MOMIndicator::new(SECOND_ORDER_SLOPE_PERIOD), slope);
+    let cross_max = graph.add(CrossOverIndicator::new(), (filter_tunnel, max_tunnel));
+    let cross_min = graph.add(CrossOverIndicator::new(), (filter_tunnel, min_tunnel));
+    let signal = graph.add(SignalIndicator::new(), (cross_max, cross_min, slope));
+    let slope4h = graph.project(Timeframe::Hours(4), slope4h);
+    let stop_profit_taking = graph.add(
+      StopProfitTakingIndicator::new(),
+      (second_order_slope, slope4h),
+    );
     VegasStrategy {
-      fast_tunnel: EMAIndicator::new(FAST_TUNNEL_PERIOD),
-      slow_tunnel: EMAIndicator::new(SLOW_TUNNEL_PERIOD),
-      filter_tunnel: EMAIndicator::new(FILTER_PERIOD),
-      max_tunnel: MaxIndicator::new(),
-      min_tunnel: MinIndicator::new(),
-      slope: LinearregSlopeIndicator::new(SLOPE_PERIOD),
-      tunnel4h: EMAIndicator::new(FAST_TUNNEL_PERIOD * 4),
-      slope4h: LinearregSlopeIndicator::new(SLOPE_PERIOD * 4),
-      second_order_slope: MOMIndicator::new(SECOND_ORDER_SLOPE_PERIOD),
-      cross_max: CrossOverIndicator::new(),
-      cross_min: CrossOverIndicator::new(),
-      signal: SignalIndicator::new(),
-      stop_profit_taking: StopProfitTakingIndicator::new(),
+      signal,
+      stop_profit_taking,
 
       overnight: Overnight::new(),
+      pre_midnight_index: None,
+      midnight_index: None,
       p_trade: Box::into_raw(Box::new(VegasTradeInfo::new())),
     }
   }
 
+  /// 跨过收盘（纽约时间 17:00）前一小时的 bar
   #[inline]
-  fn is_pre_midnight(&self, dt: &DateTime<Utc>) -> bool {
-    let uh = dt.hour();
-    let ch = get_market_close_utc_hours(dt);
-    uh >= ch - 1 && uh < ch
+  fn is_pre_midnight(&self, index: usize) -> bool {
+    self.pre_midnight_index == Some(index)
   }
 
+  /// 跨过开盘的 bar
   #[inline]
-  fn is_midnight(&self, dt: &DateTime<Utc>) -> bool {
-    let uh = dt.hour();
-    let ch = get_market_close_utc_hours(dt);
-    uh >= ch && uh < ch + 1
+  fn is_midnight(&self, index: usize) -> bool {
+    self.midnight_index == Some(index)
   }
 }
 
@@ -101,38 +100,16 @@ impl Strategy for VegasStrategy {
       broker.position().origin_price,
     );
     // println!("Final pnlmm: {}", self.pnlcomm);
-    println!(
-      "[INFO] Final value: {}",
-      broker.cash() + data.calc_position_value(broker.position_size())
-    );
+    println!("[INFO] Final value: {}", broker.value(data));
   }
-  fn feed(&mut self, data: &CsvDataSource) {
-    self.fast_tunnel.feed(&data.close);
-    self.slow_tunnel.feed(&data.close);
-    self.filter_tunnel.feed(&data.close);
-    self.max_tunnel.feed(&self.fast_tunnel, &self.slow_tunnel);
-    self.min_tunnel.feed(&self.fast_tunnel, &self.slow_tunnel);
-    self.slope.feed(&self.slow_tunnel);
-    self.tunnel4h.feed(&data.close);
-    self.slope4h.feed(&self.tunnel4h);
-    self.second_order_slope.feed(&self.slope);
-    self.cross_max.feed(&self.filter_tunnel, &self.max_tunnel);
-    self.cross_min.feed(&self.filter_tunnel, &self.min_tunnel);
-    self
-      .signal
-      .feed(&self.cross_max, &self.cross_min, &self.slope);
-    self
-      .stop_profit_taking
-      .feed(&self.second_order_slope, &self.slope4h);
-
-    // println!("{}", self.stop_profit_taking.inner().1);
-    // let (a, b) = self.signal.inner();
-    // println!("{}", b);
-    // a.iter().enumerate().skip(b).for_each(|(i, v)| {
-    //   println!("{}: {}", i, v)
-    // });
-    // panic!("exit")
+  fn on_timer(&mut self, event: &TimerEvent, _data: &CsvDataSource, _broker: &mut CsvBroker) {
+    match event.name.as_str() {
+      PRE_MIDNIGHT_TIMER => self.pre_midnight_index = Some(event.index),
+      MIDNIGHT_TIMER => self.midnight_index = Some(event.index),
+      _ => {}
+    }
   }
+  fn feed(&mut self, _data: &CsvDataSource) {}
 
   fn on_order(&self, order: &Order, broker: &CsvBroker) {
     if let OrderStatus::Completed(completed_at) = order.status {
@@ -179,8 +156,8 @@ impl Strategy for VegasStrategy {
     }
     let dt = get_data!(data.timestamp);
     let v_close_price = get_data!(data.close);
-    let v_signal = get_data!(self.signal);
-    let v_stop_profit_taking = get_data!(self.stop_profit_taking);
+    let v_signal = get_data!(data.indicator(self.signal));
+    let v_stop_profit_taking = get_data!(data.indicator(self.stop_profit_taking));
 
     let has_overnight = !matches!(self.overnight.overtype, OvernightType::None);
     let has_overnight_signal = !matches!(self.overnight.signal, OvernightSignal::None);
@@ -188,10 +165,10 @@ impl Strategy for VegasStrategy {
     let trade_info = unsafe { &mut *self.p_trade };
 
     // if index == 804 {
-    //   println!("{} {} {}", self.is_pre_midnight(&dt), dt, dt.with_timezone(&chrono_tz::America::New_York));
+    //   println!("{} {} {}", self.is_pre_midnight(index), dt, dt.with_timezone(&chrono_tz::America::New_York));
     // }
     if position_size == 0 {
-      if self.is_pre_midnight(&dt) {
+      if self.is_pre_midnight(index) {
         if has_overnight {
           eprintln!("[ERROR] [{}] UNEXPECTED overnight type", dt);
           self.overnight.overtype = OvernightType::None;
@@ -208,7 +185,7 @@ impl Strategy for VegasStrategy {
         }
         return;
       }
-      if self.is_midnight(&dt) {
+      if self.is_midnight(index) {
         if v_signal != 0. {
           let sig_ty = bool_map!(
             v_signal > 0.,
@@ -407,7 +384,7 @@ impl Strategy for VegasStrategy {
             data,
             broker,
           );
-        } else if self.is_pre_midnight(&dt) {
+        } else if self.is_pre_midnight(index) {
           println!(
             "[INFO] [{}] Midnight upcoming. Temporarily close {} position.",
             dt,
@@ -438,7 +415,7 @@ impl Strategy for VegasStrategy {
           data,
           broker,
         );
-      } else if self.is_pre_midnight(&dt) {
+      } else if self.is_pre_midnight(index) {
         println!(
           "[INFO] {} Midnight upcoming. Temporarily close {} position.",
           dt,
@@ -1,8 +1,8 @@
 use std::{cell::RefCell, time::Instant};
 
 use rushtrader::{
-  Broker, CsvBroker, CsvDataSource, CsvTimeType, DataLine, DataSource, Engine, Order, OrderStatus,
-  SMAIndicator, Strategy, Trade, TradeStatus,
+  Broker, CsvBroker, CsvDataSource, CsvTimeType, DataLine, Engine, Indicator, Order, OrderStatus,
+  SMAIndicator, SingleBroker, Strategy, Trade, TradeStatus,
 };
 
 struct DemoStrategy {
@@ -33,13 +33,10 @@ impl Strategy for DemoStrategy {
     println!("Final cash: {}", broker.cash());
     println!("Final position: {}", broker.position());
     println!("Final pnlmm: {}", self.pnlcomm.borrow());
-    println!(
-      "Final Portfolio Value: {}",
-      broker.cash() + data.calc_position_value(broker.position_size())
-    );
+    println!("Final Portfolio Value: {}", broker.value(data));
   }
   fn feed(&mut self, data: &CsvDataSource) {
-    self.sma.feed(&data.close);
+    self.sma.compute(&[&data.close]);
   }
 
   fn on_order(&self, order: &Order, _: &CsvBroker) {
@@ -84,14 +81,14 @@ impl Strategy for DemoStrategy {
 
 fn main() {
   let st = Instant::now();
+  let dir = std::env::current_dir().unwrap();
+  // 第二次运行开始直接从 target 目录下的缓存加载，csv 文件被修改后会自动重新生成缓存
   let data = CsvDataSource::builder()
     .time_field("date")
     .time_type(CsvTimeType::Date("%Y-%m-%d"))
-    .load_from_file(
-      &std::env::current_dir()
-        .unwrap()
-        .join("examples")
-        .join("orcl-1995-2014.csv"),
+    .load_from_file_cached(
+      &dir.join("examples").join("orcl-1995-2014.csv"),
+      &dir.join("target").join("orcl-1995-2014.cache"),
     )
     .unwrap();
 
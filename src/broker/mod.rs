@@ -6,18 +6,35 @@ pub use order::*;
 pub use position::*;
 pub use trade::*;
 
-use crate::{DataSource, Strategy};
+use crate::{CorporateAction, DataSource, Strategy};
 
 pub trait Broker {
   type DS: DataSource;
   /// 获取当前剩余现金
   fn cash(&self) -> f64;
+  /// 按当前价格计算全部仓位的现金价值
+  fn position_value(&self, data: &Self::DS) -> f64;
+  /// 获取当前总资产，即剩余现金加上全部仓位的现金价值
+  fn value(&self, data: &Self::DS) -> f64 {
+    self.cash() + self.position_value(data)
+  }
+  /// 处理除权日生效的公司行为（拆股和分红），在除权日当天或之后的第一个 bar 的定时器和 Strategy::next 之前调用。
+  /// 默认不做任何处理。
+  fn on_corporate_action(&mut self, _action: &CorporateAction, _data: &Self::DS) {
+    // do nothing by default
+  }
+}
+
+/// 只交易一个品种的 broker，比如 CsvBroker 和 CsvTickBroker。多品种的 PortfolioBroker 按品种提供同样的操作。
+pub trait SingleBroker: Broker {
   /// 获取当前仓位持仓量
   fn position_size(&self) -> isize;
   /// 获取当前仓位建仓价
   fn position(&self) -> &Position;
   /// 当前是否是空仓
-  fn is_position_empty(&self) -> bool;
+  fn is_position_empty(&self) -> bool {
+    self.position_size() == 0
+  }
   /// 建买仓（多仓）
   fn buy<S: Strategy<BK = Self, DS = Self::DS>>(&mut self, size: isize, data: &Self::DS, strat: &S);
   /// 建卖仓（空仓）
@@ -25,6 +25,8 @@ pub enum OrderStatus {
 }
 
 pub struct Order {
+  /// 订单的品种，只有多品种的 broker（比如 PortfolioBroker）才会设置
+  pub symbol: Option<String>,
   pub ordertype: OrderType,
   pub position_type: OrderPositionType,
   pub size: isize,
@@ -44,6 +46,7 @@ impl Order {
     created_at: DateTime<Utc>,
   ) -> Self {
     Self {
+      symbol: None,
       ordertype: OrderType::Market,
       position_type,
       size,
@@ -69,4 +72,19 @@ impl Order {
   pub fn is_buy(&self) -> bool {
     matches!(self.position_type, OrderPositionType::Buy)
   }
+  /// 带方向的成交量，buy 为正，sell 为负。
+  #[inline]
+  pub fn deal_size(&self) -> isize {
+    if self.is_buy() {
+      self.exe_size
+    } else {
+      -self.exe_size
+    }
+  }
+  /// 按成交量和成交价计算订单的成交额和手续费。
+  #[inline]
+  pub(crate) fn settle(&mut self, comm: f64) {
+    self.cost = (self.deal_size() as f64) * self.exe_price;
+    self.comm = comm;
+  }
 }
@@ -1,5 +1,7 @@
 use std::fmt::Display;
 
+use crate::Order;
+
 #[derive(Debug)]
 pub struct Position {
   pub size: isize,
@@ -16,6 +18,34 @@ impl Position {
     }
   }
 }
+impl Position {
+  /// 按已经成交的订单更新仓位，返回更新前的持仓量。
+  pub(crate) fn deal(&mut self, order: &Order) -> isize {
+    let exe_price = order.exe_price;
+    let deal_size = order.deal_size();
+
+    let pre_s = self.size;
+    if pre_s == 0 {
+      self.size = deal_size;
+      self.price = exe_price;
+    } else {
+      self.size += deal_size;
+    }
+    let post_s = self.size;
+    if post_s != 0 {
+      if (pre_s > 0 && post_s > 0) || (pre_s < 0 && post_s < 0) {
+        // calcuate average price
+        self.price = ((pre_s as f64) * self.price + (deal_size as f64) * exe_price)
+          / (pre_s + deal_size) as f64;
+      } else {
+        // close and open position
+        self.price = exe_price;
+        self.origin_price = exe_price;
+      }
+    }
+    pre_s
+  }
+}
 impl Default for Position {
   fn default() -> Self {
     Self::new()
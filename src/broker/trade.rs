@@ -1,13 +1,18 @@
 use chrono::{DateTime, Utc};
 
-#[derive(Debug)]
+use crate::Order;
+
+#[derive(Debug, Clone)]
 pub enum TradeStatus {
   Open(DateTime<Utc>),
   Closed(DateTime<Utc>),
   Uninit,
 }
 
+#[derive(Debug, Clone)]
 pub struct Trade {
+  /// 交易的品种，只有多品种的 broker（比如 PortfolioBroker）才会设置
+  pub symbol: Option<String>,
   pub pnl: f64,
   pub pnlcomm: f64,
   pub status: TradeStatus,
@@ -16,6 +21,7 @@ pub struct Trade {
 impl Trade {
   pub(crate) fn new() -> Self {
     Self {
+      symbol: None,
       status: TradeStatus::Uninit,
       pnl: 0.,
       pnlcomm: 0.,
@@ -23,3 +29,92 @@ impl Trade {
     }
   }
 }
+
+impl Trade {
+  /// 按已经成交的订单以及订单成交前后的持仓量更新交易，
+  /// 返回需要通知策略（Strategy::on_trade）的交易状态，按发生的先后顺序排列。
+  pub(crate) fn deal(
+    &mut self,
+    order: &Order,
+    pre_s: isize,
+    post_s: isize,
+    completed_at: DateTime<Utc>,
+  ) -> Vec<Trade> {
+    let is_uninit = matches!(&self.status, TradeStatus::Uninit);
+
+    if is_uninit && pre_s != 0 {
+      panic!("unexpected");
+    }
+
+    let mut events = Vec::new();
+    // https://zhuanlan.zhihu.com/p/299630905
+    let cost = -order.cost;
+    let commcost = cost - order.comm;
+    if is_uninit {
+      self.pnl = cost;
+      self.pnlcomm = commcost;
+      self.status = TradeStatus::Open(completed_at);
+      events.push(self.clone());
+    } else if post_s == 0 {
+      self.pnl += cost;
+      self.pnlcomm += commcost;
+      self.status = TradeStatus::Closed(completed_at);
+      events.push(self.clone());
+      self.status = TradeStatus::Uninit;
+    } else if pre_s > 0 && post_s < 0 || pre_s < 0 && post_s > 0 {
+      let pre_percent = (pre_s as f64 / (post_s - pre_s) as f64).abs();
+      self.pnl += cost * pre_percent;
+      self.pnlcomm += commcost * pre_percent;
+      self.status = TradeStatus::Closed(completed_at);
+      events.push(self.clone());
+      let post_percent = (post_s as f64 / (post_s - pre_s) as f64).abs();
+      self.pnl = cost * post_percent;
+      self.pnlcomm = commcost * post_percent;
+      self.status = TradeStatus::Open(completed_at);
+      events.push(self.clone());
+    } else {
+      self.pnl += cost;
+      self.pnlcomm += commcost;
+    }
+    events
+  }
+}
+
+#[test]
+fn test_trade_deal() {
+  use crate::OrderPositionType;
+
+  let at = Utc::now();
+  let order = |size: isize, price: f64| {
+    let mut order = Order::new(
+      size,
+      if size > 0 {
+        OrderPositionType::Buy
+      } else {
+        OrderPositionType::Sell
+      },
+      at,
+    );
+    order.exe_size = size;
+    order.exe_price = price;
+    order.cost = size as f64 * price;
+    order.comm = order.cost.abs() * 0.01;
+    order
+  };
+  let mut trade = Trade::new();
+  assert_eq!(trade.deal(&order(2, 10.), 0, 2, at).len(), 1);
+  // 反手：卖出 4 个，一半平掉多头，一半开空头
+  let events = trade.deal(&order(-4, 12.), 2, -2, at);
+  assert_eq!(events.len(), 2);
+  assert!(matches!(events[0].status, TradeStatus::Closed(_)));
+  assert!((events[0].pnl - 4.).abs() < 1e-9);
+  assert!((events[0].pnlcomm - 3.56).abs() < 1e-9);
+  assert!(matches!(events[1].status, TradeStatus::Open(_)));
+  assert!((events[1].pnl - 24.).abs() < 1e-9);
+  // 部分平仓之后再全部平仓
+  assert!(trade.deal(&order(1, 11.), -2, -1, at).is_empty());
+  let events = trade.deal(&order(1, 11.), -1, 0, at);
+  assert!(matches!(events[0].status, TradeStatus::Closed(_)));
+  assert!((events[0].pnl - 2.).abs() < 1e-9);
+  assert!((events[0].pnlcomm - 1.54).abs() < 1e-9);
+}
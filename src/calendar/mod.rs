@@ -0,0 +1,5 @@
+mod timer;
+mod trading;
+
+pub use timer::*;
+pub use trading::*;
@@ -0,0 +1,201 @@
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+
+use super::trading::{local_time_to_utc, TradingCalendar};
+
+/// 查找交易时段时最多向后查找的天数，超过后认为交易日历没有交易时段
+const MAX_SESSION_DAYS: usize = 366;
+
+/// 定时器的计划触发时间。
+#[derive(Debug, Clone)]
+pub enum Timer {
+  /// 每天 tz 时区的 time
+  Daily { time: NaiveTime, tz: Tz },
+  /// 每周 weekday 的 time（tz 时区）
+  Weekly {
+    weekday: Weekday,
+    time: NaiveTime,
+    tz: Tz,
+  },
+  /// 每个交易时段开盘之后 offset，offset 可以为负数
+  SessionOpen {
+    calendar: TradingCalendar,
+    offset: Duration,
+  },
+  /// 每个交易时段收盘之后 offset，比如收盘前一小时为 Duration::hours(-1)
+  SessionClose {
+    calendar: TradingCalendar,
+    offset: Duration,
+  },
+}
+
+impl Timer {
+  /// t 之后（不包括 t）的第一个计划触发时间，交易日历一年内都没有交易时段时返回 None
+  pub fn next_after(&self, t: &DateTime<Utc>) -> Option<DateTime<Utc>> {
+    // 从前一天开始查找，避免遗漏跨天的交易时段以及时区偏移的影响。
+    let days_from = |t: DateTime<Utc>, tz: Tz| {
+      t.with_timezone(&tz)
+        .date_naive()
+        .pred_opt()
+        .into_iter()
+        .flat_map(|date| date.iter_days())
+    };
+    match self {
+      Timer::Daily { time, tz } => days_from(*t, *tz)
+        .take(4)
+        .map(|date| local_time_to_utc(*tz, date, *time))
+        .find(|dt| dt > t),
+      Timer::Weekly { weekday, time, tz } => days_from(*t, *tz)
+        .take(10)
+        .filter(|date| date.weekday() == *weekday)
+        .map(|date| local_time_to_utc(*tz, date, *time))
+        .find(|dt| dt > t),
+      Timer::SessionOpen { calendar, offset } => days_from(*t - *offset, calendar.timezone())
+        .take(MAX_SESSION_DAYS)
+        .filter_map(|date| calendar.session_on(date))
+        .map(|(open, _)| open + *offset)
+        .find(|dt| dt > t),
+      Timer::SessionClose { calendar, offset } => days_from(*t - *offset, calendar.timezone())
+        .take(MAX_SESSION_DAYS)
+        .filter_map(|date| calendar.session_on(date))
+        .map(|(_, close)| close + *offset)
+        .find(|dt| dt > t),
+    }
+  }
+}
+
+/// 定时器触发时传给 Strategy::on_timer 的事件。
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimerEvent {
+  /// 注册定时器时指定的名称
+  pub name: String,
+  /// 被跨过的计划触发时间，一个 bar 跨过多个计划触发时间时为其中最早的一个
+  pub time: DateTime<Utc>,
+  /// 跨过计划触发时间的 bar 的下标
+  pub index: usize,
+}
+
+/// 按 bar 的时间依次检查已注册的定时器。
+pub(crate) struct TimerScheduler {
+  timers: Vec<(String, Timer, Option<DateTime<Utc>>)>,
+}
+
+impl TimerScheduler {
+  /// start 为第一个 bar 的时间，只有之后的 bar 跨过的计划触发时间才会触发定时器
+  pub(crate) fn new(timers: &[(String, Timer)], start: &DateTime<Utc>) -> Self {
+    Self {
+      timers: timers
+        .iter()
+        .map(|(name, timer)| (name.clone(), timer.clone(), timer.next_after(start)))
+        .collect(),
+    }
+  }
+  /// 返回第 index 个 bar（时间为 t）跨过计划触发时间的定时器事件
+  pub(crate) fn poll(&mut self, index: usize, t: &DateTime<Utc>) -> Vec<TimerEvent> {
+    let mut events = Vec::new();
+    for (name, timer, next) in self.timers.iter_mut() {
+      if let Some(time) = next.filter(|time| time <= t) {
+        events.push(TimerEvent {
+          name: name.clone(),
+          time,
+          index,
+        });
+        *next = timer.next_after(t);
+      }
+    }
+    events
+  }
+}
+
+#[test]
+fn test_timer() {
+  use crate::{
+    CsvBroker, CsvDataSource, CsvTimeType, Engine, SingleBroker, Strategy, TradingCalendar,
+  };
+  use chrono::TimeZone;
+  use chrono_tz::America::New_York;
+
+  let utc = |m, d, h| Utc.with_ymd_and_hms(2022, m, d, h, 0, 0).unwrap();
+  let five_pm = NaiveTime::from_hms_opt(17, 0, 0).unwrap();
+  let daily = Timer::Daily {
+    time: five_pm,
+    tz: New_York,
+  };
+  // 夏令时结束前后纽约时间 17:00 分别为 21:00 UTC 和 22:00 UTC
+  assert_eq!(daily.next_after(&utc(11, 4, 12)), Some(utc(11, 4, 21)));
+  assert_eq!(daily.next_after(&utc(11, 4, 21)), Some(utc(11, 5, 21)));
+  assert_eq!(daily.next_after(&utc(11, 6, 21)), Some(utc(11, 6, 22)));
+  let weekly = Timer::Weekly {
+    weekday: Weekday::Sun,
+    time: five_pm,
+    tz: New_York,
+  };
+  assert_eq!(weekly.next_after(&utc(11, 1, 0)), Some(utc(11, 6, 22)));
+  let pre_close = Timer::SessionClose {
+    calendar: TradingCalendar::fx(),
+    offset: Duration::hours(-1),
+  };
+  // 周五收盘前一小时之后，下一次是下周一收盘前一小时
+  assert_eq!(pre_close.next_after(&utc(11, 4, 20)), Some(utc(11, 7, 21)));
+  let empty = Timer::SessionOpen {
+    calendar: TradingCalendar::new(New_York),
+    offset: Duration::zero(),
+  };
+  assert_eq!(empty.next_after(&utc(11, 4, 20)), None);
+
+  struct Strat {
+    events: Vec<(String, usize)>,
+  }
+  impl Strategy for Strat {
+    type DS = CsvDataSource;
+    type BK = CsvBroker;
+    fn feed(&mut self, _data: &Self::DS) {}
+    fn next(&mut self, index: usize, _data: &Self::DS, broker: &mut Self::BK) {
+      // on_timer 在同一个 bar 的 next 之前执行
+      if index == 2 {
+        assert_eq!(broker.position_size(), 1);
+      }
+    }
+    fn calc_commission(&self, _size: isize, _price: f64) -> f64 {
+      0.
+    }
+    fn on_timer(&mut self, event: &TimerEvent, data: &Self::DS, broker: &mut Self::BK) {
+      assert!(data.timestamp.at(event.index).unwrap() >= event.time);
+      if event.name == "close" && broker.position_size() == 0 {
+        broker.buy(1, data, self);
+      }
+      self.events.push((event.name.clone(), event.index));
+    }
+    fn on_finish(&self, _data: &Self::DS, _broker: &Self::BK) {
+      assert_eq!(
+        self.events,
+        vec![
+          ("close".to_string(), 1),
+          ("open".to_string(), 2),
+          ("close".to_string(), 4),
+          ("open".to_string(), 4)
+        ]
+      );
+    }
+  }
+  let content = "date,open,close
+2022-11-03 19:00,1,1
+2022-11-03 20:30,1,1
+2022-11-03 21:00,1,1
+2022-11-04 12:00,1,1
+2022-11-07 22:00,1,1";
+  let data = CsvDataSource::builder()
+    .time_field("date")
+    .time_type(CsvTimeType::Datetime("%Y-%m-%d %H:%M"))
+    .load_from_string(content)
+    .unwrap();
+  let mut engine = Engine::new(data, Strat { events: Vec::new() }, CsvBroker::new(100.));
+  engine.add_timer("close", pre_close).add_timer(
+    "open",
+    Timer::SessionOpen {
+      calendar: TradingCalendar::fx(),
+      offset: Duration::zero(),
+    },
+  );
+  engine.run();
+}
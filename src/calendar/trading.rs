@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{
+  DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveTime, Offset, TimeZone, Utc, Weekday,
+};
+use chrono_tz::{America::New_York, Tz};
+
+/// 交易日历，按交易所所在时区描述每个交易日的交易时段、节假日和提前收盘。
+///
+/// 交易时段以交易日的收盘时间为准：open 不早于 close 时交易时段从前一天的 open 开始，
+/// 比如外汇的周一从周日 17:00 开盘到周一 17:00 收盘。时间区间为 [open, close)。
+#[derive(Debug, Clone)]
+pub struct TradingCalendar {
+  tz: Tz,
+  /// 周一到周日的交易时段（当地时间），None 表示休市
+  sessions: [Option<(NaiveTime, NaiveTime)>; 7],
+  holidays: BTreeSet<NaiveDate>,
+  early_closes: BTreeMap<NaiveDate, NaiveTime>,
+}
+
+impl TradingCalendar {
+  /// 创建一个没有任何交易时段的日历，需要通过 session 或者 weekdays 添加交易时段。
+  pub fn new(tz: Tz) -> Self {
+    Self {
+      tz,
+      sessions: [None; 7],
+      holidays: BTreeSet::new(),
+      early_closes: BTreeMap::new(),
+    }
+  }
+  /// 纽约证券交易所的常规交易时段，周一到周五 09:30 - 16:00（纽约时间）。
+  /// 不包含节假日和提前收盘，需要通过 holiday 和 early_close 添加。
+  pub fn nyse() -> Self {
+    let time = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+    Self::new(New_York).weekdays(time(9, 30), time(16, 0))
+  }
+  /// 外汇市场，从周日 17:00 连续交易到周五 17:00（纽约时间），每个交易日在 17:00 收盘。
+  pub fn fx() -> Self {
+    let close = NaiveTime::from_hms_opt(17, 0, 0).unwrap();
+    Self::new(New_York).weekdays(close, close)
+  }
+  /// 配置 weekday 这一天的交易时段
+  pub fn session(mut self, weekday: Weekday, open: NaiveTime, close: NaiveTime) -> Self {
+    self.sessions[weekday.num_days_from_monday() as usize] = Some((open, close));
+    self
+  }
+  /// 配置周一到周五的交易时段
+  pub fn weekdays(self, open: NaiveTime, close: NaiveTime) -> Self {
+    [
+      Weekday::Mon,
+      Weekday::Tue,
+      Weekday::Wed,
+      Weekday::Thu,
+      Weekday::Fri,
+    ]
+    .into_iter()
+    .fold(self, |calendar, weekday| {
+      calendar.session(weekday, open, close)
+    })
+  }
+  /// 添加一个节假日，当天（以收盘日为准）休市
+  pub fn holiday(mut self, date: NaiveDate) -> Self {
+    self.holidays.insert(date);
+    self
+  }
+  /// 添加一个提前收盘的交易日，close 为当天的收盘时间
+  pub fn early_close(mut self, date: NaiveDate, close: NaiveTime) -> Self {
+    self.early_closes.insert(date, close);
+    self
+  }
+  #[inline]
+  pub fn timezone(&self) -> Tz {
+    self.tz
+  }
+  /// 交易日 date 的开盘和收盘时间，节假日以及没有交易时段的日期返回 None
+  pub fn session_on(&self, date: NaiveDate) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    if self.holidays.contains(&date) {
+      return None;
+    }
+    let (open, close) = self.sessions[date.weekday().num_days_from_monday() as usize]?;
+    let open_date = if open >= close {
+      date.pred_opt()?
+    } else {
+      date
+    };
+    let close = self.early_closes.get(&date).copied().unwrap_or(close);
+    Some((
+      local_time_to_utc(self.tz, open_date, open),
+      local_time_to_utc(self.tz, date, close),
+    ))
+  }
+  /// dt 所在交易时段的开盘和收盘时间，休市时返回 None
+  pub fn session_at(&self, dt: &DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let date = dt.with_timezone(&self.tz).date_naive();
+    // 跨天的交易时段在前一天开盘，因此 dt 可能属于下一个交易日。
+    [Some(date), date.succ_opt()]
+      .into_iter()
+      .flatten()
+      .filter_map(|date| self.session_on(date))
+      .find(|(open, close)| open <= dt && dt < close)
+  }
+  #[inline]
+  pub fn is_session_open(&self, dt: &DateTime<Utc>) -> bool {
+    self.session_at(dt).is_some()
+  }
+  /// 距离当前交易时段收盘的分钟数（向下取整），休市时返回 None
+  pub fn minutes_to_close(&self, dt: &DateTime<Utc>) -> Option<i64> {
+    self
+      .session_at(dt)
+      .map(|(_, close)| (close - *dt).num_minutes())
+  }
+  /// 当前交易时段已经开盘的分钟数（向下取整），休市时返回 None
+  pub fn minutes_since_open(&self, dt: &DateTime<Utc>) -> Option<i64> {
+    self
+      .session_at(dt)
+      .map(|(open, _)| (*dt - open).num_minutes())
+  }
+}
+
+/// 将 tz 时区的当地时间转换为 UTC 时间，不存在的当地时间按跳变前的偏移量换算，即顺延到夏令时跳变之后，
+/// 重复的当地时间取较早的一个。
+pub(crate) fn local_time_to_utc(tz: Tz, date: NaiveDate, time: NaiveTime) -> DateTime<Utc> {
+  let local = date.and_time(time);
+  match tz.from_local_datetime(&local) {
+    LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => dt.with_timezone(&Utc),
+    LocalResult::None => {
+      let offset = tz
+        .offset_from_utc_datetime(&(local - Duration::days(1)))
+        .fix();
+      Utc.from_utc_datetime(&(local - offset))
+    }
+  }
+}
+
+#[test]
+fn test_trading_calendar() {
+  use crate::{CsvDataSource, CsvTimeType};
+
+  let utc = |m, d, h, min| Utc.with_ymd_and_hms(2022, m, d, h, min, 0).unwrap();
+  let fx = TradingCalendar::fx();
+  // 夏令时 17:00 纽约时间为 21:00 UTC，冬令时为 22:00 UTC
+  assert_eq!(fx.minutes_to_close(&utc(11, 2, 20, 0)), Some(60));
+  assert_eq!(fx.minutes_since_open(&utc(11, 2, 21, 0)), Some(0));
+  assert_eq!(fx.minutes_to_close(&utc(11, 9, 20, 30)), Some(90));
+  // 周五 17:00 收盘，周日 17:00 开盘
+  assert!(fx.is_session_open(&utc(11, 4, 20, 59)));
+  assert!(!fx.is_session_open(&utc(11, 4, 21, 0)));
+  assert!(!fx.is_session_open(&utc(11, 5, 12, 0)));
+  assert!(!fx.is_session_open(&utc(11, 6, 21, 59)));
+  assert_eq!(
+    fx.session_at(&utc(11, 6, 22, 0)),
+    Some((utc(11, 6, 22, 0), utc(11, 7, 22, 0)))
+  );
+
+  let date = |m, d| NaiveDate::from_ymd_opt(2022, m, d).unwrap();
+  let nyse = TradingCalendar::nyse()
+    .holiday(date(11, 24))
+    .early_close(date(11, 25), NaiveTime::from_hms_opt(13, 0, 0).unwrap());
+  assert_eq!(nyse.timezone(), New_York);
+  assert!(!nyse.is_session_open(&utc(11, 23, 14, 29)));
+  assert!(nyse.is_session_open(&utc(11, 23, 14, 30)));
+  assert!(nyse.session_on(date(11, 24)).is_none());
+  assert_eq!(nyse.minutes_to_close(&utc(11, 25, 17, 0)), Some(60));
+  assert!(!nyse.is_session_open(&utc(11, 25, 18, 0)));
+
+  let content = "date,close
+2022-11-25 14:00,1
+2022-11-25 15:00,2
+2022-11-25 17:30,3
+2022-11-25 18:00,4
+2022-11-28 15:00,5";
+  let builder = || {
+    CsvDataSource::builder()
+      .time_field("date")
+      .time_type(CsvTimeType::Datetime("%Y-%m-%d %H:%M"))
+  };
+  let data = builder()
+    .calendar(nyse.clone())
+    .regular_hours(true)
+    .load_from_string(content)
+    .unwrap();
+  assert_eq!(data.close.as_slice(), &[2., 3., 5.]);
+  assert_eq!(data.minutes_to_close(1), Some(30));
+  assert!(data.is_session_open(2));
+
+  let data = builder().calendar(nyse).load_from_string(content).unwrap();
+  assert_eq!(data.len(), 5);
+  assert!(!data.is_session_open(0));
+  assert_eq!(data.minutes_to_close(0), None);
+  let e = builder()
+    .regular_hours(true)
+    .load_from_string(content)
+    .err()
+    .unwrap();
+  assert_eq!(e.to_string(), "regular_hours requires a trading calendar");
+}
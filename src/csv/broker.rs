@@ -1,14 +1,17 @@
 use chrono::{DateTime, Utc};
 
 use crate::{
-  broker::Broker, CsvDataSource, DataLine, Order, OrderStatus, Position, Strategy, Trade,
-  TradeStatus,
+  broker::{Broker, SingleBroker},
+  data::SingleDataSource,
+  CorporateAction, CsvDataSource, Order, OrderStatus, Position, QuoteSide, Strategy, Trade,
 };
 
 pub struct CsvBroker {
   pub(crate) cash: f64,
   pub(crate) position: Position,
   pub(crate) trade: Trade,
+  /// 当前 bar 及之后成交的 (成交时间, 带方向的成交量)，用于判断公司行为生效时哪些持仓已经在除权日之前持有
+  fills: Vec<(DateTime<Utc>, isize)>,
 }
 impl Broker for CsvBroker {
   type DS = CsvDataSource;
@@ -18,6 +21,52 @@ impl Broker for CsvBroker {
   fn cash(&self) -> f64 {
     self.cash
   }
+  #[inline]
+  fn position_value(&self, data: &CsvDataSource) -> f64 {
+    data.calc_position_value(self.position.size)
+  }
+  /// 只有除权日之前已经持有的仓位参与公司行为，除权日当天开盘成交的仓位已经是除权后的价格。
+  /// 拆股按比例调整持仓量和持仓价格，不足一股的部分按除权日的 close 报价折算为现金；
+  /// 分红按持仓量计入现金（空仓支付分红），并计入当前交易的 pnl。
+  fn on_corporate_action(&mut self, action: &CorporateAction, data: &CsvDataSource) {
+    let ex_time = data.timestamp[data.offset];
+    let ineligible: isize = self
+      .fills
+      .iter()
+      .filter(|(t, _)| *t >= ex_time)
+      .map(|(_, size)| size)
+      .sum();
+    let eligible = self.position.size - ineligible;
+    if eligible == 0 {
+      return;
+    }
+    let cash = match *action {
+      CorporateAction::Split(ratio) => {
+        let shares = eligible as f64 * ratio;
+        let cost = self.position.size as f64 * self.position.price;
+        self.position.size = ineligible + shares.trunc() as isize;
+        self.position.price = if self.position.size == 0 {
+          0.
+        } else {
+          cost / self.position.size as f64
+        };
+        self.position.origin_price /= ratio;
+        let side = if shares > 0. {
+          QuoteSide::Bid
+        } else {
+          QuoteSide::Ask
+        };
+        shares.fract() * data.quote_close(side, data.offset).unwrap_or(0.)
+      }
+      CorporateAction::Dividend(amount) => eligible as f64 * amount,
+    };
+    self.cash += cash;
+    self.trade.pnl += cash;
+    self.trade.pnlcomm += cash;
+  }
+}
+
+impl SingleBroker for CsvBroker {
   /// 获取当前仓位持仓量
   #[inline]
   fn position_size(&self) -> isize {
@@ -28,14 +77,9 @@ impl Broker for CsvBroker {
   fn position(&self) -> &Position {
     &self.position
   }
-
-  /// 是否是空仓位，等价于 position() == 0
-  #[inline]
-  fn is_position_empty(&self) -> bool {
-    self.position_size() == 0
-  }
+  /// 建买仓（多仓）
   #[inline]
-  fn buy<S: Strategy<BK = Self, DS = Self::DS>>(
+  fn buy<S: Strategy<BK = Self, DS = CsvDataSource>>(
     &mut self,
     size: isize,
     data: &CsvDataSource,
@@ -43,8 +87,9 @@ impl Broker for CsvBroker {
   ) {
     self.submit_order(Order::buy(size, data.timestamp[data.offset]), data, strat);
   }
+  /// 建卖仓（空仓）
   #[inline]
-  fn sell<S: Strategy<BK = Self, DS = Self::DS>>(
+  fn sell<S: Strategy<BK = Self, DS = CsvDataSource>>(
     &mut self,
     size: isize,
     data: &CsvDataSource,
@@ -61,6 +106,7 @@ impl CsvBroker {
       position: Position::new(),
       // trade: RefCell::new(Trade::new()),
       trade: Trade::new(),
+      fills: Vec::new(),
     }
   }
   #[inline]
@@ -79,10 +125,15 @@ impl CsvBroker {
     // 对于 csv 数据，成交价我们以下一个 bar 的 open 价作为成交价。
     // 对于 http broker 等应该是以实际的交易成交价作为 exe_price。
     // 如果当前已经是最后一个 bar，暂时就还是以 close 价作为成交价。
+    // 有 bid、ask 报价时，买入以 ask 价成交，卖出以 bid 价成交。
+    let side = if order.is_buy() {
+      QuoteSide::Ask
+    } else {
+      QuoteSide::Bid
+    };
     let exe_price = data
-      .open
-      .at(data.offset + 1)
-      .unwrap_or_else(|| data.close.at(data.offset).unwrap());
+      .quote_open(side, data.offset + 1)
+      .unwrap_or_else(|| data.quote_close(side, data.offset).unwrap());
     let exe_time = data
       .timestamp
       .at(data.offset + 1)
@@ -91,6 +142,9 @@ impl CsvBroker {
     // 对于 csv 数据，成交额认为等于下单量。对于 http broker 等应该以实际成交额为准（事实上一个 order 可能会由多个 trade 成交）
     order.exe_size = order.size;
 
+    let now = data.timestamp[data.offset];
+    self.fills.retain(|(t, _)| *t >= now);
+    self.fills.push((exe_time, order.deal_size()));
     self.complete_order(&mut order, strat, exe_time);
     order
   }
@@ -101,73 +155,72 @@ impl CsvBroker {
     strat: &S,
     completed_at: DateTime<Utc>,
   ) {
-    let exe_price = order.exe_price;
-    let exe_size = order.exe_size;
-    let deal_size = if order.is_buy() { exe_size } else { -exe_size };
-    order.cost = (deal_size as f64) * exe_price;
-    self.cash -= order.cost;
-    order.comm = strat.calc_commission(exe_size, exe_price);
-    self.cash -= order.comm;
-    let mut position = &mut self.position;
-
-    let pre_s = position.size;
-    if pre_s == 0 {
-      position.size = deal_size;
-      position.price = exe_price;
-    } else {
-      position.size += deal_size;
-    }
-    let post_s = position.size;
-    if post_s != 0 {
-      if (pre_s > 0 && post_s > 0) || (pre_s < 0 && post_s < 0) {
-        // calcuate average price
-        position.price = ((pre_s as f64) * position.price + (deal_size as f64) * exe_price)
-          / (pre_s + deal_size) as f64;
-      } else {
-        // close and open position
-        position.price = exe_price;
-        position.origin_price = exe_price;
-      }
-    }
+    order.settle(strat.calc_commission(order.exe_size, order.exe_price));
+    self.cash -= order.cost + order.comm;
+    let pre_s = self.position.deal(order);
+    let post_s = self.position.size;
 
     order.status = OrderStatus::Completed(completed_at);
     strat.on_order(order, self);
 
-    let is_uninit = matches!(&self.trade.status, TradeStatus::Uninit);
-
-    if is_uninit && pre_s != 0 {
-      panic!("unexpected");
+    for trade in self.trade.deal(order, pre_s, post_s, completed_at) {
+      strat.on_trade(&trade, self);
     }
+  }
+}
 
-    // println!("{} {} {} {}", order.comm, order.cost, pre_s, post_s);
-    // https://zhuanlan.zhihu.com/p/299630905
-    let cost = -order.cost;
-    let commcost = cost - order.comm;
-    if is_uninit {
-      self.trade.pnl = cost;
-      self.trade.pnlcomm = commcost;
-      self.trade.status = TradeStatus::Open(completed_at);
-      strat.on_trade(&self.trade, self);
-    } else if post_s == 0 {
-      self.trade.pnl += cost;
-      self.trade.pnlcomm += commcost;
-      self.trade.status = TradeStatus::Closed(completed_at);
-      strat.on_trade(&self.trade, self);
-      self.trade.status = TradeStatus::Uninit;
-    } else if pre_s > 0 && post_s < 0 || pre_s < 0 && post_s > 0 {
-      let pre_percent = (pre_s / (post_s - pre_s)).abs() as f64;
-      self.trade.pnl += cost * pre_percent;
-      self.trade.pnlcomm += commcost * pre_percent;
-      self.trade.status = TradeStatus::Closed(completed_at);
-      strat.on_trade(&self.trade, self);
-      let post_percent = (post_s / (post_s - pre_s)).abs() as f64;
-      self.trade.pnl = cost * post_percent;
-      self.trade.pnlcomm = commcost * post_percent;
-      self.trade.status = TradeStatus::Open(completed_at);
-      strat.on_trade(&self.trade, self);
-    } else {
-      self.trade.pnl += cost;
-      self.trade.pnlcomm += commcost;
+#[test]
+fn test_spread_aware_execution() {
+  use crate::{CsvTimeType, DataLine, Engine};
+
+  let builder = || {
+    CsvDataSource::builder()
+      .time_field("date")
+      .time_type(CsvTimeType::Date("%Y-%m-%d"))
+  };
+  let data = builder()
+    .load_from_string(
+      "date,open,close,bid open,bid close,ask open,ask close
+2022-01-03,1,1,0.9,0.9,1.1,1.1
+2022-01-04,2,2,1.9,1.8,2.1,2.2",
+    )
+    .unwrap();
+  assert!(data.has_quotes());
+  assert_eq!(data.bid.close.at(1), Some(1.8));
+  assert_eq!(data.quote_open(QuoteSide::Ask, 1), Some(2.1));
+  assert_eq!(data.quote_close(QuoteSide::Bid, 1), Some(1.8));
+
+  // spread 列按中间价换算 bid、ask
+  let content = "date,open,close,spread
+2022-01-03,1,1,0.2
+2022-01-04,2,2,0.2
+2022-01-05,3,3,0.4";
+  let data = builder().load_from_string(content).unwrap();
+  assert!(data.has_quotes() && data.bid.is_empty());
+  assert_eq!(data.quote_open(QuoteSide::Bid, 1), Some(1.9));
+
+  struct Strat;
+  impl Strategy for Strat {
+    type DS = CsvDataSource;
+    type BK = CsvBroker;
+    fn feed(&mut self, _data: &Self::DS) {}
+    fn next(&mut self, index: usize, data: &Self::DS, broker: &mut Self::BK) {
+      match index {
+        0 => broker.buy(10, data, self),
+        1 => broker.sell(20, data, self),
+        _ => {}
+      }
+    }
+    fn calc_commission(&self, _size: isize, _price: f64) -> f64 {
+      0.
+    }
+    fn on_finish(&self, data: &Self::DS, broker: &Self::BK) {
+      // 以 ask 价 2.1 买入 10，以 bid 价 2.8 卖出 20，剩余空仓 10 按 ask 价 3.2 计算价值
+      assert_eq!(broker.position_size(), -10);
+      assert!((broker.cash() - (100. - 21. + 56.)).abs() < 1e-9);
+      assert!((broker.value(data) - (broker.cash() - 32.)).abs() < 1e-9);
     }
   }
+  let mut engine = Engine::new(data, Strat, CsvBroker::new(100.));
+  engine.run();
 }
@@ -1,13 +1,25 @@
 use std::{
+  fs,
   io::{self},
   path::Path,
+  rc::Rc,
 };
 
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+
 use super::{
+  corporate_action::{back_adjust, CorporateActionMode, CorporateActions},
   source::CsvDataSource,
-  util::{load_csv_from_file, load_csv_from_lines, load_csv_from_string, new_io_err_str},
+  util::{
+    load_csv_from_file, load_csv_from_lines, load_csv_from_string, new_io_err_str, retain_rows,
+    DataVecs,
+  },
+  validate::{CsvFixPolicy, CsvValidationReport},
 };
+use crate::TradingCalendar;
 
+#[derive(Debug)]
 pub enum CsvTimeType {
   Unknown,
   Second,
@@ -16,10 +28,57 @@ pub enum CsvTimeType {
   Datetime(&'static str),
 }
 
+/// 时间重复的行的处理策略。
+#[derive(Debug)]
+pub enum CsvDuplicateTime {
+  /// 保留全部重复的行
+  Keep,
+  /// 只保留文件中最先出现的一行
+  First,
+  /// 只保留文件中最后出现的一行
+  Last,
+  /// 返回错误
+  Error,
+}
+
+/// 本地时间落在夏令时回拨区间（同一本地时间对应两个 UTC 时间）时的处理策略。
+#[derive(Debug)]
+pub enum CsvAmbiguousTime {
+  /// 取较早的 UTC 时间（即回拨前的偏移量）
+  Earliest,
+  /// 取较晚的 UTC 时间（即回拨后的偏移量）
+  Latest,
+  /// 返回错误
+  Error,
+}
+
+/// 本地时间落在夏令时跳变区间（该本地时间不存在）时的处理策略。
+#[derive(Debug)]
+pub enum CsvNonexistentTime {
+  /// 按跳变前的偏移量换算，等价于向后顺延跳变的时长（如 02:30 -> 03:30）
+  Shift,
+  /// 返回错误
+  Error,
+}
+
 macro_rules! gen_builder {
     ($struct_name: ident, $($name: ident : $default_value: literal), +) => {
       pub struct $struct_name {
         pub(super) time_type: CsvTimeType,
+        pub(super) timezone: Tz,
+        pub(super) ambiguous_time: CsvAmbiguousTime,
+        pub(super) nonexistent_time: CsvNonexistentTime,
+        pub(super) sort_rows: bool,
+        pub(super) duplicate_time: CsvDuplicateTime,
+        pub(super) fix_policy: Option<CsvFixPolicy>,
+        pub(super) expected_interval: Option<Duration>,
+        pub(super) max_gap: Option<Duration>,
+        pub(super) outlier_threshold: Option<f64>,
+        pub(super) between: Option<(DateTime<Utc>, DateTime<Utc>)>,
+        pub(super) extra_fields: Vec<String>,
+        pub(super) corporate_actions: Option<(CorporateActions, CorporateActionMode)>,
+        pub(super) calendar: Option<TradingCalendar>,
+        pub(super) regular_hours: bool,
         $ (
           pub(super) $name: String,
         )*
@@ -28,6 +87,20 @@ macro_rules! gen_builder {
         pub fn new() -> Self {
           Self {
             time_type: CsvTimeType::Unknown,
+            timezone: Tz::UTC,
+            ambiguous_time: CsvAmbiguousTime::Error,
+            nonexistent_time: CsvNonexistentTime::Error,
+            sort_rows: false,
+            duplicate_time: CsvDuplicateTime::Keep,
+            fix_policy: None,
+            expected_interval: None,
+            max_gap: None,
+            outlier_threshold: None,
+            between: None,
+            extra_fields: Vec::new(),
+            corporate_actions: None,
+            calendar: None,
+            regular_hours: false,
             $ (
               $name: $default_value.to_string(),
             )*
@@ -58,7 +131,16 @@ gen_builder!(
   low_field: "low",
   volume_field: "volume",
   openintrest_field: "open intrest",
-  adjustclose_field: "adj close"
+  adjustclose_field: "adj close",
+  bid_open_field: "bid open",
+  bid_close_field: "bid close",
+  bid_high_field: "bid high",
+  bid_low_field: "bid low",
+  ask_open_field: "ask open",
+  ask_close_field: "ask close",
+  ask_high_field: "ask high",
+  ask_low_field: "ask low",
+  spread_field: "spread"
 );
 
 impl CsvDataSourceBuilder {
@@ -71,32 +153,181 @@ impl CsvDataSourceBuilder {
     self.time_type = time_type;
     self
   }
-  fn check_config(&self) -> io::Result<()> {
+  /// 指定 Date 和 Datetime 类型的 time 列所在的时区，默认为 UTC。
+  /// 解析出的本地时间会按该时区（包含夏令时规则）换算为 UTC 时间。
+  /// Second 和 Millsecond 类型本身就是 UTC 时间戳，不受该配置影响。
+  pub fn timezone(mut self, tz: Tz) -> Self {
+    self.timezone = tz;
+    self
+  }
+  /// 指定本地时间存在歧义（夏令时回拨）时的处理策略，默认为 Error。
+  pub fn ambiguous_time(mut self, policy: CsvAmbiguousTime) -> Self {
+    self.ambiguous_time = policy;
+    self
+  }
+  /// 指定本地时间不存在（夏令时跳变）时的处理策略，默认为 Error。
+  pub fn nonexistent_time(mut self, policy: CsvNonexistentTime) -> Self {
+    self.nonexistent_time = policy;
+    self
+  }
+  /// 是否对乱序的数据按时间重新排序，默认为 false。
+  /// 按时间降序排列的数据（比如最新的数据在文件最前面）总是会被自动反转为升序，不需要开启该配置；
+  /// 既不是升序也不是降序的数据，在没有开启该配置时保持文件中的顺序。
+  pub fn sort_rows(mut self, sort: bool) -> Self {
+    self.sort_rows = sort;
+    self
+  }
+  /// 指定时间重复的行的处理策略，默认为 Keep。
+  pub fn duplicate_time(mut self, policy: CsvDuplicateTime) -> Self {
+    self.duplicate_time = policy;
+    self
+  }
+  /// 只加载时间在 [from, to) 区间内的数据。
+  pub fn between(mut self, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+    self.between = Some((from, to));
+    self
+  }
+  /// 开启加载后的数据校验，并指定发现问题时的处理策略。默认不校验。
+  /// 校验会检查非法数值（NaN、价格小于等于 0 等）、OHLC 不一致（high 小于 low 等），
+  /// 以及配置了 expected_interval 和 outlier_threshold 时的缺失 bar 和异常跳变，
+  /// 校验报告可以通过 CsvDataSource::report 获取。
+  pub fn validate(mut self, policy: CsvFixPolicy) -> Self {
+    self.fix_policy = Some(policy);
+    self
+  }
+  /// 指定数据的预期周期（比如小时线为 1 小时），用于检测缺失的 bar。默认不检测。
+  pub fn expected_interval(mut self, interval: Duration) -> Self {
+    self.expected_interval = Some(interval);
+    self
+  }
+  /// 指定相邻 bar 的最大间隔，超过该间隔的缺失视为休市（比如周末），不作为缺失的 bar。默认不限制。
+  pub fn max_gap(mut self, gap: Duration) -> Self {
+    self.max_gap = Some(gap);
+    self
+  }
+  /// 指定 close 价相对上一个 bar 的变化率阈值（比如 0.2 代表 20%），超过阈值视为异常数据。默认不检测。
+  pub fn outlier_threshold(mut self, threshold: f64) -> Self {
+    self.outlier_threshold = Some(threshold);
+    self
+  }
+  /// 额外加载一个数值列，忽略大小写，加载后可以通过 CsvDataSource::extra 获取。
+  /// 文件中不存在该列时对应的 CsvDataLine 为空。
+  pub fn extra_field(mut self, field: &str) -> Self {
+    let field = field.to_lowercase();
+    if !self.extra_fields.contains(&field) {
+      self.extra_fields.push(field);
+    }
+    self
+  }
+  /// 指定公司行为（拆股和分红）以及处理方式，参见 CorporateActionMode。
+  pub fn corporate_actions(mut self, actions: CorporateActions, mode: CorporateActionMode) -> Self {
+    self.corporate_actions = Some((actions, mode));
+    self
+  }
+  /// 指定交易日历，加载后可以通过 CsvDataSource::is_session_open 等方法判断 bar 是否在交易时段内。
+  pub fn calendar(mut self, calendar: TradingCalendar) -> Self {
+    self.calendar = Some(calendar);
+    self
+  }
+  /// 只保留交易日历的交易时段内的 bar，需要同时指定 calendar，默认为 false
+  pub fn regular_hours(mut self, regular_hours: bool) -> Self {
+    self.regular_hours = regular_hours;
+    self
+  }
+  /// time 列以及按 data_vecs 顺序排列的各个数据列的字段名
+  pub(super) fn fields(&self) -> Vec<&String> {
+    let mut fields = vec![
+      &self.time_field,
+      &self.open_field,
+      &self.close_field,
+      &self.high_field,
+      &self.low_field,
+      &self.volume_field,
+      &self.openintrest_field,
+      &self.adjustclose_field,
+      &self.bid_open_field,
+      &self.bid_close_field,
+      &self.bid_high_field,
+      &self.bid_low_field,
+      &self.ask_open_field,
+      &self.ask_close_field,
+      &self.ask_high_field,
+      &self.ask_low_field,
+      &self.spread_field,
+    ];
+    fields.extend(self.extra_fields.iter());
+    fields
+  }
+  pub(super) fn check_config(&self) -> io::Result<()> {
     if self.time_field.is_empty() {
       return Err(new_io_err_str("time_field config missing"));
     }
     if matches!(self.time_type, CsvTimeType::Unknown) {
       return Err(new_io_err_str("time_type config missing"));
     }
+    self.check_interval()
+  }
+  /// 不需要解析 time 列的数据源（比如内存中的 Bar）只检查校验相关的配置
+  pub(super) fn check_interval(&self) -> io::Result<()> {
+    if matches!(self.expected_interval, Some(interval) if interval <= Duration::zero()) {
+      return Err(new_io_err_str(
+        "expected_interval must be greater than zero",
+      ));
+    }
     Ok(())
   }
+  /// 按配置处理公司行为，并构建数据源。所有的加载方式最终都通过该方法构建数据源。
+  pub(super) fn build(
+    &self,
+    timestamp_vec: Vec<DateTime<Utc>>,
+    data_vecs: DataVecs,
+    report: Option<CsvValidationReport>,
+  ) -> io::Result<CsvDataSource> {
+    let (timestamp_vec, mut data_vecs) = match (&self.calendar, self.regular_hours) {
+      (Some(calendar), true) => {
+        retain_rows(timestamp_vec, data_vecs, |t| calendar.is_session_open(t))
+      }
+      (None, true) => return Err(new_io_err_str("regular_hours requires a trading calendar")),
+      (_, false) => (timestamp_vec, data_vecs),
+    };
+    let actions = match &self.corporate_actions {
+      Some((actions, mode)) => {
+        let actions = actions.locate(&timestamp_vec);
+        match mode {
+          CorporateActionMode::BackAdjust => {
+            back_adjust(&mut data_vecs, &actions)?;
+            Vec::new()
+          }
+          CorporateActionMode::Broker => actions,
+        }
+      }
+      None => Vec::new(),
+    };
+    let mut data = CsvDataSource::inner_new(timestamp_vec, data_vecs, &self.extra_fields, report);
+    data.actions = actions;
+    data.calendar = self.calendar.clone().map(Rc::new);
+    Ok(data)
+  }
   /// 加载全部数据到内存中。
   pub fn load_from_file(self, file: &Path) -> io::Result<CsvDataSource> {
     self.check_config()?;
-    let (timestamp_vec, data_vecs) = load_csv_from_file(file, &self)?;
-    Ok(CsvDataSource::inner_new(timestamp_vec, data_vecs))
+    let modified = fs::metadata(file)?.modified()?;
+    let (timestamp_vec, data_vecs, report) = load_csv_from_file(file, &self)?;
+    let mut data = self.build(timestamp_vec, data_vecs, report)?;
+    data.source = Some((file.to_path_buf(), modified));
+    Ok(data)
   }
   pub fn load_from_string(self, content: &str) -> io::Result<CsvDataSource> {
     self.check_config()?;
-    let (timestamp_vec, data_vecs) = load_csv_from_string(content, &self)?;
-    Ok(CsvDataSource::inner_new(timestamp_vec, data_vecs))
+    let (timestamp_vec, data_vecs, report) = load_csv_from_string(content, &self)?;
+    self.build(timestamp_vec, data_vecs, report)
   }
   pub fn load_from_lines<'a, T: Iterator<Item = &'a str>>(
     self,
     lines: T,
   ) -> io::Result<CsvDataSource> {
     self.check_config()?;
-    let (timestamp_vec, data_vecs) = load_csv_from_lines(lines, &self)?;
-    Ok(CsvDataSource::inner_new(timestamp_vec, data_vecs))
+    let (timestamp_vec, data_vecs, report) = load_csv_from_lines(lines, &self)?;
+    self.build(timestamp_vec, data_vecs, report)
   }
 }
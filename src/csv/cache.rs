@@ -0,0 +1,396 @@
+use std::{
+  fs,
+  io::{self, Write},
+  path::{Path, PathBuf},
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{TimeZone, Utc};
+
+use super::{
+  builder::CsvDataSourceBuilder,
+  source::CsvDataSource,
+  util::{new_io_err, new_io_err_str, DataVecs, COLUMN_COUNT},
+};
+
+const CACHE_MAGIC: &[u8; 8] = b"RTCACHE\0";
+/// 缓存格式的版本，格式或者列的定义发生变化时需要增加版本号
+const CACHE_VERSION: u32 = 3;
+
+/// 缓存文件的头部。缓存文件的格式为（所有数值均为 little-endian）：
+/// ```text
+/// magic: [u8; 8], version: u32, column_mask: u32, rows: u64, loader_hash: u64,
+/// source_mtime_secs: u64, source_mtime_nanos: u32, source_len: u32, source: [u8; source_len],
+/// extra_count: u32, 每个额外数值列的 name_len: u32, name: [u8; name_len],
+/// 补齐到 8 字节对齐的 padding,
+/// timestamp: [i64; rows]（UTC 纳秒时间戳），
+/// 按 data_vecs 顺序排列的、column_mask 中存在的列：[f64; rows]，
+/// 额外的数值列：[f64; rows]
+/// ```
+/// 所有列都是 8 字节对齐的连续数组，可以直接 mmap 后按 &[f64] 访问。
+struct CacheHeader {
+  column_mask: u32,
+  rows: usize,
+  /// 加载数据时影响解析结果的配置的哈希值，参见 CsvDataSourceBuilder::loader_hash，0 表示未知
+  loader_hash: u64,
+  /// 原始 csv 文件及其加载时的修改时间
+  source: Option<(PathBuf, SystemTime)>,
+  extra_names: Vec<String>,
+}
+
+#[inline]
+fn read_array<const N: usize>(bytes: &[u8], pos: &mut usize) -> io::Result<[u8; N]> {
+  let v = bytes
+    .get(*pos..*pos + N)
+    .ok_or_else(|| new_io_err_str("cache file is truncated"))?;
+  *pos += N;
+  Ok(v.try_into().unwrap())
+}
+
+#[inline]
+fn read_str(bytes: &[u8], pos: &mut usize) -> io::Result<String> {
+  let len = u32::from_le_bytes(read_array(bytes, pos)?) as usize;
+  let v = bytes
+    .get(*pos..*pos + len)
+    .ok_or_else(|| new_io_err_str("cache file is truncated"))?;
+  *pos += len;
+  Ok(String::from_utf8_lossy(v).into_owned())
+}
+
+#[inline]
+fn write_str(out: &mut Vec<u8>, v: &str) {
+  out.extend_from_slice(&(v.len() as u32).to_le_bytes());
+  out.extend_from_slice(v.as_bytes());
+}
+
+#[inline]
+fn padding(len: usize) -> usize {
+  (8 - len % 8) % 8
+}
+
+impl CacheHeader {
+  fn write(&self, out: &mut Vec<u8>) {
+    out.extend_from_slice(CACHE_MAGIC);
+    out.extend_from_slice(&CACHE_VERSION.to_le_bytes());
+    out.extend_from_slice(&self.column_mask.to_le_bytes());
+    out.extend_from_slice(&(self.rows as u64).to_le_bytes());
+    out.extend_from_slice(&self.loader_hash.to_le_bytes());
+    let (path, mtime) = match &self.source {
+      Some((path, mtime)) => {
+        let mtime = mtime.duration_since(UNIX_EPOCH).unwrap_or_default();
+        (path.to_string_lossy().into_owned(), mtime)
+      }
+      None => (String::new(), Duration::ZERO),
+    };
+    out.extend_from_slice(&mtime.as_secs().to_le_bytes());
+    out.extend_from_slice(&mtime.subsec_nanos().to_le_bytes());
+    write_str(out, &path);
+    out.extend_from_slice(&(self.extra_names.len() as u32).to_le_bytes());
+    for name in &self.extra_names {
+      write_str(out, name);
+    }
+    out.resize(out.len() + padding(out.len()), 0);
+  }
+
+  /// 解析头部，返回头部以及列数据的开始位置
+  fn read(bytes: &[u8]) -> io::Result<(Self, usize)> {
+    let mut pos = 0;
+    if &read_array::<8>(bytes, &mut pos)? != CACHE_MAGIC {
+      return Err(new_io_err_str("not a rushtrader cache file"));
+    }
+    let version = u32::from_le_bytes(read_array(bytes, &mut pos)?);
+    if version != CACHE_VERSION {
+      return Err(new_io_err(format!(
+        "cache version {} does not match the current version {}",
+        version, CACHE_VERSION
+      )));
+    }
+    let column_mask = u32::from_le_bytes(read_array(bytes, &mut pos)?);
+    let rows = u64::from_le_bytes(read_array(bytes, &mut pos)?) as usize;
+    let loader_hash = u64::from_le_bytes(read_array(bytes, &mut pos)?);
+    let secs = u64::from_le_bytes(read_array(bytes, &mut pos)?);
+    let nanos = u32::from_le_bytes(read_array(bytes, &mut pos)?);
+    let path = read_str(bytes, &mut pos)?;
+    let extra_count = u32::from_le_bytes(read_array(bytes, &mut pos)?);
+    let extra_names = (0..extra_count)
+      .map(|_| read_str(bytes, &mut pos))
+      .collect::<io::Result<Vec<_>>>()?;
+    pos += padding(pos);
+    let source = if path.is_empty() {
+      None
+    } else {
+      Some((PathBuf::from(path), UNIX_EPOCH + Duration::new(secs, nanos)))
+    };
+    Ok((
+      Self {
+        column_mask,
+        rows,
+        loader_hash,
+        source,
+        extra_names,
+      },
+      pos,
+    ))
+  }
+
+  /// 文件中保存的数据列的数量（不包括 timestamp）
+  #[inline]
+  fn present_columns(&self) -> usize {
+    self.column_mask.count_ones() as usize + self.extra_names.len()
+  }
+
+  /// 原始 csv 文件在缓存之后被修改过时返回错误。原始文件已经不存在时认为缓存仍然有效。
+  fn check_source(&self) -> io::Result<()> {
+    if let Some((path, mtime)) = &self.source {
+      if let Ok(meta) = fs::metadata(path) {
+        if meta.modified()? != *mtime {
+          return Err(new_io_err(format!(
+            "cache is stale, source file {} has been modified",
+            path.display()
+          )));
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+impl CsvDataSource {
+  /// 将数据保存为二进制的列式缓存文件，之后可以通过 load_cache 快速加载。
+  /// 通过 CsvDataSourceBuilder::load_from_file 加载的数据会同时记录原始文件及其修改时间，
+  /// 用于在 load_cache 时检查缓存是否过期。校验报告和注册的更大周期不会被缓存。
+  pub fn save_cache(&self, path: &Path) -> io::Result<()> {
+    self.write_cache(path, 0)
+  }
+
+  fn write_cache(&self, path: &Path, loader_hash: u64) -> io::Result<()> {
+    let columns = self.columns();
+    let len = self.len();
+    let column_mask = columns[..COLUMN_COUNT]
+      .iter()
+      .enumerate()
+      .filter(|(_, column)| column.len() == len && len > 0)
+      .fold(0u32, |mask, (i, _)| mask | 1 << i);
+    let header = CacheHeader {
+      column_mask,
+      rows: len,
+      loader_hash,
+      source: self.source.clone(),
+      extra_names: self.extra_names(),
+    };
+    let present = header.present_columns();
+    let mut out = Vec::with_capacity(64 + (present + 1) * len * 8);
+    header.write(&mut out);
+    for t in self.timestamp.as_slice() {
+      let nanos = t.timestamp() * 1_000_000_000 + t.timestamp_subsec_nanos() as i64;
+      out.extend_from_slice(&nanos.to_le_bytes());
+    }
+    for (i, column) in columns.iter().enumerate() {
+      if i >= COLUMN_COUNT || column_mask & 1 << i != 0 {
+        // 额外的数值列在文件中不存在时为空，使用 NaN 补齐
+        let values = column.as_slice();
+        for k in 0..len {
+          out.extend_from_slice(&values.get(k).copied().unwrap_or(f64::NAN).to_le_bytes());
+        }
+      }
+    }
+    let mut file = fs::File::create(path)?;
+    file.write_all(&out)?;
+    file.sync_all()
+  }
+
+  /// 加载 save_cache 保存的缓存文件。缓存的版本不一致或者原始 csv 文件已经被修改时返回错误。
+  pub fn load_cache(path: &Path) -> io::Result<CsvDataSource> {
+    Self::read_cache(path).map(|(data, _)| data)
+  }
+
+  /// 加载缓存文件，同时返回缓存的 loader_hash
+  fn read_cache(path: &Path) -> io::Result<(CsvDataSource, u64)> {
+    let bytes = fs::read(path)?;
+    let (header, mut pos) = CacheHeader::read(&bytes)?;
+    header.check_source()?;
+    let rows = header.rows;
+    let expected = rows
+      .saturating_mul(8 * (1 + header.present_columns()))
+      .saturating_add(pos);
+    if bytes.len() != expected {
+      return Err(new_io_err(format!(
+        "cache file size {} does not match the expected size {}",
+        bytes.len(),
+        expected
+      )));
+    }
+    let mut next_word = || {
+      let v: [u8; 8] = bytes[pos..pos + 8].try_into().unwrap();
+      pos += 8;
+      v
+    };
+    let timestamp_vec = (0..rows)
+      .map(|_| {
+        let nanos = i64::from_le_bytes(next_word());
+        let secs = nanos.div_euclid(1_000_000_000);
+        let nsecs = nanos.rem_euclid(1_000_000_000) as u32;
+        Utc.timestamp_opt(secs, nsecs).unwrap()
+      })
+      .collect();
+    let mut data_vecs: DataVecs = vec![Vec::new(); COLUMN_COUNT + header.extra_names.len()];
+    for (i, vec) in data_vecs.iter_mut().enumerate() {
+      if i >= COLUMN_COUNT || header.column_mask & 1 << i != 0 {
+        *vec = (0..rows).map(|_| f64::from_le_bytes(next_word())).collect();
+      }
+    }
+    let mut data = CsvDataSource::inner_new(timestamp_vec, data_vecs, &header.extra_names, None);
+    data.source = header.source;
+    Ok((data, header.loader_hash))
+  }
+}
+
+impl CsvDataSourceBuilder {
+  /// 影响解析结果的配置（字段映射、时间解析、排序去重、截取和校验）的哈希值，
+  /// 公司行为和交易日历在加载缓存之后处理，不包括在内。
+  fn loader_hash(&self) -> u64 {
+    let config = format!(
+      "{:?}|{:?}|{}|{:?}|{:?}|{}|{:?}|{:?}|{:?}|{:?}|{:?}|{:?}",
+      self.fields(),
+      self.time_type,
+      self.timezone.name(),
+      self.ambiguous_time,
+      self.nonexistent_time,
+      self.sort_rows,
+      self.duplicate_time,
+      self.fix_policy,
+      self.expected_interval,
+      self.max_gap,
+      self.outlier_threshold,
+      self.between,
+    );
+    // FNV-1a，保证不同的 Rust 版本和进程之间结果一致
+    config
+      .bytes()
+      .fold(0xcbf29ce484222325u64, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+      })
+      .max(1)
+  }
+  /// 优先从缓存文件加载数据，缓存不存在、版本不一致、已经过期或者加载配置发生变化时，
+  /// 从 csv 文件加载并重新生成缓存。
+  /// 缓存的是没有处理公司行为和交易时段的原始数据，公司行为和交易日历在加载缓存之后按当前的配置处理。
+  pub fn load_from_file_cached(mut self, file: &Path, cache: &Path) -> io::Result<CsvDataSource> {
+    let corporate_actions = self.corporate_actions.take();
+    let calendar = self.calendar.take();
+    let regular_hours = std::mem::take(&mut self.regular_hours);
+    let loader_hash = self.loader_hash();
+    let data = match CsvDataSource::read_cache(cache) {
+      Ok((data, hash))
+        if hash == loader_hash && matches!(&data.source, Some((source, _)) if source == file) =>
+      {
+        data
+      }
+      _ => {
+        let data = self.load_from_file(file)?;
+        data.write_cache(cache, loader_hash)?;
+        data
+      }
+    };
+    if corporate_actions.is_none() && calendar.is_none() && !regular_hours {
+      return Ok(data);
+    }
+    let builder = Self {
+      corporate_actions,
+      calendar,
+      regular_hours,
+      extra_fields: data.extra_names(),
+      ..CsvDataSourceBuilder::new()
+    };
+    let columns = data
+      .columns()
+      .iter()
+      .map(|c| c.as_slice().to_vec())
+      .collect();
+    let mut adjusted = builder.build(data.timestamp.as_slice().to_vec(), columns, data.report)?;
+    adjusted.source = data.source;
+    Ok(adjusted)
+  }
+}
+
+#[test]
+fn test_cache_roundtrip() {
+  use crate::{CsvTimeType, DataLine};
+  use chrono_tz::America::New_York;
+
+  let dir = std::env::temp_dir().join(format!("rushtrader-cache-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  let csv = dir.join("data.csv");
+  let cache = dir.join("data.cache");
+  fs::write(
+    &csv,
+    "date,open,close,spread,vwap\n2022-01-03 10:00:00.5,1,1.5,0.1,1.2\n2022-01-04 10:00:00,2,2.5,0.2,2.2",
+  )
+  .unwrap();
+  let builder = || {
+    CsvDataSource::builder()
+      .time_field("date")
+      .time_type(CsvTimeType::Datetime("%Y-%m-%d %H:%M:%S%.f"))
+      .extra_field("VWAP")
+  };
+
+  let data = builder().load_from_file_cached(&csv, &cache).unwrap();
+  let cached = CsvDataSource::load_cache(&cache).unwrap();
+  assert_eq!(cached.timestamp.as_slice(), data.timestamp.as_slice());
+  assert_eq!(cached.close.as_slice(), &[1.5, 2.5]);
+  assert_eq!(cached.spread.at(1), Some(0.2));
+  assert!(cached.high.is_empty());
+  assert_eq!(cached.extra("vwap").unwrap().as_slice(), &[1.2, 2.2]);
+
+  // 截取的数据源没有原始文件，缓存总是有效的
+  let sliced = data.slice_rows(1..2);
+  sliced.save_cache(&cache).unwrap();
+  assert_eq!(CsvDataSource::load_cache(&cache).unwrap().len(), 1);
+
+  data.save_cache(&cache).unwrap();
+  let mut bytes = fs::read(&cache).unwrap();
+  // 原始文件被修改后缓存过期
+  fs::File::options()
+    .write(true)
+    .open(&csv)
+    .unwrap()
+    .set_modified(SystemTime::now() + Duration::from_secs(10))
+    .unwrap();
+  let e = CsvDataSource::load_cache(&cache).err().unwrap();
+  assert!(e.to_string().starts_with("cache is stale"));
+  assert_eq!(
+    builder()
+      .load_from_file_cached(&csv, &cache)
+      .unwrap()
+      .close
+      .at(0),
+    Some(1.5)
+  );
+  assert!(CsvDataSource::load_cache(&cache).is_ok());
+
+  // 加载配置发生变化时重新解析 csv 文件
+  let shifted = builder()
+    .timezone(New_York)
+    .load_from_file_cached(&csv, &cache)
+    .unwrap();
+  assert_eq!(
+    shifted.timestamp[0],
+    data.timestamp[0] + chrono::Duration::hours(5)
+  );
+  let between = builder()
+    .between(
+      data.timestamp[1],
+      data.timestamp[1] + chrono::Duration::days(1),
+    )
+    .load_from_file_cached(&csv, &cache)
+    .unwrap();
+  assert_eq!(between.close.as_slice(), &[2.5]);
+  let reloaded = builder().load_from_file_cached(&csv, &cache).unwrap();
+  assert_eq!(reloaded.timestamp.as_slice(), data.timestamp.as_slice());
+
+  bytes[8] = 0;
+  fs::write(&cache, &bytes).unwrap();
+  let e = CsvDataSource::load_cache(&cache).err().unwrap();
+  assert!(e.to_string().starts_with("cache version 0"));
+  fs::remove_dir_all(&dir).unwrap();
+}
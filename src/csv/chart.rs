@@ -0,0 +1,296 @@
+use crate::DataLineFeed;
+
+use super::{
+  source::CsvDataSource,
+  timeframe::ProjectedDataLine,
+  util::{DataVecs, COLUMN_COUNT},
+};
+
+/// Renko 砖块的大小。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenkoBox {
+  /// 固定的价格大小
+  Fixed(f64),
+  /// 以 N 周期的 ATR 作为砖块大小，ATR 在第 N 个 bar 确定后保持不变，砖块从该 bar 开始计算
+  Atr(usize),
+}
+
+/// 由 OHLC 数据转换得到的图表类型。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartType {
+  /// 平均 K 线，和源数据一一对应
+  HeikinAshi,
+  /// 基于 close 的 Renko 砖块，同方向移动一个砖块或者反方向移动两个砖块时生成新的砖块
+  Renko(RenkoBox),
+  /// 价格区间（high - low）达到指定大小时生成一个 bar，bar 内的价格路径按 open、low、high、close
+  /// （阴线为 open、high、low、close）的顺序近似
+  Range(f64),
+}
+
+/// 由 CsvDataSource::to_chart 转换得到的图表数据。
+/// 转换后的每个 bar 都以其走完时所在的源数据 bar 的时间作为时间戳（Renko 的一个源数据 bar 可能生成多个砖块，
+/// 因此时间戳可能重复），基于图表数据计算的指标可以通过 project 投影到源数据的下标上，不会引入未来数据。
+/// Renko 和 range bar 最后一个未走完的 bar 不会被包含。
+pub struct CsvChart {
+  chart: ChartType,
+  /// 转换后的数据源
+  pub data: CsvDataSource,
+  /// 每个转换后的 bar 走完时所在的源数据 bar 的下标，升序排列
+  source_index: Vec<usize>,
+  /// 源数据的 bar 数量
+  source_len: usize,
+}
+
+impl CsvChart {
+  #[inline]
+  pub fn chart(&self) -> ChartType {
+    self.chart
+  }
+  /// 转换后第 index 个 bar 走完时所在的源数据 bar 的下标
+  #[inline]
+  pub fn source_index(&self, index: usize) -> Option<usize> {
+    self.source_index.get(index).copied()
+  }
+  /// 源数据第 index 个 bar 走完时，最近一个已经走完的转换后的 bar 的下标。
+  pub fn index_at(&self, index: usize) -> Option<usize> {
+    if index >= self.source_len {
+      return None;
+    }
+    self
+      .source_index
+      .partition_point(|i| *i <= index)
+      .checked_sub(1)
+  }
+  /// 将转换后的数据（比如基于 self.data 计算的指标）投影到源数据的下标上。
+  pub fn project<D: DataLineFeed + ?Sized>(&self, line: &D) -> ProjectedDataLine {
+    assert_eq!(line.inner().0.len(), self.data.len());
+    ProjectedDataLine::new(line, self.source_len, |i| self.index_at(i))
+  }
+}
+
+/// 转换过程中生成的 bar 及其走完时所在的源数据 bar 的下标，按 open, close, high, low, volume 的顺序写入 data_vecs
+struct ChartBars {
+  source_index: Vec<usize>,
+  data_vecs: DataVecs,
+}
+
+impl ChartBars {
+  fn new() -> Self {
+    Self {
+      source_index: Vec::new(),
+      data_vecs: vec![Vec::new(); COLUMN_COUNT],
+    }
+  }
+  fn push(&mut self, source_index: usize, open: f64, close: f64, high: f64, low: f64, volume: f64) {
+    self.source_index.push(source_index);
+    for (i, v) in [open, close, high, low, volume].into_iter().enumerate() {
+      self.data_vecs[i].push(v);
+    }
+  }
+}
+
+impl CsvDataSource {
+  /// 将 OHLC 数据转换为 Heikin-Ashi、Renko 或者 range bar，参见 ChartType 和 CsvChart。
+  /// 源数据缺少需要的列或者参数无效时 panic。
+  pub fn to_chart(&self, chart: ChartType) -> CsvChart {
+    let bars = match chart {
+      ChartType::HeikinAshi => self.heikin_ashi(),
+      ChartType::Renko(size) => self.renko(size),
+      ChartType::Range(range) => self.range_bars(range),
+    };
+    let timestamp = bars
+      .source_index
+      .iter()
+      .map(|&i| self.timestamp[i])
+      .collect();
+    CsvChart {
+      chart,
+      data: CsvDataSource::inner_new(timestamp, bars.data_vecs, &[], None),
+      source_index: bars.source_index,
+      source_len: self.len(),
+    }
+  }
+  fn ohlc(&self) -> [&[f64]; 4] {
+    let ohlc = [&self.open, &self.high, &self.low, &self.close].map(|line| line.as_slice());
+    if ohlc.iter().any(|line| line.is_empty()) {
+      panic!("chart transformations require open, high, low and close fields");
+    }
+    ohlc
+  }
+  #[inline]
+  fn volume_at(&self, index: usize) -> f64 {
+    self.volume.as_slice().get(index).copied().unwrap_or(0.)
+  }
+  fn heikin_ashi(&self) -> ChartBars {
+    let [open, high, low, close] = self.ohlc();
+    let mut bars = ChartBars::new();
+    let mut prev: Option<(f64, f64)> = None;
+    for i in 0..self.len() {
+      let ha_close = (open[i] + high[i] + low[i] + close[i]) / 4.;
+      let ha_open = match prev {
+        Some((prev_open, prev_close)) => (prev_open + prev_close) / 2.,
+        None => (open[i] + close[i]) / 2.,
+      };
+      bars.push(
+        i,
+        ha_open,
+        ha_close,
+        high[i].max(ha_open).max(ha_close),
+        low[i].min(ha_open).min(ha_close),
+        self.volume_at(i),
+      );
+      prev = Some((ha_open, ha_close));
+    }
+    bars
+  }
+  fn renko(&self, size: RenkoBox) -> ChartBars {
+    let close = self.close.as_slice();
+    if close.is_empty() {
+      panic!("renko requires the close field");
+    }
+    let (size, begin) = match size {
+      RenkoBox::Fixed(size) => (size, 0),
+      RenkoBox::Atr(period) => {
+        let [_, high, low, close] = self.ohlc();
+        if period == 0 {
+          panic!("atr period must be greater than zero");
+        }
+        if self.len() <= period {
+          return ChartBars::new();
+        }
+        // 第 1 到第 period 个 bar 的真实波幅的均值
+        let atr = (1..=period)
+          .map(|i| {
+            (high[i] - low[i])
+              .max((high[i] - close[i - 1]).abs())
+              .max((low[i] - close[i - 1]).abs())
+          })
+          .sum::<f64>()
+          / period as f64;
+        (atr, period)
+      }
+    };
+    if size.is_nan() || size <= 0. {
+      panic!("renko box size must be greater than zero");
+    }
+    let mut bars = ChartBars::new();
+    // 最后一个砖块的上下沿，初始为起始 bar 的 close
+    let (mut top, mut bottom) = (close[begin], close[begin]);
+    let mut volume = 0.;
+    for (i, price) in close.iter().enumerate().skip(begin + 1) {
+      volume += self.volume_at(i);
+      while *price >= top + size {
+        bars.push(i, top, top + size, top + size, top, volume);
+        volume = 0.;
+        (bottom, top) = (top, top + size);
+      }
+      while *price <= bottom - size {
+        bars.push(i, bottom, bottom - size, bottom, bottom - size, volume);
+        volume = 0.;
+        (top, bottom) = (bottom, bottom - size);
+      }
+    }
+    bars
+  }
+  fn range_bars(&self, range: f64) -> ChartBars {
+    if range.is_nan() || range <= 0. {
+      panic!("range must be greater than zero");
+    }
+    let [open, high, low, close] = self.ohlc();
+    let mut bars = ChartBars::new();
+    if self.is_empty() {
+      return bars;
+    }
+    // 当前 bar 的 open、high、low 和累计的 volume
+    let (mut bar_open, mut bar_high, mut bar_low) = (open[0], open[0], open[0]);
+    let mut volume = 0.;
+    for i in 0..self.len() {
+      volume += self.volume_at(i);
+      let path = if close[i] >= open[i] {
+        [open[i], low[i], high[i], close[i]]
+      } else {
+        [open[i], high[i], low[i], close[i]]
+      };
+      for price in path {
+        loop {
+          let bar_close = if price > bar_high && price - bar_low >= range {
+            bar_low + range
+          } else if price < bar_low && bar_high - price >= range {
+            bar_high - range
+          } else {
+            bar_high = bar_high.max(price);
+            bar_low = bar_low.min(price);
+            break;
+          };
+          bar_high = bar_high.max(bar_close);
+          bar_low = bar_low.min(bar_close);
+          bars.push(i, bar_open, bar_close, bar_high, bar_low, volume);
+          volume = 0.;
+          (bar_open, bar_high, bar_low) = (bar_close, bar_close, bar_close);
+        }
+      }
+    }
+    bars
+  }
+}
+
+#[test]
+fn test_chart_transforms() {
+  use crate::{CsvTimeType, DataLine};
+
+  let content = "date,open,high,low,close,volume
+2022-01-03,10,11,9,10.5,1
+2022-01-04,10.5,12.5,10,12,1
+2022-01-05,12,14.2,11.5,14,1
+2022-01-06,14,14.5,11,11.2,1
+2022-01-07,11.2,11.5,10.8,11,1";
+  let data = CsvDataSource::builder()
+    .time_field("date")
+    .time_type(CsvTimeType::Date("%Y-%m-%d"))
+    .load_from_string(content)
+    .unwrap();
+
+  let ha = data.to_chart(ChartType::HeikinAshi);
+  assert_eq!(ha.data.len(), data.len());
+  assert_eq!(ha.data.timestamp[1], data.timestamp[1]);
+  assert_eq!(ha.data.open.at(0), Some(10.25));
+  assert_eq!(ha.data.close.at(0), Some(10.125));
+  assert_eq!(ha.data.open.at(1), Some((10.25 + 10.125) / 2.));
+  assert_eq!(ha.data.high.at(1), Some(12.5));
+
+  // 砖块大小为 1，从 10.5 开始：12 生成 1 个砖块，14 生成 2 个，11.2 反向移动两个砖块生成 1 个
+  let renko = data.to_chart(ChartType::Renko(RenkoBox::Fixed(1.)));
+  assert_eq!(renko.data.open.as_slice(), &[10.5, 11.5, 12.5, 12.5]);
+  assert_eq!(renko.data.close.as_slice(), &[11.5, 12.5, 13.5, 11.5]);
+  assert_eq!(renko.source_index(2), Some(2));
+  assert_eq!(renko.data.timestamp[3], data.timestamp[3]);
+  assert_eq!(renko.data.volume.as_slice(), &[1., 1., 0., 1.]);
+  assert_eq!(renko.index_at(0), None);
+  assert_eq!(renko.index_at(2), Some(2));
+  assert_eq!(renko.index_at(4), Some(3));
+  let projected = renko.project(&renko.data.close);
+  assert_eq!(projected.at(0), None);
+  assert_eq!(projected.at(1), Some(11.5));
+  assert_eq!(projected.at(3), Some(11.5));
+
+  // ATR(2) = (2.5 + 2.7) / 2 = 2.6，从第 2 个 bar 的 14 开始，11.2 生成 1 个向下的砖块
+  let renko = data.to_chart(ChartType::Renko(RenkoBox::Atr(2)));
+  assert_eq!(renko.data.len(), 1);
+  assert!((renko.data.close.at(0).unwrap() - 11.4).abs() < 1e-9);
+  assert_eq!(renko.index_at(2), None);
+  assert_eq!(renko.index_at(4), Some(0));
+
+  // 区间为 2：10 -> 9 -> 11 生成第一个 bar，之后的 bar 从上一个 bar 的 close 开始
+  let range = data.to_chart(ChartType::Range(2.));
+  let close = range.data.close.as_slice();
+  assert_eq!(close, &[11., 12., 13.5, 12.5]);
+  assert_eq!(range.data.low.at(0), Some(9.));
+  assert!(range
+    .data
+    .high
+    .as_slice()
+    .iter()
+    .zip(range.data.low.as_slice())
+    .all(|(h, l)| h - l <= 2. + 1e-9));
+  assert_eq!(range.source_index(0), Some(0));
+}
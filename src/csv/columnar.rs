@@ -0,0 +1,283 @@
+use std::{
+  fs::{self, File},
+  io::{self, BufReader},
+  path::Path,
+};
+
+use arrow_array::{
+  cast::AsArray,
+  types::{Date32Type, Float64Type, Int64Type, TimestampNanosecondType},
+  Array, ArrayRef, RecordBatch, RecordBatchReader,
+};
+use arrow_cast::cast;
+use arrow_ipc::reader::{FileReader, StreamReader};
+use arrow_schema::{ArrowError, DataType, Schema, TimeUnit};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+use super::{
+  builder::{CsvDataSourceBuilder, CsvTimeType},
+  source::CsvDataSource,
+  util::{
+    local_to_utc, new_io_err, new_io_err_str, new_row_err, parse_time_field, process_rows,
+    DataVecs, LoadResult,
+  },
+};
+
+#[inline]
+fn arrow_err(e: impl ToString) -> io::Error {
+  new_io_err(e.to_string())
+}
+
+/// 将 time 列转换为 UTC 时间，支持：
+/// Timestamp（带时区时为 UTC 时间，不带时区时按 CsvDataSourceBuilder::timezone 换算），
+/// Date32/Date64（按 timezone 换算当天 00:00），整数（按 time_type 解析为秒或者毫秒时间戳），
+/// 字符串（和 csv 一样按 time_type 解析）。
+fn convert_time_column(
+  builder: &CsvDataSourceBuilder,
+  array: &ArrayRef,
+  column: &str,
+  first_row: usize,
+  out: &mut Vec<DateTime<Utc>>,
+) -> io::Result<()> {
+  if array.null_count() > 0 {
+    let i = (0..array.len()).find(|&i| array.is_null(i)).unwrap();
+    return Err(new_row_err(first_row + i, column, "null timestamp"));
+  }
+  match array.data_type() {
+    DataType::Timestamp(_, tz) => {
+      let array = cast(
+        array,
+        &DataType::Timestamp(TimeUnit::Nanosecond, tz.clone()),
+      )
+      .map_err(arrow_err)?;
+      for (i, v) in array
+        .as_primitive::<TimestampNanosecondType>()
+        .values()
+        .iter()
+        .enumerate()
+      {
+        let t = Utc.timestamp_nanos(*v);
+        out.push(match tz {
+          Some(_) => t,
+          None => local_to_utc(builder, t.naive_utc())
+            .map_err(|e| new_row_err(first_row + i, column, e))?,
+        });
+      }
+    }
+    DataType::Date32 | DataType::Date64 => {
+      let array = cast(array, &DataType::Date32).map_err(arrow_err)?;
+      let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+      for (i, v) in array
+        .as_primitive::<Date32Type>()
+        .values()
+        .iter()
+        .enumerate()
+      {
+        let date = epoch + chrono::Duration::days(*v as i64);
+        out.push(
+          local_to_utc(builder, date.and_hms_opt(0, 0, 0).unwrap())
+            .map_err(|e| new_row_err(first_row + i, column, e))?,
+        );
+      }
+    }
+    t if t.is_integer() => {
+      let array = cast(array, &DataType::Int64).map_err(arrow_err)?;
+      for (i, v) in array
+        .as_primitive::<Int64Type>()
+        .values()
+        .iter()
+        .enumerate()
+      {
+        let t = match builder.time_type {
+          CsvTimeType::Second => Utc.timestamp_opt(*v, 0).single(),
+          CsvTimeType::Millsecond => Utc.timestamp_millis_opt(*v).single(),
+          _ => {
+            return Err(new_io_err_str(
+              "integer time column requires 'time_type' Second or Millsecond",
+            ))
+          }
+        };
+        out.push(t.ok_or_else(|| new_row_err(first_row + i, column, "invalid timestamp"))?);
+      }
+    }
+    _ => {
+      let array = cast(array, &DataType::Utf8).map_err(arrow_err)?;
+      for (i, v) in array.as_string::<i32>().iter().enumerate() {
+        let v = parse_time_field(builder, v.unwrap_or_default()).map_err(|e| {
+          new_row_err(
+            first_row + i,
+            column,
+            format!("{}, please check 'time_type' config.", e),
+          )
+        })?;
+        out.push(v);
+      }
+    }
+  }
+  Ok(())
+}
+
+/// 从 RecordBatch 中按 CsvDataSourceBuilder 的字段配置读取数据，数值列统一转换为 f64，null 转换为 NaN。
+fn load_batches<I: Iterator<Item = Result<RecordBatch, ArrowError>>>(
+  schema: &Schema,
+  batches: I,
+  builder: &CsvDataSourceBuilder,
+) -> io::Result<LoadResult> {
+  if builder.time_field.is_empty() {
+    return Err(new_io_err_str("time_field config missing"));
+  }
+  let names: Vec<String> = schema
+    .fields()
+    .iter()
+    .map(|f| f.name().to_lowercase())
+    .collect();
+  // field indeies of: timestamp, 以及按 data_vecs 顺序排列的各个数据列
+  let idx_arr: Vec<Option<usize>> = builder
+    .fields()
+    .iter()
+    .map(|field| names.iter().position(|name| name == *field))
+    .collect();
+  let time_idx = idx_arr[0].ok_or_else(|| new_io_err_str("schema miss timestamp field"))?;
+
+  let mut timestamp_vec = Vec::new();
+  let mut data_vecs: DataVecs = vec![Vec::new(); idx_arr.len() - 1];
+  for batch in batches {
+    let batch = batch.map_err(arrow_err)?;
+    // 数据行号从 1 开始
+    let first_row = timestamp_vec.len() + 1;
+    convert_time_column(
+      builder,
+      batch.column(time_idx),
+      schema.field(time_idx).name(),
+      first_row,
+      &mut timestamp_vec,
+    )?;
+    for (vec, idx) in data_vecs.iter_mut().zip(idx_arr[1..].iter()) {
+      let idx = match idx {
+        Some(idx) => *idx,
+        None => continue,
+      };
+      let array = cast(batch.column(idx), &DataType::Float64)
+        .map_err(|e| new_io_err(format!("column '{}': {}", schema.field(idx).name(), e)))?;
+      let array = array.as_primitive::<Float64Type>();
+      vec.extend(array.iter().map(|v| v.unwrap_or(f64::NAN)));
+    }
+  }
+  process_rows(timestamp_vec, data_vecs, builder)
+}
+
+impl CsvDataSourceBuilder {
+  fn finish_columnar(
+    self,
+    file: &Path,
+    load: impl FnOnce(&Self) -> io::Result<LoadResult>,
+  ) -> io::Result<CsvDataSource> {
+    let modified = fs::metadata(file)?.modified()?;
+    let (timestamp_vec, data_vecs, report) = load(&self)?;
+    let mut data = self.build(timestamp_vec, data_vecs, report)?;
+    data.source = Some((file.to_path_buf(), modified));
+    Ok(data)
+  }
+  /// 从 Parquet 文件加载数据，列的映射、排序和校验等配置和 csv 一致。
+  /// 数值列可以是任意的整数或者浮点类型，time 列的支持类型参见 convert_time_column。
+  pub fn load_from_parquet(self, file: &Path) -> io::Result<CsvDataSource> {
+    self.finish_columnar(file, |builder| {
+      let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(file)?)
+        .map_err(arrow_err)?
+        .build()
+        .map_err(arrow_err)?;
+      let schema = reader.schema();
+      load_batches(&schema, reader, builder)
+    })
+  }
+  /// 从 Arrow IPC 文件（file 格式或者 stream 格式）加载数据，配置和 load_from_parquet 一致。
+  pub fn load_from_arrow(self, file: &Path) -> io::Result<CsvDataSource> {
+    self.finish_columnar(file, |builder| {
+      match FileReader::try_new(BufReader::new(File::open(file)?), None) {
+        Ok(reader) => {
+          let schema = reader.schema();
+          load_batches(&schema, reader, builder)
+        }
+        Err(_) => {
+          let reader =
+            StreamReader::try_new(BufReader::new(File::open(file)?), None).map_err(arrow_err)?;
+          let schema = reader.schema();
+          load_batches(&schema, reader, builder)
+        }
+      }
+    })
+  }
+}
+
+#[test]
+fn test_load_columnar() {
+  use crate::DataLine;
+  use arrow_array::{Float32Array, Int64Array, StringArray, TimestampMillisecondArray};
+  use arrow_schema::Field;
+  use std::sync::Arc;
+
+  let dir = std::env::temp_dir().join(format!("rushtrader-columnar-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  let schema = Arc::new(Schema::new(vec![
+    Field::new(
+      "Time",
+      DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+      false,
+    ),
+    Field::new("Open", DataType::Float32, true),
+    Field::new("Close", DataType::Float32, true),
+    Field::new("Volume", DataType::Int64, true),
+    Field::new("vwap", DataType::Float32, true),
+    Field::new("note", DataType::Utf8, true),
+  ]));
+  // 降序排列的数据，加载后会被反转为升序
+  let batch = RecordBatch::try_new(
+    schema.clone(),
+    vec![
+      Arc::new(TimestampMillisecondArray::from(vec![86_400_000, 0]).with_timezone("UTC")),
+      Arc::new(Float32Array::from(vec![2., 1.])),
+      Arc::new(Float32Array::from(vec![2.5, 1.5])),
+      Arc::new(Int64Array::from(vec![Some(20), None])),
+      Arc::new(Float32Array::from(vec![2.25, 1.25])),
+      Arc::new(StringArray::from(vec!["b", "a"])),
+    ],
+  )
+  .unwrap();
+  let builder = || {
+    CsvDataSource::builder()
+      .time_field("time")
+      .extra_field("vwap")
+  };
+  let check = |data: CsvDataSource| {
+    assert_eq!(data.len(), 2);
+    assert_eq!(data.timestamp[1], Utc.timestamp_opt(86_400, 0).unwrap());
+    assert_eq!(data.close.as_slice(), &[1.5, 2.5]);
+    assert!(data.volume.at(0).unwrap().is_nan());
+    assert_eq!(data.extra("VWAP").unwrap().as_slice(), &[1.25, 2.25]);
+    assert!(data.high.is_empty());
+  };
+
+  let file = dir.join("data.parquet");
+  let mut writer =
+    parquet::arrow::ArrowWriter::try_new(File::create(&file).unwrap(), schema.clone(), None)
+      .unwrap();
+  writer.write(&batch).unwrap();
+  writer.close().unwrap();
+  check(builder().load_from_parquet(&file).unwrap());
+
+  let file = dir.join("data.arrow");
+  let mut writer =
+    arrow_ipc::writer::FileWriter::try_new(File::create(&file).unwrap(), &schema).unwrap();
+  writer.write(&batch).unwrap();
+  writer.finish().unwrap();
+  check(builder().load_from_arrow(&file).unwrap());
+
+  let e = CsvDataSource::builder()
+    .time_field("date")
+    .load_from_arrow(&file)
+    .err()
+    .unwrap();
+  assert_eq!(e.to_string(), "schema miss timestamp field");
+  fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,360 @@
+use std::io;
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::{
+  source::CsvDataSource,
+  util::{new_io_err, new_io_err_str, DataVecs, COLUMN_COUNT},
+};
+use crate::DataLine;
+
+/// 换月（从当前合约切换到下一个合约）的时间规则。
+#[derive(Debug, Clone)]
+pub enum RollSchedule {
+  /// 在指定的时间换月，dates[i] 是从第 i 个合约切换到第 i + 1 个合约的时间，长度必须为合约数量减 1
+  Dates(Vec<DateTime<Utc>>),
+  /// 下一个合约的 volume 超过当前合约之后的下一个 bar 换月
+  VolumeCrossover,
+  /// 下一个合约的 openintrest 超过当前合约之后的下一个 bar 换月
+  OpenInterestCrossover,
+  /// 在当前合约到期日的 N 天之前换月
+  DaysBeforeExpiry(i64),
+}
+
+/// 换月时对历史价格的调整方式，以最新的合约为准向前调整，消除换月时两个合约的价差。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RollAdjustment {
+  /// 不调整，直接拼接
+  None,
+  /// 按新旧合约价格的比例调整，适合长期的收益率计算，调整后的价格始终为正
+  Ratio,
+  /// 按新旧合约价格的差值调整，保持价格变化的绝对值（即盈亏）不变
+  Difference,
+}
+
+/// 换月事件，策略可以在 Strategy::next 中通过 CsvDataSource::roll_at 获取，用于移仓。
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollEvent {
+  /// 新合约在连续序列中的第一个 bar 的下标
+  pub index: usize,
+  pub from: String,
+  pub to: String,
+  /// 换月前最后一个 bar 的时间，以及该时间旧合约和新合约的 close 价（未调整），用于计算调整量
+  pub reference_time: DateTime<Utc>,
+  pub from_price: f64,
+  pub to_price: f64,
+}
+
+struct Contract {
+  name: String,
+  data: CsvDataSource,
+  expiry: Option<DateTime<Utc>>,
+}
+
+/// 将多个期货合约的数据按换月规则拼接为连续合约的数据源，并按调整方式对历史价格进行向前复权。
+/// volume、openintrest、adjustclose 和额外的数值列不会被调整；只有全部合约都存在的列才会被保留。
+pub struct ContinuousFuturesBuilder {
+  contracts: Vec<Contract>,
+  schedule: RollSchedule,
+  adjustment: RollAdjustment,
+}
+
+impl ContinuousFuturesBuilder {
+  pub fn new(schedule: RollSchedule, adjustment: RollAdjustment) -> Self {
+    Self {
+      contracts: Vec::new(),
+      schedule,
+      adjustment,
+    }
+  }
+  /// 按到期的先后顺序添加合约，expiry 为合约的到期时间，只有 RollSchedule::DaysBeforeExpiry 需要，
+  /// 为 None 时以合约最后一个 bar 的时间作为到期时间。
+  pub fn contract(
+    mut self,
+    name: &str,
+    data: CsvDataSource,
+    expiry: Option<DateTime<Utc>>,
+  ) -> Self {
+    self.contracts.push(Contract {
+      name: name.to_string(),
+      data,
+      expiry,
+    });
+    self
+  }
+
+  /// 计算第 i 个合约切换到第 i + 1 个合约的时间，即新合约在连续序列中的第一个 bar 的时间。
+  /// prev 为上一次换月的时间，当前合约的数据从 prev 开始。
+  fn roll_time(&self, i: usize, prev: Option<DateTime<Utc>>) -> io::Result<DateTime<Utc>> {
+    let (cur, next) = (&self.contracts[i], &self.contracts[i + 1]);
+    let cur_ts = cur.data.timestamp.as_slice();
+    let next_ts = next.data.timestamp.as_slice();
+    // 新合约中第一个时间大于等于 t 的 bar 的时间
+    let next_from = |t: DateTime<Utc>| next_ts.get(next_ts.partition_point(|v| *v < t)).copied();
+    let start = cur_ts.partition_point(|t| Some(*t) < prev);
+    let candidate = match &self.schedule {
+      RollSchedule::Dates(dates) => dates[i],
+      RollSchedule::DaysBeforeExpiry(days) => {
+        let expiry = cur.expiry.or_else(|| cur_ts.last().copied()).unwrap();
+        expiry - Duration::days(*days)
+      }
+      RollSchedule::VolumeCrossover | RollSchedule::OpenInterestCrossover => {
+        let column = |data: &CsvDataSource| {
+          match self.schedule {
+            RollSchedule::VolumeCrossover => &data.volume,
+            _ => &data.openintrest,
+          }
+          .as_slice()
+          .to_vec()
+        };
+        let (cur_v, next_v) = (column(&cur.data), column(&next.data));
+        if cur_v.is_empty() || next_v.is_empty() {
+          return Err(new_io_err_str(
+            "crossover roll schedule requires the volume or openintrest field",
+          ));
+        }
+        // 在收盘时观察到交叉，下一个 bar 换月，没有交叉时在当前合约的最后一个 bar 之后换月
+        let crossover = cur_ts[start..]
+          .iter()
+          .zip(&cur_v[start..])
+          .find(|(t, v)| next_ts.binary_search(t).is_ok_and(|k| next_v[k] > **v))
+          .map(|(t, _)| *t)
+          .or_else(|| cur_ts.last().copied())
+          .unwrap();
+        crossover + Duration::nanoseconds(1)
+      }
+    };
+    let roll = match prev {
+      Some(prev) if candidate < prev => next_from(prev),
+      _ => next_from(candidate),
+    }
+    .ok_or_else(|| new_io_err(format!("contract {} has no data after the roll", next.name)))?;
+    if !cur_ts[start..].iter().any(|t| *t < roll) {
+      return Err(new_io_err(format!(
+        "roll schedule leaves contract {} without data",
+        cur.name
+      )));
+    }
+    Ok(roll)
+  }
+
+  pub fn build(self) -> io::Result<CsvDataSource> {
+    let n = self.contracts.len();
+    if n == 0 {
+      return Err(new_io_err_str(
+        "continuous futures requires at least one contract",
+      ));
+    }
+    if matches!(&self.schedule, RollSchedule::Dates(dates) if dates.len() != n - 1) {
+      return Err(new_io_err(format!(
+        "roll schedule expects {} dates but found {}",
+        n - 1,
+        match &self.schedule {
+          RollSchedule::Dates(dates) => dates.len(),
+          _ => 0,
+        }
+      )));
+    }
+    if self.contracts.iter().any(|c| c.data.close.is_empty()) {
+      return Err(new_io_err_str(
+        "continuous futures requires the close field of every contract",
+      ));
+    }
+
+    // 每个合约在连续序列中使用的数据区间
+    let mut rolls: Vec<DateTime<Utc>> = Vec::with_capacity(n - 1);
+    for i in 0..n - 1 {
+      let roll = self.roll_time(i, rolls.last().copied())?;
+      rolls.push(roll);
+    }
+    let ranges: Vec<_> = (0..n)
+      .map(|i| {
+        let ts = self.contracts[i].data.timestamp.as_slice();
+        let start = i
+          .checked_sub(1)
+          .map_or(0, |k| ts.partition_point(|t| *t < rolls[k]));
+        let end = rolls
+          .get(i)
+          .map_or(ts.len(), |roll| ts.partition_point(|t| t < roll));
+        start..end
+      })
+      .collect();
+
+    // 换月事件以及每个合约的价格调整量，从最新的合约向前累积
+    let mut events = Vec::with_capacity(n - 1);
+    let mut index = 0;
+    for i in 0..n - 1 {
+      let (cur, next) = (&self.contracts[i].data, &self.contracts[i + 1].data);
+      index += ranges[i].len();
+      let last = ranges[i].end - 1;
+      let reference_time = cur.timestamp[last];
+      let from_price = cur.close.at(last).unwrap();
+      // 新合约在 reference_time 及之前最近的 close 价，没有时使用新合约第一个 bar 的 open 价
+      let next_ts = next.timestamp.as_slice();
+      let k = next_ts.partition_point(|t| *t <= reference_time);
+      let to_price = match k {
+        0 => next
+          .open
+          .at(ranges[i + 1].start)
+          .unwrap_or_else(|| next.close.at(ranges[i + 1].start).unwrap()),
+        k => next.close.at(k - 1).unwrap(),
+      };
+      events.push(RollEvent {
+        index,
+        from: self.contracts[i].name.clone(),
+        to: self.contracts[i + 1].name.clone(),
+        reference_time,
+        from_price,
+        to_price,
+      });
+    }
+    let mut adjustments = vec![(1., 0.); n];
+    for i in (0..n - 1).rev() {
+      let (ratio, diff) = adjustments[i + 1];
+      let event = &events[i];
+      adjustments[i] = match self.adjustment {
+        RollAdjustment::None => (1., 0.),
+        RollAdjustment::Ratio => (ratio * event.to_price / event.from_price, 0.),
+        RollAdjustment::Difference => (1., diff + event.to_price - event.from_price),
+      };
+    }
+
+    // 只保留全部合约都存在的列，额外的数值列按第一个合约的列名匹配
+    let extra_names = self.contracts[0].data.extra_names();
+    let extra_names: Vec<String> = extra_names
+      .into_iter()
+      .filter(|name| {
+        self
+          .contracts
+          .iter()
+          .all(|c| c.data.extra(name).is_some_and(|l| !l.is_empty()))
+      })
+      .collect();
+    let mut timestamp_vec = Vec::new();
+    let mut data_vecs: DataVecs = vec![Vec::new(); COLUMN_COUNT + extra_names.len()];
+    for (contract, (range, (ratio, diff))) in self
+      .contracts
+      .iter()
+      .zip(ranges.into_iter().zip(adjustments))
+    {
+      let data = &contract.data;
+      timestamp_vec.extend_from_slice(&data.timestamp.as_slice()[range.clone()]);
+      let mut columns = data.columns()[..COLUMN_COUNT].to_vec();
+      columns.extend(extra_names.iter().map(|name| data.extra(name).unwrap()));
+      for (c, (vec, column)) in data_vecs.iter_mut().zip(columns).enumerate() {
+        let values = column.as_slice();
+        if values.is_empty() {
+          continue;
+        }
+        vec.extend(values[range.clone()].iter().map(|v| match c {
+          0..=3 | 7..=14 => v * ratio + diff,
+          // spread 是价差，只按比例调整
+          15 => v * ratio,
+          _ => *v,
+        }));
+      }
+    }
+    let len = timestamp_vec.len();
+    for vec in data_vecs.iter_mut() {
+      if vec.len() != len {
+        vec.clear();
+      }
+    }
+    let mut data = CsvDataSource::inner_new(timestamp_vec, data_vecs, &extra_names, None);
+    data.rolls = events;
+    Ok(data)
+  }
+}
+
+#[test]
+fn test_continuous_futures() {
+  use crate::{CsvBroker, CsvTimeType, Engine, Strategy};
+  use chrono::TimeZone;
+  use std::cell::RefCell;
+
+  let load = |content: &str| {
+    CsvDataSource::builder()
+      .time_field("date")
+      .time_type(CsvTimeType::Date("%Y-%m-%d"))
+      .load_from_string(content)
+      .unwrap()
+  };
+  let date = |d: u32| Utc.with_ymd_and_hms(2022, 1, d, 0, 0, 0).unwrap();
+  let contract = |content: &str| {
+    format!(
+      "date,open,close,volume,open intrest\n{}",
+      content.replace(' ', "\n")
+    )
+  };
+  let a = contract("2022-01-03,100,100,50,900 2022-01-04,101,101,40,700 2022-01-05,102,102,20,300 2022-01-06,103,103,10,100");
+  let b = contract("2022-01-04,110,110,30,500 2022-01-05,111,111,60,800 2022-01-06,112,112,70,900 2022-01-07,114,114,80,950");
+  let builder = |schedule, adjustment| {
+    ContinuousFuturesBuilder::new(schedule, adjustment)
+      .contract("A", load(&a), Some(date(7)))
+      .contract("B", load(&b), Some(date(20)))
+  };
+
+  // volume 在 01-05 交叉，01-06 换月；以 01-05 的 close 计算调整量：102 -> 111
+  let data = builder(RollSchedule::VolumeCrossover, RollAdjustment::Difference)
+    .build()
+    .unwrap();
+  assert_eq!(data.len(), 5);
+  assert_eq!(data.close.as_slice(), &[109., 110., 111., 112., 114.]);
+  assert_eq!(data.volume.as_slice(), &[50., 40., 20., 70., 80.]);
+  let event = data.roll_at(3).unwrap();
+  assert_eq!((event.from.as_str(), event.to.as_str()), ("A", "B"));
+  assert_eq!((event.from_price, event.to_price), (102., 111.));
+  assert!(data.roll_at(2).is_none());
+
+  // openintrest 在 01-05 交叉（01-04 时 500 < 700），同样 01-06 换月
+  let data = builder(RollSchedule::OpenInterestCrossover, RollAdjustment::Ratio)
+    .build()
+    .unwrap();
+  assert_eq!(data.rolls()[0].index, 3);
+  assert!((data.close.at(0).unwrap() - 100. * 111. / 102.).abs() < 1e-9);
+
+  let data = builder(RollSchedule::Dates(vec![date(4)]), RollAdjustment::None)
+    .build()
+    .unwrap();
+  assert_eq!(data.close.as_slice(), &[100., 110., 111., 112., 114.]);
+  // 新合约在参考时间之前没有数据时，使用新合约第一个 bar 的 open 价
+  assert_eq!(data.rolls()[0].to_price, 110.);
+
+  // 合约 A 在 01-07 到期，提前 2 天即 01-05 换月
+  let data = builder(RollSchedule::DaysBeforeExpiry(2), RollAdjustment::None)
+    .build()
+    .unwrap();
+  assert_eq!(data.rolls()[0].index, 2);
+
+  let e = builder(RollSchedule::Dates(vec![date(10)]), RollAdjustment::None)
+    .build()
+    .err()
+    .unwrap();
+  assert_eq!(e.to_string(), "contract B has no data after the roll");
+
+  // 策略在换月的 bar 上移仓
+  struct Strat(RefCell<Vec<String>>);
+  impl Strategy for Strat {
+    type DS = CsvDataSource;
+    type BK = CsvBroker;
+    fn feed(&mut self, _data: &Self::DS) {}
+    fn next(&mut self, index: usize, data: &Self::DS, _broker: &mut Self::BK) {
+      if let Some(event) = data.roll_at(index) {
+        self
+          .0
+          .borrow_mut()
+          .push(format!("{}->{}", event.from, event.to));
+      }
+    }
+    fn calc_commission(&self, _size: isize, _price: f64) -> f64 {
+      0.
+    }
+    fn on_finish(&self, _data: &Self::DS, _broker: &Self::BK) {
+      assert_eq!(self.0.borrow().as_slice(), &["A->B".to_string()]);
+    }
+  }
+  let data = builder(RollSchedule::VolumeCrossover, RollAdjustment::Ratio)
+    .build()
+    .unwrap();
+  Engine::new(data, Strat(RefCell::new(Vec::new())), CsvBroker::new(0.)).run();
+}
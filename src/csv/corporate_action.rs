@@ -0,0 +1,289 @@
+use std::{fs::read_to_string, io, path::Path};
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use super::util::{new_io_err, new_io_err_str, new_row_err, numbered_lines, parse_f64, DataVecs};
+
+/// 公司行为，在除权日（ex-date）生效。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CorporateAction {
+  /// 拆股，参数为每 1 股拆分后的股数，比如 2 拆 1 为 2，10 合 1 为 0.1
+  Split(f64),
+  /// 每股派发的现金分红
+  Dividend(f64),
+}
+
+/// 公司行为的处理方式。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CorporateActionMode {
+  /// 加载数据时按公司行为对除权日之前的 OHLC、报价和 volume 向前复权，最新的价格保持不变，
+  /// broker 不需要做任何处理。adjustclose、openintrest 和额外的数值列不会被调整。
+  BackAdjust,
+  /// 保留原始价格，由 broker 在除权日调整仓位（拆股）以及将分红计入现金（空仓需要支付分红）。
+  Broker,
+}
+
+/// 按日期排列的公司行为列表。
+#[derive(Debug, Clone, Default)]
+pub struct CorporateActions {
+  actions: Vec<(DateTime<Utc>, CorporateAction)>,
+}
+
+impl CorporateActions {
+  pub fn new() -> Self {
+    Self {
+      actions: Vec::new(),
+    }
+  }
+  fn push(mut self, date: DateTime<Utc>, action: CorporateAction) -> Self {
+    let i = self.actions.partition_point(|(d, _)| *d <= date);
+    self.actions.insert(i, (date, action));
+    self
+  }
+  /// 添加一次拆股，date 为除权日，ratio 必须大于 0
+  pub fn split(self, date: DateTime<Utc>, ratio: f64) -> Self {
+    assert!(ratio > 0., "split ratio must be greater than zero");
+    self.push(date, CorporateAction::Split(ratio))
+  }
+  /// 添加一次现金分红，date 为除权日，amount 为每股分红，不能小于 0
+  pub fn dividend(self, date: DateTime<Utc>, amount: f64) -> Self {
+    assert!(amount >= 0., "dividend amount must not be negative");
+    self.push(date, CorporateAction::Dividend(amount))
+  }
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.actions.len()
+  }
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.actions.is_empty()
+  }
+  #[inline]
+  pub fn as_slice(&self) -> &[(DateTime<Utc>, CorporateAction)] {
+    &self.actions
+  }
+  pub fn load_from_file(file: &Path, date_format: &str, tz: Tz) -> io::Result<Self> {
+    let content = read_to_string(file)?;
+    Self::load_from_string(&content, date_format, tz)
+  }
+  /// 从 csv 加载公司行为，header 为 date,action,value（忽略大小写和列的顺序），
+  /// action 为 split 或者 dividend，date 按 date_format 解析为 tz 时区的日期，在当天 00:00 生效。比如：
+  /// ```text
+  /// date,action,value
+  /// 2000-03-27,split,2
+  /// 2009-04-15,dividend,0.05
+  /// ```
+  pub fn load_from_string(content: &str, date_format: &str, tz: Tz) -> io::Result<Self> {
+    let mut lines = numbered_lines(content.lines());
+    let (_, header_line) = lines
+      .next()
+      .ok_or_else(|| new_io_err_str("csv missing header line"))?;
+    let columns: Vec<String> = header_line
+      .split(',')
+      .map(|s| s.trim().to_lowercase())
+      .collect();
+    let idx_arr = ["date", "action", "value"].map(|field| columns.iter().position(|c| c == field));
+    let [Some(date_idx), Some(action_idx), Some(value_idx)] = idx_arr else {
+      return Err(new_io_err_str(
+        "csv header must contain date, action and value fields",
+      ));
+    };
+    let mut actions = Self::new();
+    for (row, line) in lines {
+      let segs: Vec<&str> = line.split(',').map(|s| s.trim()).collect();
+      let seg = |idx: usize| {
+        segs.get(idx).copied().ok_or_else(|| {
+          new_io_err(format!(
+            "row {}: expect at least {} columns but found {}",
+            row,
+            idx + 1,
+            segs.len()
+          ))
+        })
+      };
+      let date = NaiveDate::parse_from_str(seg(date_idx)?, date_format)
+        .ok()
+        .and_then(|d| tz.from_local_datetime(&d.and_hms_opt(0, 0, 0)?).earliest())
+        .ok_or_else(|| new_row_err(row, "date", "invalid date"))?
+        .with_timezone(&Utc);
+      let value = parse_f64(seg(value_idx)?).map_err(|e| new_row_err(row, "value", e))?;
+      actions = match seg(action_idx)?.to_lowercase().as_str() {
+        "split" if value > 0. => actions.split(date, value),
+        "dividend" if value >= 0. => actions.dividend(date, value),
+        "split" | "dividend" => return Err(new_row_err(row, "value", "invalid value")),
+        action => {
+          return Err(new_row_err(
+            row,
+            "action",
+            format!("unknown action '{}'", action),
+          ))
+        }
+      };
+    }
+    Ok(actions)
+  }
+  /// 将公司行为定位到除权日当天或之后的第一个 bar 的下标上，忽略第一个 bar 之前和最后一个 bar 之后的公司行为。
+  pub(super) fn locate(&self, timestamp: &[DateTime<Utc>]) -> Vec<(usize, CorporateAction)> {
+    self
+      .actions
+      .iter()
+      .map(|(date, action)| (timestamp.partition_point(|t| t < date), *action))
+      .filter(|(i, _)| *i > 0 && *i < timestamp.len())
+      .collect()
+  }
+}
+
+/// 按公司行为对 data_vecs 向前复权。拆股的价格因子为 1 / ratio，volume 因子为 ratio；
+/// 分红的价格因子为 1 - amount / 除权日前一个 bar 的 close。
+pub(super) fn back_adjust(
+  data_vecs: &mut DataVecs,
+  actions: &[(usize, CorporateAction)],
+) -> io::Result<()> {
+  let Some(len) = data_vecs.iter().map(|v| v.len()).max() else {
+    return Ok(());
+  };
+  let mut price_factors = vec![1.; len];
+  let mut volume_factors = vec![1.; len];
+  for (index, action) in actions {
+    let (price_factor, volume_factor) = match action {
+      CorporateAction::Split(ratio) => (1. / ratio, *ratio),
+      CorporateAction::Dividend(amount) => {
+        let close = data_vecs[1]
+          .get(index - 1)
+          .copied()
+          .ok_or_else(|| new_io_err_str("adjusting dividends requires the close field"))?;
+        let factor = 1. - amount / close;
+        if factor.is_nan() || factor <= 0. {
+          return Err(new_io_err(format!(
+            "dividend {} is not less than the previous close {}",
+            amount, close
+          )));
+        }
+        (factor, 1.)
+      }
+    };
+    price_factors[..*index]
+      .iter_mut()
+      .for_each(|f| *f *= price_factor);
+    volume_factors[..*index]
+      .iter_mut()
+      .for_each(|f| *f *= volume_factor);
+  }
+  for (i, vec) in data_vecs.iter_mut().enumerate() {
+    let factors = match i {
+      0..=3 | 7..=15 => &price_factors,
+      4 => &volume_factors,
+      _ => continue,
+    };
+    vec.iter_mut().zip(factors).for_each(|(v, f)| *v *= f);
+  }
+  Ok(())
+}
+
+#[test]
+fn test_corporate_actions() {
+  use crate::{
+    Broker, CsvBroker, CsvDataSource, CsvDataSourceBuilder, CsvTimeType, DataLine, Engine,
+    SingleBroker, Strategy,
+  };
+
+  let content = "date,open,close,volume
+2022-01-03,100,100,10
+2022-01-04,102,104,10
+2022-01-05,52,51,20
+2022-01-06,50,50,20
+2022-01-07,51,52,20";
+  let date = |d: u32| Utc.with_ymd_and_hms(2022, 1, d, 0, 0, 0).unwrap();
+  let actions = CorporateActions::load_from_string(
+    "Action,Date,Value\nsplit,2022-01-05,2\ndividend,2022-01-06,1.02\ndividend,2023-01-01,1",
+    "%Y-%m-%d",
+    Tz::UTC,
+  )
+  .unwrap();
+  assert_eq!(actions.len(), 3);
+  assert_eq!(actions.as_slice()[0], (date(5), CorporateAction::Split(2.)));
+  let builder = |mode| {
+    CsvDataSourceBuilder::new()
+      .time_field("date")
+      .time_type(CsvTimeType::Date("%Y-%m-%d"))
+      .corporate_actions(actions.clone(), mode)
+  };
+
+  // 向前复权：拆股之前的价格除以 2，分红之前的价格乘以 1 - 1.02 / 51
+  let data = builder(CorporateActionMode::BackAdjust)
+    .load_from_string(content)
+    .unwrap();
+  let close = data.close.as_slice();
+  assert!((close[0] - 100. / 2. * 0.98).abs() < 1e-9);
+  assert!((close[2] - 51. * 0.98).abs() < 1e-9);
+  assert_eq!(&close[3..], &[50., 52.]);
+  assert_eq!(data.volume.as_slice(), &[20., 20., 20., 20., 20.]);
+  assert!(data.corporate_actions().is_empty());
+
+  let data = builder(CorporateActionMode::Broker)
+    .load_from_string(content)
+    .unwrap();
+  assert_eq!(data.close.at(0), Some(100.));
+  assert_eq!(
+    data.corporate_actions(),
+    &[
+      (2, CorporateAction::Split(2.)),
+      (3, CorporateAction::Dividend(1.02))
+    ]
+  );
+
+  struct Strat;
+  impl Strategy for Strat {
+    type DS = CsvDataSource;
+    type BK = CsvBroker;
+    fn feed(&mut self, _data: &Self::DS) {}
+    fn next(&mut self, index: usize, data: &Self::DS, broker: &mut Self::BK) {
+      match index {
+        // 以 102 买入 5 股，除权日以拆股后的价格 52 再买入 2 股，这 2 股不参与拆股
+        0 => broker.buy(5, data, self),
+        1 => broker.buy(2, data, self),
+        // 拆股在除权日的 next 之前生效，策略看到的是拆股后的持仓和价格
+        2 => {
+          assert_eq!(broker.position_size(), 12);
+          assert!((broker.position().price - (510. + 104.) / 12.).abs() < 1e-9);
+          // 上一个 bar 的 close 按拆股后的价格 104 / 2 计算持仓价值
+          assert!((broker.value(data) - (1000. - 510. - 104. + 12. * 52.)).abs() < 1e-9);
+        }
+        // 分红在除权日的 next 之前计入现金，持仓价值按除息后的价格计算
+        3 => {
+          let cash = 1000. - 510. - 104. + 12. * 1.02;
+          assert!((broker.cash() - cash).abs() < 1e-9);
+          assert!((broker.value(data) - (cash + 12. * (51. - 1.02))).abs() < 1e-9);
+        }
+        _ => {}
+      }
+    }
+    fn calc_commission(&self, _size: isize, _price: f64) -> f64 {
+      0.
+    }
+    fn on_finish(&self, data: &Self::DS, broker: &Self::BK) {
+      // 12 股获得每股 1.02 的分红
+      assert_eq!(broker.position_size(), 12);
+      let cash = 1000. - 510. - 104. + 12. * 1.02;
+      assert!((broker.cash() - cash).abs() < 1e-9);
+      assert!((broker.value(data) - (cash + 12. * 52.)).abs() < 1e-9);
+    }
+  }
+  let data = builder(CorporateActionMode::Broker)
+    .load_from_string(content)
+    .unwrap();
+  Engine::new(data, Strat, CsvBroker::new(1000.)).run();
+
+  let e = CorporateActions::load_from_string(
+    "date,action,value\n2022-01-01,merge,1",
+    "%Y-%m-%d",
+    Tz::UTC,
+  )
+  .err()
+  .unwrap();
+  assert_eq!(
+    e.to_string(),
+    "row 2, column 'action': unknown action 'merge'"
+  );
+}
@@ -0,0 +1,191 @@
+use std::{
+  fs::{self, read_to_string},
+  io,
+  path::Path,
+};
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::Value;
+
+use super::{
+  builder::{CsvDataSourceBuilder, CsvTimeType},
+  source::CsvDataSource,
+  util::{
+    new_io_err, new_row_err, parse_f64, parse_time_field, process_rows, DataVecs, LoadResult,
+  },
+};
+
+/// 解析 time 字段，数值按 time_type 解析为秒或者毫秒时间戳，字符串和 csv 一样按 time_type 解析。
+fn parse_json_time(builder: &CsvDataSourceBuilder, v: &Value) -> io::Result<DateTime<Utc>> {
+  match v {
+    Value::String(v) => parse_time_field(builder, v),
+    Value::Number(n) => {
+      let n = n
+        .as_i64()
+        .ok_or_else(|| new_io_err(format!("invalid integer timestamp {}", n)))?;
+      let t = match builder.time_type {
+        CsvTimeType::Second => Utc.timestamp_opt(n, 0).single(),
+        CsvTimeType::Millsecond => Utc.timestamp_millis_opt(n).single(),
+        _ => return Err(new_io_err(format!("unexpected number {}", n))),
+      };
+      t.ok_or_else(|| new_io_err(format!("invalid timestamp {}", n)))
+    }
+    _ => Err(new_io_err(format!(
+      "expect string or number but found {}",
+      v
+    ))),
+  }
+}
+
+/// 解析数值字段，支持数值和数值字符串，null 解析为 NaN。
+fn parse_json_f64(v: &Value) -> io::Result<f64> {
+  match v {
+    Value::Number(n) => Ok(n.as_f64().unwrap_or(f64::NAN)),
+    Value::String(v) => parse_f64(v),
+    Value::Null => Ok(f64::NAN),
+    _ => Err(new_io_err(format!("expect number but found {}", v))),
+  }
+}
+
+fn load_json_from_lines<'a, T: Iterator<Item = &'a str>>(
+  lines: T,
+  builder: &CsvDataSourceBuilder,
+) -> io::Result<LoadResult> {
+  let fields = builder.fields();
+  let mut timestamp_vec = Vec::new();
+  let mut data_vecs: DataVecs = vec![Vec::new(); fields.len() - 1];
+  // 任意一行中出现过的字段视为存在的列，其它行缺少该字段时补 NaN
+  let mut present = vec![false; fields.len() - 1];
+  // 数据行号从 1 开始，忽略空行。
+  for (row, line) in lines.enumerate().map(|(i, l)| (i + 1, l.trim())) {
+    if line.is_empty() {
+      continue;
+    }
+    let object = match serde_json::from_str(line) {
+      Ok(Value::Object(object)) => object,
+      Ok(_) => return Err(new_io_err(format!("row {}: expect a json object", row))),
+      Err(e) => return Err(new_io_err(format!("row {}: {}", row, e))),
+    };
+    let mut values = vec![None; fields.len()];
+    for (key, value) in object.iter() {
+      if let Some(i) = fields
+        .iter()
+        .position(|field| **field == key.to_lowercase())
+      {
+        values[i] = Some((key, value));
+      }
+    }
+    let (key, value) = values[0].ok_or_else(|| {
+      new_io_err(format!(
+        "row {}: missing timestamp key '{}'",
+        row, builder.time_field
+      ))
+    })?;
+    let v = parse_json_time(builder, value)
+      .map_err(|e| new_row_err(row, key, format!("{}, please check 'time_type' config.", e)))?;
+    timestamp_vec.push(v);
+    for (i, value) in values[1..].iter().enumerate() {
+      let v = match value {
+        Some((key, value)) => {
+          present[i] = true;
+          parse_json_f64(value).map_err(|e| new_row_err(row, key, e))?
+        }
+        None => f64::NAN,
+      };
+      data_vecs[i].push(v);
+    }
+  }
+  for (vec, present) in data_vecs.iter_mut().zip(present) {
+    if !present {
+      vec.clear();
+    }
+  }
+  process_rows(timestamp_vec, data_vecs, builder)
+}
+
+impl CsvDataSourceBuilder {
+  /// 从 JSON Lines 文件加载数据，每一行是一个 bar 对象，比如：
+  /// ```text
+  /// {"date": "2022-01-03", "open": 1.0, "close": 1.5, "volume": 100}
+  /// ```
+  /// 对象的 key 和 csv 的列名一样通过 time_field、open_field 等配置（忽略大小写），
+  /// 数值可以是数值或者数值字符串，null 以及缺少的 key 解析为 NaN，所有行都没有出现的 key 视为不存在的列。
+  /// 数值类型的 time 按 time_type 解析为秒（Second）或者毫秒（Millsecond）时间戳。
+  pub fn load_from_json_lines_file(self, file: &Path) -> io::Result<CsvDataSource> {
+    self.check_config()?;
+    let modified = fs::metadata(file)?.modified()?;
+    let content = read_to_string(file)?;
+    let (timestamp_vec, data_vecs, report) = load_json_from_lines(content.lines(), &self)?;
+    let mut data = self.build(timestamp_vec, data_vecs, report)?;
+    data.source = Some((file.to_path_buf(), modified));
+    Ok(data)
+  }
+  pub fn load_from_json_lines_string(self, content: &str) -> io::Result<CsvDataSource> {
+    self.load_from_json_lines(content.lines())
+  }
+  pub fn load_from_json_lines<'a, T: Iterator<Item = &'a str>>(
+    self,
+    lines: T,
+  ) -> io::Result<CsvDataSource> {
+    self.check_config()?;
+    let (timestamp_vec, data_vecs, report) = load_json_from_lines(lines, &self)?;
+    self.build(timestamp_vec, data_vecs, report)
+  }
+}
+
+#[test]
+fn test_load_json_lines() {
+  use crate::DataLine;
+
+  let content = r#"
+    {"Date": "2022-01-03", "open": 1, "close": "1.5", "volume": 100, "vwap": 1.2}
+    {"Date": "2022-01-04", "open": 2, "close": 2.5, "volume": null}
+
+    {"Date": "2022-01-05", "open": 3, "close": 3.5, "volume": 300, "memo": "x"}
+  "#;
+  let data = CsvDataSource::builder()
+    .time_field("date")
+    .time_type(CsvTimeType::Date("%Y-%m-%d"))
+    .extra_field("vwap")
+    .load_from_json_lines_string(content)
+    .unwrap();
+  assert_eq!(data.len(), 3);
+  assert_eq!(
+    data.timestamp[2],
+    Utc.with_ymd_and_hms(2022, 1, 5, 0, 0, 0).unwrap()
+  );
+  assert_eq!(data.close.as_slice(), &[1.5, 2.5, 3.5]);
+  assert!(data.volume.at(1).unwrap().is_nan());
+  assert!(data.high.is_empty());
+  let vwap = data.extra("vwap").unwrap();
+  assert_eq!(vwap.at(0), Some(1.2));
+  assert!(vwap.at(2).unwrap().is_nan());
+
+  let data = CsvDataSource::builder()
+    .time_field("t")
+    .time_type(CsvTimeType::Millsecond)
+    .close_field("c")
+    .load_from_json_lines_string("{\"t\": 2000, \"c\": 2}\n{\"t\": 1000, \"c\": 1}")
+    .unwrap();
+  assert_eq!(data.timestamp[0], Utc.timestamp_opt(1, 0).unwrap());
+  assert_eq!(data.close.as_slice(), &[1., 2.]);
+
+  let builder = || {
+    CsvDataSource::builder()
+      .time_field("t")
+      .time_type(CsvTimeType::Second)
+  };
+  let err = |content: &str| {
+    builder()
+      .load_from_json_lines_string(content)
+      .err()
+      .unwrap()
+      .to_string()
+  };
+  assert_eq!(err("{\"t\": 1}\n[1]"), "row 2: expect a json object");
+  assert_eq!(err("{\"close\": 1}"), "row 1: missing timestamp key 't'");
+  assert_eq!(
+    err("{\"t\": 1, \"close\": true}"),
+    "row 1, column 'close': expect number but found true"
+  );
+}
@@ -0,0 +1,129 @@
+use std::io;
+
+use chrono::{DateTime, Utc};
+
+use super::{
+  builder::CsvDataSourceBuilder,
+  source::CsvDataSource,
+  util::{new_io_err, process_rows, DataVecs, COLUMN_COUNT},
+};
+
+/// 内存中的一个 bar，用于从 Vec 或者迭代器构建数据源（比如单元测试和生成的模拟数据）。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bar {
+  pub timestamp: DateTime<Utc>,
+  pub open: f64,
+  pub high: f64,
+  pub low: f64,
+  pub close: f64,
+  pub volume: f64,
+}
+
+impl Bar {
+  pub fn new(
+    timestamp: DateTime<Utc>,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+  ) -> Self {
+    Self {
+      timestamp,
+      open,
+      high,
+      low,
+      close,
+      volume,
+    }
+  }
+}
+
+impl CsvDataSourceBuilder {
+  /// 从 Bar 的 Vec 或者迭代器构建数据源。不需要配置 time_field 和 time_type，
+  /// 排序、去重、截取和校验等配置和 csv 一致。
+  pub fn load_from_bars<I: IntoIterator<Item = Bar>>(self, bars: I) -> io::Result<CsvDataSource> {
+    self.check_interval()?;
+    let mut timestamp_vec = Vec::new();
+    let mut data_vecs: DataVecs = vec![Vec::new(); COLUMN_COUNT + self.extra_fields.len()];
+    for bar in bars {
+      timestamp_vec.push(bar.timestamp);
+      for (i, v) in [bar.open, bar.close, bar.high, bar.low, bar.volume]
+        .into_iter()
+        .enumerate()
+      {
+        data_vecs[i].push(v);
+      }
+    }
+    let (timestamp_vec, data_vecs, report) = process_rows(timestamp_vec, data_vecs, &self)?;
+    self.build(timestamp_vec, data_vecs, report)
+  }
+  /// 从按列存储的 Vec 构建数据源，columns 中的列名和 csv 的列名一样通过 open_field、extra_field 等配置匹配，
+  /// 忽略大小写，没有匹配的列会被忽略。每一列的长度必须和 timestamp 一致。
+  pub fn load_from_vecs(
+    self,
+    timestamp: Vec<DateTime<Utc>>,
+    columns: Vec<(&str, Vec<f64>)>,
+  ) -> io::Result<CsvDataSource> {
+    self.check_interval()?;
+    let fields = self.fields();
+    let mut data_vecs: DataVecs = vec![Vec::new(); fields.len() - 1];
+    for (name, vec) in columns {
+      if vec.len() != timestamp.len() {
+        return Err(new_io_err(format!(
+          "column '{}': expect {} values but found {}",
+          name,
+          timestamp.len(),
+          vec.len()
+        )));
+      }
+      let name = name.to_lowercase();
+      if let Some(i) = fields[1..].iter().position(|field| **field == name) {
+        data_vecs[i] = vec;
+      }
+    }
+    let (timestamp_vec, data_vecs, report) = process_rows(timestamp, data_vecs, &self)?;
+    self.build(timestamp_vec, data_vecs, report)
+  }
+}
+
+#[test]
+fn test_load_from_memory() {
+  use crate::DataLine;
+  use chrono::{Duration, TimeZone};
+
+  let t0 = Utc.with_ymd_and_hms(2022, 1, 3, 0, 0, 0).unwrap();
+  let bars = (0..3).map(|i| {
+    let p = 10. + i as f64;
+    Bar::new(t0 + Duration::days(i), p, p + 1., p - 1., p + 0.5, 100.)
+  });
+  let data = CsvDataSource::builder().load_from_bars(bars).unwrap();
+  assert_eq!(data.len(), 3);
+  assert_eq!(data.timestamp[1], t0 + Duration::days(1));
+  assert_eq!(data.close.as_slice(), &[10.5, 11.5, 12.5]);
+  assert_eq!(data.high.at(2), Some(13.));
+  assert!(data.spread.is_empty());
+
+  let data = CsvDataSource::builder()
+    .extra_field("vwap")
+    .load_from_vecs(
+      vec![t0 + Duration::days(1), t0],
+      vec![
+        ("Close", vec![2., 1.]),
+        ("VWAP", vec![1.9, 0.9]),
+        ("memo", vec![0., 0.]),
+      ],
+    )
+    .unwrap();
+  // 降序的数据被反转为升序
+  assert_eq!(data.timestamp[0], t0);
+  assert_eq!(data.close.as_slice(), &[1., 2.]);
+  assert_eq!(data.extra("vwap").unwrap().as_slice(), &[0.9, 1.9]);
+  assert!(data.open.is_empty());
+
+  let e = CsvDataSource::builder()
+    .load_from_vecs(vec![t0], vec![("close", vec![])])
+    .err()
+    .unwrap();
+  assert_eq!(e.to_string(), "column 'close': expect 1 values but found 0");
+}
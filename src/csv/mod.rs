@@ -1,10 +1,37 @@
 mod builder;
+mod cache;
+mod chart;
+#[cfg(feature = "columnar")]
+mod columnar;
+mod continuous;
+mod corporate_action;
+mod json;
+mod memory;
 mod source;
 // mod stream;
 mod broker;
+mod resample;
+mod tick;
+mod tick_bar;
+mod tick_broker;
+mod timeframe;
 mod util;
+mod validate;
 
 pub use broker::*;
+pub use builder::CsvAmbiguousTime;
 pub use builder::CsvDataSourceBuilder;
+pub use builder::CsvDuplicateTime;
+pub use builder::CsvNonexistentTime;
 pub use builder::CsvTimeType;
+pub use chart::*;
+pub use continuous::*;
+pub use corporate_action::*;
+pub use memory::*;
+pub use resample::*;
 pub use source::*;
+pub use tick::*;
+pub use tick_bar::*;
+pub use tick_broker::*;
+pub use timeframe::*;
+pub use validate::*;
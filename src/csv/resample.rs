@@ -0,0 +1,298 @@
+use std::ops::Range;
+
+use chrono::{
+  DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc,
+};
+use chrono_tz::Tz;
+
+use super::{source::CsvDataSource, util::DataVecs};
+use crate::{calendar::local_time_to_utc, TradingCalendar};
+
+/// 重采样的目标周期。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timeframe {
+  /// N 分钟线，N 需要大于 0，最好能整除一天的分钟数
+  Minutes(u32),
+  /// N 小时线，N 需要大于 0，最好能整除 24
+  Hours(u32),
+  Daily,
+  /// 周线，以周一作为一周的开始
+  Weekly,
+  Monthly,
+}
+
+/// 将 CsvDataSource 重采样为更大的周期，比如小时线转换为 4 小时线或者日线。
+/// 周期的分界按 timezone 下的本地时间计算，并且可以通过 day_close 对齐交易时段，
+/// 比如外汇通常以纽约时间 17:00 作为日线的收盘：
+/// ```ignore
+/// let data4h = CsvResampler::new(Timeframe::Hours(4))
+///   .timezone(chrono_tz::America::New_York)
+///   .day_close(NaiveTime::from_hms_opt(17, 0, 0).unwrap())
+///   .resample(&data);
+/// ```
+/// 重采样后的 bar 以周期的开始时间作为时间戳，open 取第一个 bar 的 open，close 取最后一个 bar 的 close，
+/// high 和 low 分别取最大值和最小值，volume 求和，openintrest、adjustclose、spread 和额外的数值列取最后一个 bar 的值，
+/// bid、ask 报价按同样的规则聚合。
+#[derive(Debug, Clone)]
+pub struct CsvResampler {
+  timeframe: Timeframe,
+  timezone: Tz,
+  day_close: NaiveTime,
+  calendar: Option<Box<TradingCalendar>>,
+}
+
+impl CsvResampler {
+  pub fn new(timeframe: Timeframe) -> Self {
+    if matches!(timeframe, Timeframe::Minutes(0) | Timeframe::Hours(0)) {
+      panic!("timeframe must be greater than zero");
+    }
+    Self {
+      timeframe,
+      timezone: Tz::UTC,
+      day_close: NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+      calendar: None,
+    }
+  }
+  /// 指定计算周期分界时使用的时区，默认为 UTC。
+  pub fn timezone(mut self, tz: Tz) -> Self {
+    self.timezone = tz;
+    self
+  }
+  /// 指定日线的收盘时间（timezone 下的本地时间），默认为 00:00。
+  /// 收盘时间之后的 bar 属于下一个交易日，日内周期也从收盘时间开始对齐。
+  pub fn day_close(mut self, time: NaiveTime) -> Self {
+    self.day_close = time;
+    self
+  }
+  /// 指定交易日历，用于判断注册为大周期（CsvDataSource::add_timeframe）时周期是否已经走完：
+  /// 周期内最后一个 bar 走完之后到周期结束之前没有交易时段时，周期随之走完。
+  /// 不指定时以数据本身为准，下一个 bar 属于之后的周期即认为周期已经走完。
+  pub fn calendar(mut self, calendar: TradingCalendar) -> Self {
+    self.calendar = Some(Box::new(calendar));
+    self
+  }
+  #[inline]
+  pub fn timeframe(&self) -> Timeframe {
+    self.timeframe
+  }
+
+  /// 交易日的开始时间相对于自然日 00:00 的偏移量。
+  #[inline]
+  fn day_shift(&self) -> Duration {
+    let close = self.day_close.num_seconds_from_midnight() as i64;
+    if close == 0 {
+      Duration::zero()
+    } else {
+      Duration::seconds(86400 - close)
+    }
+  }
+
+  /// 计算时间所在周期的开始时间（平移到交易日后的本地时间）。
+  fn period_start(&self, t: &DateTime<Utc>) -> NaiveDateTime {
+    let shifted = t.with_timezone(&self.timezone).naive_local() + self.day_shift();
+    let date = shifted.date();
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+    let intraday = |minutes: i64| {
+      let elapsed = (shifted - midnight).num_minutes();
+      midnight + Duration::minutes(elapsed / minutes * minutes)
+    };
+    match self.timeframe {
+      Timeframe::Minutes(n) => intraday(n as i64),
+      Timeframe::Hours(n) => intraday(n as i64 * 60),
+      Timeframe::Daily => midnight,
+      Timeframe::Weekly => midnight - Duration::days(date.weekday().num_days_from_monday() as i64),
+      Timeframe::Monthly => NaiveDate::from_ymd_opt(date.year(), date.month(), 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap(),
+    }
+  }
+
+  /// 计算时间所在周期的结束时间（UTC），即下一个周期的开始时间。
+  fn period_end(&self, t: &DateTime<Utc>) -> DateTime<Utc> {
+    let start = self.period_start(t);
+    let next_day = start.date().and_hms_opt(0, 0, 0).unwrap() + Duration::days(1);
+    let end = match self.timeframe {
+      // 日内周期在交易日的开始时间重新对齐
+      Timeframe::Minutes(n) => (start + Duration::minutes(n as i64)).min(next_day),
+      Timeframe::Hours(n) => (start + Duration::hours(n as i64)).min(next_day),
+      Timeframe::Daily => next_day,
+      Timeframe::Weekly => start + Duration::days(7),
+      Timeframe::Monthly => {
+        let date = start.date();
+        let (year, month) = match date.month() {
+          12 => (date.year() + 1, 1),
+          month => (date.year(), month + 1),
+        };
+        NaiveDate::from_ymd_opt(year, month, 1)
+          .unwrap()
+          .and_hms_opt(0, 0, 0)
+          .unwrap()
+      }
+    };
+    let end = end - self.day_shift();
+    local_time_to_utc(self.timezone, end.date(), end.time())
+  }
+
+  /// 时间为 t、周期为 interval 的 bar 走完时，所在的周期是否也已经走完：bar 的结束时间达到周期的结束时间，
+  /// 或者在周期结束之前不会再有交易。没有指定交易日历时由调用方根据 has_next 判断，
+  /// has_next 表示下一个 bar 存在并且属于之后的周期。
+  pub(crate) fn is_closed_after(
+    &self,
+    t: &DateTime<Utc>,
+    interval: Duration,
+    has_next: bool,
+  ) -> bool {
+    let (bar_end, end) = (*t + interval, self.period_end(t));
+    if bar_end >= end {
+      return true;
+    }
+    let calendar = match &self.calendar {
+      Some(calendar) => calendar,
+      None => return has_next,
+    };
+    // 跨天的交易时段在前一天开盘，因此需要检查到周期结束的后一天
+    let tz = calendar.timezone();
+    let last = end.with_timezone(&tz).date_naive() + Duration::days(1);
+    let mut date = bar_end.with_timezone(&tz).date_naive();
+    while date <= last {
+      if let Some((open, close)) = calendar.session_on(date) {
+        if open < end && close > bar_end {
+          return false;
+        }
+      }
+      date += Duration::days(1);
+    }
+    true
+  }
+
+  /// 将平移后的周期开始时间换算回 UTC 时间。
+  fn period_start_utc(&self, start: NaiveDateTime) -> Option<DateTime<Utc>> {
+    self
+      .timezone
+      .from_local_datetime(&(start - self.day_shift()))
+      .earliest()
+      .map(|dt| dt.with_timezone(&Utc))
+  }
+
+  /// 将按时间升序排列的时间序列划分到各个周期，返回每个周期的开始时间（UTC）和包含的下标区间。
+  pub(crate) fn periods(&self, timestamps: &[DateTime<Utc>]) -> Vec<(DateTime<Utc>, Range<usize>)> {
+    let mut periods = Vec::new();
+    let mut begin = 0;
+    let mut current = None;
+    for (i, t) in timestamps.iter().enumerate() {
+      let start = self.period_start(t);
+      match current {
+        Some(cur) if cur == start => {}
+        Some(cur) => {
+          // 本地时间不存在（夏令时跳变）时以周期内第一个 bar 的时间为准
+          let label = self.period_start_utc(cur).unwrap_or(timestamps[begin]);
+          periods.push((label, begin..i));
+          begin = i;
+          current = Some(start);
+        }
+        None => current = Some(start),
+      }
+    }
+    if let Some(cur) = current {
+      let label = self.period_start_utc(cur).unwrap_or(timestamps[begin]);
+      periods.push((label, begin..timestamps.len()));
+    }
+    periods
+  }
+
+  /// 重采样得到新的数据源。
+  pub fn resample(&self, data: &CsvDataSource) -> CsvDataSource {
+    let periods = self.periods(data.timestamp.as_slice());
+    let count = periods.len();
+    let mut timestamp_vec = Vec::with_capacity(count);
+    let columns = data.columns();
+    let mut data_vecs: DataVecs = (0..columns.len())
+      .map(|_| Vec::with_capacity(count))
+      .collect();
+    for (label, range) in periods {
+      timestamp_vec.push(label);
+      for (i, (vec, column)) in data_vecs.iter_mut().zip(columns.iter()).enumerate() {
+        if column.is_empty() {
+          continue;
+        }
+        let values = &column.as_slice()[range.clone()];
+        vec.push(match i {
+          0 | 7 | 11 => values[0],
+          2 | 9 | 13 => values.iter().copied().fold(f64::MIN, f64::max),
+          3 | 10 | 14 => values.iter().copied().fold(f64::MAX, f64::min),
+          4 => values.iter().sum(),
+          _ => values[values.len() - 1],
+        });
+      }
+    }
+    CsvDataSource::inner_new(timestamp_vec, data_vecs, &data.extra_names(), None)
+  }
+}
+
+impl CsvDataSource {
+  /// 按 UTC 时间将数据重采样为更大的周期，需要对齐时区或交易时段时请使用 CsvResampler。
+  pub fn resample(&self, timeframe: Timeframe) -> CsvDataSource {
+    CsvResampler::new(timeframe).resample(self)
+  }
+}
+
+#[test]
+fn test_resample() {
+  use crate::{CsvTimeType, DataLine};
+  use chrono_tz::America::New_York;
+
+  let mut content = String::from("date,open,high,low,close,volume\n");
+  // 2022-03-10 ~ 2022-03-14 的小时线，跨越周末和纽约的夏令时切换（2022-03-13）
+  let begin = Utc.with_ymd_and_hms(2022, 3, 10, 0, 0, 0).unwrap();
+  for i in 0..24 * 5 {
+    let t = begin + Duration::hours(i);
+    let v = i as f64 + 1.;
+    content.push_str(&format!(
+      "{},{},{},{},{},1\n",
+      t.format("%Y-%m-%d %H:%M"),
+      v,
+      v + 0.5,
+      v - 0.5,
+      v + 0.1
+    ));
+  }
+  let data = CsvDataSource::builder()
+    .time_field("date")
+    .time_type(CsvTimeType::Datetime("%Y-%m-%d %H:%M"))
+    .load_from_string(&content)
+    .unwrap();
+
+  let data4h = data.resample(Timeframe::Hours(4));
+  assert_eq!(data4h.len(), 30);
+  assert_eq!(data4h.timestamp[1], begin + Duration::hours(4));
+  assert_eq!(data4h.open.at(1), Some(5.));
+  assert_eq!(data4h.close.at(1), Some(8.1));
+  assert_eq!(data4h.high.at(1), Some(8.5));
+  assert_eq!(data4h.low.at(1), Some(4.5));
+  assert_eq!(data4h.volume.at(1), Some(4.));
+
+  let daily = CsvResampler::new(Timeframe::Daily)
+    .timezone(New_York)
+    .day_close(NaiveTime::from_hms_opt(17, 0, 0).unwrap())
+    .resample(&data);
+  // 第一个交易日从 2022-03-09 17:00 EST 开始，即 2022-03-09 22:00 UTC
+  assert_eq!(
+    daily.timestamp[0],
+    Utc.with_ymd_and_hms(2022, 3, 9, 22, 0, 0).unwrap()
+  );
+  assert_eq!(daily.volume.at(0), Some(22.));
+  assert_eq!(daily.close.at(0), Some(22.1));
+  // 夏令时切换后，交易日从 17:00 EDT 即 21:00 UTC 开始
+  let idx = (0..daily.len())
+    .find(|&i| daily.timestamp[i] == Utc.with_ymd_and_hms(2022, 3, 13, 21, 0, 0).unwrap())
+    .unwrap();
+  assert_eq!(daily.volume.at(idx - 1), Some(23.));
+  assert_eq!(daily.volume.at(idx), Some(24.));
+
+  let weekly = data.resample(Timeframe::Weekly);
+  assert_eq!(weekly.len(), 2);
+  assert_eq!(weekly.volume.at(0), Some(96.));
+  assert_eq!(weekly.open.at(1), Some(97.));
+  assert_eq!(data.resample(Timeframe::Monthly).len(), 1);
+}
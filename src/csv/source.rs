@@ -1,9 +1,20 @@
-use std::ops::Index;
+use std::{
+  io,
+  ops::{Index, Range},
+  path::PathBuf,
+  rc::Rc,
+  time::SystemTime,
+};
 
 use chrono::{DateTime, Utc};
 
-use crate::data::DataSource;
-use crate::{Broker, CsvDataSourceBuilder, DataLine, DataLineFeed, Strategy};
+use super::util::{DataVecs, COLUMN_COUNT};
+use crate::data::{DataSource, SingleDataSource};
+use crate::{
+  Broker, CorporateAction, CsvDataSourceBuilder, CsvTimeframe, CsvValidationReport, DataLine,
+  DataLineFeed, IndicatorGraph, IndicatorLine, IndicatorRef, RollEvent, Strategy, Timer,
+  TimerScheduler, TradingCalendar,
+};
 //
 // macro_rules! gen_mem_data_source {
 //   ($struct_name: ident, $($name: ident: $idx: literal), +) => {
@@ -36,68 +47,432 @@ use crate::{Broker, CsvDataSourceBuilder, DataLine, DataLineFeed, Strategy};
 // );
 
 pub struct CsvTimeLine {
-  data: Vec<DateTime<Utc>>,
+  data: Rc<Vec<DateTime<Utc>>>,
+  range: Range<usize>,
 }
 
 impl CsvTimeLine {
+  pub(crate) fn new(data: Vec<DateTime<Utc>>) -> Self {
+    let len = data.len();
+    Self {
+      data: Rc::new(data),
+      range: 0..len,
+    }
+  }
   #[inline(always)]
   pub fn at(&self, index: usize) -> Option<DateTime<Utc>> {
-    self.data.get(index).copied()
+    self.as_slice().get(index).copied()
+  }
+  #[inline(always)]
+  pub fn as_slice(&self) -> &[DateTime<Utc>] {
+    &self.data[self.range.clone()]
+  }
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.range.len()
+  }
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.range.is_empty()
+  }
+  /// 共享底层数据，返回 range 范围内的子序列
+  fn slice(&self, range: Range<usize>) -> Self {
+    Self {
+      data: self.data.clone(),
+      range: self.range.start + range.start..self.range.start + range.end,
+    }
   }
 }
 impl Index<usize> for CsvTimeLine {
   type Output = DateTime<Utc>;
   #[inline(always)]
   fn index(&self, index: usize) -> &Self::Output {
-    &self.data[index]
+    &self.as_slice()[index]
   }
 }
 
+/// Csv 数据的一列。多个 CsvDataLine 可以共享同一份底层数据（比如 CsvDataSource::slice 得到的数据源）。
+/// 文件中不存在的列是空的 CsvDataLine，at 总是返回 None。
 pub struct CsvDataLine {
-  data: Vec<f64>,
+  data: Rc<Vec<f64>>,
+  range: Range<usize>,
+}
+
+impl CsvDataLine {
+  pub(crate) fn new(data: Vec<f64>) -> Self {
+    let len = data.len();
+    Self {
+      data: Rc::new(data),
+      range: 0..len,
+    }
+  }
+  #[inline(always)]
+  pub fn as_slice(&self) -> &[f64] {
+    &self.data[self.range.clone()]
+  }
+  #[inline(always)]
+  pub fn len(&self) -> usize {
+    self.range.len()
+  }
+  #[inline(always)]
+  pub fn is_empty(&self) -> bool {
+    self.range.is_empty()
+  }
+  fn slice(&self, range: Range<usize>) -> Self {
+    if self.is_empty() {
+      return Self {
+        data: self.data.clone(),
+        range: 0..0,
+      };
+    }
+    Self {
+      data: self.data.clone(),
+      range: self.range.start + range.start..self.range.start + range.end,
+    }
+  }
 }
 
 impl DataLine for CsvDataLine {
   #[inline(always)]
   fn at(&self, index: usize) -> Option<f64> {
-    self.data.get(index).copied()
+    self.as_slice().get(index).copied()
   }
 }
 
 impl DataLineFeed for CsvDataLine {
   #[inline(always)]
   fn inner(&self) -> (&[f64], usize) {
-    (&self.data, 0)
+    (self.as_slice(), 0)
+  }
+}
+
+/// bid 或 ask 的 OHLC 报价，文件中不存在的列是空的 CsvDataLine。
+pub struct CsvQuote {
+  pub open: CsvDataLine,
+  pub close: CsvDataLine,
+  pub high: CsvDataLine,
+  pub low: CsvDataLine,
+}
+
+impl CsvQuote {
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.open.is_empty() && self.close.is_empty()
+  }
+  fn slice(&self, range: Range<usize>) -> Self {
+    Self {
+      open: self.open.slice(range.clone()),
+      close: self.close.slice(range.clone()),
+      high: self.high.slice(range.clone()),
+      low: self.low.slice(range),
+    }
   }
 }
 
+/// 报价的方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteSide {
+  Bid,
+  Ask,
+}
+
+/// bar 数据源。open、close、high、low 为中间价（或者成交价），
+/// 可选的 bid、ask 报价可以由单独的列提供，也可以由 spread 列（以价格为单位的完整点差）换算：
+/// bid = 中间价 - spread / 2，ask = 中间价 + spread / 2。
 pub struct CsvDataSource {
   pub(crate) offset: usize,
   pub timestamp: CsvTimeLine,
-  pub close: CsvDataLine,
   pub open: CsvDataLine,
+  pub close: CsvDataLine,
+  pub high: CsvDataLine,
+  pub low: CsvDataLine,
+  pub volume: CsvDataLine,
+  pub openintrest: CsvDataLine,
+  pub adjustclose: CsvDataLine,
+  pub bid: CsvQuote,
+  pub ask: CsvQuote,
+  pub spread: CsvDataLine,
+  pub(crate) extras: Vec<(String, CsvDataLine)>,
+  pub(crate) report: Option<CsvValidationReport>,
+  pub(crate) timeframes: Vec<CsvTimeframe>,
+  /// 原始 csv 文件及其加载时的修改时间，用于检查缓存是否过期
+  pub(crate) source: Option<(PathBuf, SystemTime)>,
+  /// 由 broker 处理的公司行为及其除权日当天或之后的第一个 bar 的下标
+  pub(crate) actions: Vec<(usize, CorporateAction)>,
+  /// 连续合约的换月事件，按 index 排列
+  pub(crate) rolls: Vec<RollEvent>,
+  /// 通过 CsvDataSourceBuilder::calendar 指定的交易日历
+  pub(crate) calendar: Option<Rc<TradingCalendar>>,
+  /// 通过 add_timer 注册的定时器及其名称
+  pub(crate) timers: Vec<(String, Timer)>,
+  /// 通过 set_indicators 注册的指标图
+  pub(crate) indicators: Option<IndicatorGraph>,
 }
 
 impl CsvDataSource {
   pub fn builder() -> CsvDataSourceBuilder {
     CsvDataSourceBuilder::new()
   }
-  pub(super) fn inner_new(timestamp_vec: Vec<DateTime<Utc>>, data_vecs: [Vec<f64>; 7]) -> Self {
-    // let mut data_vecs = data_vecs.map(|v| Some(v));
-    let mut data_vecs = data_vecs.into_iter();
+  /// 获取加载数据时的校验报告，只有开启了 CsvDataSourceBuilder::validate 时才有。
+  pub fn report(&self) -> Option<&CsvValidationReport> {
+    self.report.as_ref()
+  }
+  /// bar 的数量
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.timestamp.len()
+  }
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.timestamp.is_empty()
+  }
+  pub(crate) fn inner_new(
+    timestamp_vec: Vec<DateTime<Utc>>,
+    data_vecs: DataVecs,
+    extra_names: &[String],
+    report: Option<CsvValidationReport>,
+  ) -> Self {
+    assert_eq!(data_vecs.len(), COLUMN_COUNT + extra_names.len());
+    let mut lines = data_vecs.into_iter().map(CsvDataLine::new);
+    let mut next = || lines.next().unwrap();
+    let [open, close, high, low, volume, openintrest, adjustclose] = [(); 7].map(|_| next());
+    let [bid_open, bid_close, bid_high, bid_low, ask_open, ask_close, ask_high, ask_low, spread] =
+      [(); 9].map(|_| next());
+    let extras = extra_names
+      .iter()
+      .map(|name| (name.clone(), next()))
+      .collect();
+    let bid = CsvQuote {
+      open: bid_open,
+      close: bid_close,
+      high: bid_high,
+      low: bid_low,
+    };
+    let ask = CsvQuote {
+      open: ask_open,
+      close: ask_close,
+      high: ask_high,
+      low: ask_low,
+    };
+    Self {
+      offset: 0,
+      timestamp: CsvTimeLine::new(timestamp_vec),
+      open,
+      close,
+      high,
+      low,
+      volume,
+      openintrest,
+      adjustclose,
+      bid,
+      ask,
+      spread,
+      extras,
+      report,
+      timeframes: Vec::new(),
+      source: None,
+      actions: Vec::new(),
+      rolls: Vec::new(),
+      calendar: None,
+      timers: Vec::new(),
+      indicators: None,
+    }
+  }
+  /// 获取通过 CsvDataSourceBuilder::extra_field 加载的额外数值列，忽略大小写
+  pub fn extra(&self, name: &str) -> Option<&CsvDataLine> {
+    let name = name.to_lowercase();
+    self
+      .extras
+      .iter()
+      .find(|(n, _)| *n == name)
+      .map(|(_, line)| line)
+  }
+  /// 以 CorporateActionMode::Broker 方式加载的公司行为，以及除权日当天或之后的第一个 bar 的下标。
+  /// 公司行为在该 bar 的定时器和 Strategy::next 之前通过 Broker::on_corporate_action 交给 broker 处理。
+  pub fn corporate_actions(&self) -> &[(usize, CorporateAction)] {
+    &self.actions
+  }
+  /// 通过 ContinuousFuturesBuilder 拼接的连续合约的全部换月事件
+  pub fn rolls(&self) -> &[RollEvent] {
+    &self.rolls
+  }
+  /// 第 index 个 bar 是否是换月后新合约的第一个 bar，是则返回对应的换月事件
+  pub fn roll_at(&self, index: usize) -> Option<&RollEvent> {
+    self
+      .rolls
+      .binary_search_by_key(&index, |event| event.index)
+      .ok()
+      .map(|i| &self.rolls[i])
+  }
+  /// 通过 CsvDataSourceBuilder::calendar 指定的交易日历
+  pub fn calendar(&self) -> Option<&TradingCalendar> {
+    self.calendar.as_deref()
+  }
+  /// 第 index 个 bar 是否在交易时段内，没有指定交易日历时总是返回 true
+  pub fn is_session_open(&self, index: usize) -> bool {
+    match &self.calendar {
+      Some(calendar) => calendar.is_session_open(&self.timestamp[index]),
+      None => true,
+    }
+  }
+  /// 第 index 个 bar 距离所在交易时段收盘的分钟数，没有指定交易日历或者休市时返回 None
+  pub fn minutes_to_close(&self, index: usize) -> Option<i64> {
+    self
+      .calendar
+      .as_ref()
+      .and_then(|calendar| calendar.minutes_to_close(&self.timestamp[index]))
+  }
+  /// 注册定时器，bar 跨过计划触发时间时调用 Strategy::on_timer。同名的定时器会被替换。
+  pub fn add_timer(&mut self, name: &str, timer: Timer) {
+    self.timers.retain(|(n, _)| n != name);
+    self.timers.push((name.to_string(), timer));
+  }
+  /// 注册指标图，指标图在 Strategy::feed 之前按依赖关系计算。指标图存在循环依赖或者未指定输入的指标时返回错误。
+  pub fn set_indicators(&mut self, graph: IndicatorGraph) -> io::Result<()> {
+    graph.validate()?;
+    self.indicators = Some(graph);
+    Ok(())
+  }
+  /// 通过 set_indicators 注册的指标图
+  pub fn indicators(&self) -> Option<&IndicatorGraph> {
+    self.indicators.as_ref()
+  }
+  /// 指标图中指标的输出，没有注册指标图时 panic
+  pub fn indicator(&self, r: IndicatorRef) -> &IndicatorLine {
+    self
+      .indicators
+      .as_ref()
+      .expect("indicator graph not registered")
+      .line(r)
+  }
+  /// 额外数值列的名称（小写）
+  pub fn extra_names(&self) -> Vec<String> {
+    self.extras.iter().map(|(name, _)| name.clone()).collect()
+  }
+  /// 按 data_vecs 的顺序返回所有的数据列，包括额外的数值列
+  pub(crate) fn columns(&self) -> Vec<&CsvDataLine> {
+    let mut columns = vec![
+      &self.open,
+      &self.close,
+      &self.high,
+      &self.low,
+      &self.volume,
+      &self.openintrest,
+      &self.adjustclose,
+      &self.bid.open,
+      &self.bid.close,
+      &self.bid.high,
+      &self.bid.low,
+      &self.ask.open,
+      &self.ask.close,
+      &self.ask.high,
+      &self.ask.low,
+      &self.spread,
+    ];
+    columns.extend(self.extras.iter().map(|(_, line)| line));
+    columns
+  }
+  /// 是否有 bid、ask 报价（单独的列或者 spread 列）
+  #[inline]
+  pub fn has_quotes(&self) -> bool {
+    !(self.bid.is_empty() || self.ask.is_empty()) || !self.spread.is_empty()
+  }
+  /// 获取第 index 个 bar 的 bid 或 ask 报价，mid 为对应的中间价列（比如 open），quote 为对应的报价列。
+  /// 没有报价列时使用 spread 换算，都没有时返回中间价。
+  #[inline]
+  fn quote_at(
+    &self,
+    side: QuoteSide,
+    mid: &CsvDataLine,
+    quote: &CsvDataLine,
+    index: usize,
+  ) -> Option<f64> {
+    quote.at(index).or_else(|| {
+      let mid = mid.at(index)?;
+      let half = self.spread.at(index).unwrap_or(0.) / 2.;
+      Some(match side {
+        QuoteSide::Bid => mid - half,
+        QuoteSide::Ask => mid + half,
+      })
+    })
+  }
+  /// 第 index 个 bar 的 open 报价
+  #[inline]
+  pub fn quote_open(&self, side: QuoteSide, index: usize) -> Option<f64> {
+    match side {
+      QuoteSide::Bid => self.quote_at(side, &self.open, &self.bid.open, index),
+      QuoteSide::Ask => self.quote_at(side, &self.open, &self.ask.open, index),
+    }
+  }
+  /// 第 index 个 bar 的 close 报价
+  #[inline]
+  pub fn quote_close(&self, side: QuoteSide, index: usize) -> Option<f64> {
+    match side {
+      QuoteSide::Bid => self.quote_at(side, &self.close, &self.bid.close, index),
+      QuoteSide::Ask => self.quote_at(side, &self.close, &self.ask.close, index),
+    }
+  }
+  /// 按 bar 的下标截取数据源，新的数据源和当前数据源共享底层的列数据。
+  /// 已经注册的更大周期和指标图不会被保留，需要重新注册。
+  pub fn slice_rows(&self, range: Range<usize>) -> Self {
+    assert!(
+      range.start <= range.end && range.end <= self.len(),
+      "slice range out of bounds"
+    );
     Self {
       offset: 0,
-      timestamp: CsvTimeLine {
-        data: timestamp_vec,
-      },
-      open: CsvDataLine {
-        data: data_vecs.next().unwrap(),
-      },
-      close: CsvDataLine {
-        data: data_vecs.next().unwrap(),
-      },
+      timestamp: self.timestamp.slice(range.clone()),
+      open: self.open.slice(range.clone()),
+      close: self.close.slice(range.clone()),
+      high: self.high.slice(range.clone()),
+      low: self.low.slice(range.clone()),
+      volume: self.volume.slice(range.clone()),
+      openintrest: self.openintrest.slice(range.clone()),
+      adjustclose: self.adjustclose.slice(range.clone()),
+      bid: self.bid.slice(range.clone()),
+      ask: self.ask.slice(range.clone()),
+      spread: self.spread.slice(range.clone()),
+      extras: self
+        .extras
+        .iter()
+        .map(|(name, line)| (name.clone(), line.slice(range.clone())))
+        .collect(),
+      report: None,
+      timeframes: Vec::new(),
+      source: None,
+      actions: self
+        .actions
+        .iter()
+        .filter(|(i, _)| range.contains(i))
+        .map(|(i, action)| (i - range.start, *action))
+        .collect(),
+      rolls: self
+        .rolls
+        .iter()
+        .filter(|event| range.contains(&event.index))
+        .map(|event| RollEvent {
+          index: event.index - range.start,
+          ..event.clone()
+        })
+        .collect(),
+      calendar: self.calendar.clone(),
+      timers: self.timers.clone(),
+      indicators: None,
     }
   }
+  /// 获取时间在 [from, to) 区间内的数据，新的数据源和当前数据源共享底层的列数据。
+  pub fn slice(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+    let ts = self.timestamp.as_slice();
+    let start = ts.partition_point(|t| *t < from);
+    let end = ts.partition_point(|t| *t < to).max(start);
+    self.slice_rows(start..end)
+  }
+  /// 以 date 为分界拆分数据源，比如用于拆分训练集和测试集。
+  /// 第一个数据源包含 date 之前的数据，第二个数据源包含 date 及之后的数据。
+  pub fn split_at(&self, date: DateTime<Utc>) -> (Self, Self) {
+    let mid = self.timestamp.as_slice().partition_point(|t| *t < date);
+    (self.slice_rows(0..mid), self.slice_rows(mid..self.len()))
+  }
 }
 
 impl DataSource for CsvDataSource {
@@ -106,15 +481,91 @@ impl DataSource for CsvDataSource {
     strat: &mut S,
     broker: &mut B,
   ) -> bool {
+    if let Some(mut graph) = self.indicators.take() {
+      if let Err(e) = graph.compute(self) {
+        panic!("Could not compute indicators, err: {}", e);
+      }
+      self.indicators = Some(graph);
+    }
     strat.feed(self);
-    let len = self.timestamp.data.len();
+    let len = self.len();
+    let mut scheduler = self
+      .timestamp
+      .at(self.offset)
+      .map(|start| TimerScheduler::new(&self.timers, &start));
     while self.offset < len {
+      // 公司行为先于策略生效，策略在除权的 bar 上看到的持仓和价格一致
+      for (_, action) in self.actions.iter().filter(|(i, _)| *i == self.offset) {
+        broker.on_corporate_action(action, self);
+      }
+      if let Some(scheduler) = scheduler.as_mut() {
+        for event in scheduler.poll(self.offset, &self.timestamp[self.offset]) {
+          strat.on_timer(&event, self, broker);
+        }
+      }
       strat.next(self.offset, self, broker);
       self.offset += 1;
     }
     false
   }
+}
+
+impl SingleDataSource for CsvDataSource {
+  /// 按最近一个已经处理的 bar 的 close 报价计算仓位的当前现金价值，
+  /// 即平仓时能够成交的价格：多仓按 bid 价，空仓按 ask 价，没有报价时按 close 价。
+  /// 当前 bar 的公司行为已经由 broker 处理，报价按除权后的价格计算。
   fn calc_position_value(&self, position_size: isize) -> f64 {
-    position_size as f64 * self.close.data[self.offset - 1]
+    let side = if position_size > 0 {
+      QuoteSide::Bid
+    } else {
+      QuoteSide::Ask
+    };
+    let index = self.offset.max(1) - 1;
+    let mut price = self.quote_close(side, index).unwrap();
+    if index + 1 == self.offset {
+      for (_, action) in self.actions.iter().filter(|(i, _)| *i == self.offset) {
+        price = match *action {
+          CorporateAction::Split(ratio) => price / ratio,
+          CorporateAction::Dividend(amount) => price - amount,
+        };
+      }
+    }
+    position_size as f64 * price
   }
 }
+
+#[test]
+fn test_slice_data_source() {
+  use crate::CsvTimeType;
+  use chrono::TimeZone;
+
+  let content = "date,close\n2022-01-01,1\n2022-01-02,2\n2022-01-03,3\n2022-01-04,4";
+  let builder = || {
+    CsvDataSource::builder()
+      .time_field("date")
+      .time_type(CsvTimeType::Date("%Y-%m-%d"))
+  };
+  let date = |d: u32| Utc.with_ymd_and_hms(2022, 1, d, 0, 0, 0).unwrap();
+  let data = builder().load_from_string(content).unwrap();
+
+  let sliced = data.slice(date(2), date(4));
+  assert_eq!(sliced.len(), 2);
+  assert_eq!(sliced.timestamp[0], date(2));
+  assert_eq!(sliced.close.as_slice(), &[2., 3.]);
+  assert!(Rc::ptr_eq(&sliced.close.data, &data.close.data));
+  assert_eq!(sliced.open.at(0), None);
+  // 截取的数据源可以再次截取
+  assert_eq!(sliced.slice(date(3), date(9)).close.as_slice(), &[3.]);
+  assert!(data.slice(date(5), date(9)).is_empty());
+
+  let (train, test) = data.split_at(date(3));
+  assert_eq!(train.close.as_slice(), &[1., 2.]);
+  assert_eq!(test.close.as_slice(), &[3., 4.]);
+  assert_eq!(test.timestamp.at(0), Some(date(3)));
+
+  let data = builder()
+    .between(date(2), date(4))
+    .load_from_string(content)
+    .unwrap();
+  assert_eq!(data.close.as_slice(), &[2., 3.]);
+}
@@ -0,0 +1,256 @@
+use std::{fs::read_to_string, io, path::Path};
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+
+use crate::data::{DataSource, SingleDataSource};
+use crate::{Broker, CsvDataLine, CsvTimeLine, DataLine, Strategy};
+
+use super::{
+  builder::{
+    CsvAmbiguousTime, CsvDataSourceBuilder, CsvDuplicateTime, CsvNonexistentTime, CsvTimeType,
+  },
+  util::{
+    filter_rows, new_io_err, new_io_err_str, new_row_err, numbered_lines, order_rows, parse_f64,
+    parse_time_field, DataVecs, COLUMN_COUNT,
+  },
+};
+
+/// Tick 数据源，每一行是一笔报价或成交（timestamp, bid, ask, last, size）。
+/// 策略可以直接在 tick 上运行（配合 CsvTickBroker），也可以通过 to_bars 聚合为 bar 数据源。
+/// 文件中不存在的列是空的 CsvDataLine。
+pub struct CsvTickDataSource {
+  pub(crate) offset: usize,
+  pub timestamp: CsvTimeLine,
+  pub bid: CsvDataLine,
+  pub ask: CsvDataLine,
+  pub last: CsvDataLine,
+  pub size: CsvDataLine,
+}
+
+impl CsvTickDataSource {
+  pub fn builder() -> CsvTickDataSourceBuilder {
+    CsvTickDataSourceBuilder::new()
+  }
+  /// tick 的数量
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.timestamp.len()
+  }
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.timestamp.is_empty()
+  }
+  /// 第 index 个 tick 的价格，有 last 列时取 last，否则取 bid 和 ask 的中间价。
+  #[inline]
+  pub fn price(&self, index: usize) -> Option<f64> {
+    self.last.at(index).or_else(|| {
+      self
+        .bid
+        .at(index)
+        .zip(self.ask.at(index))
+        .map(|(bid, ask)| (bid + ask) / 2.)
+    })
+  }
+}
+
+impl DataSource for CsvTickDataSource {
+  fn read<B: Broker<DS = Self>, S: Strategy<DS = Self, BK = B>>(
+    &mut self,
+    strat: &mut S,
+    broker: &mut B,
+  ) -> bool {
+    strat.feed(self);
+    let len = self.len();
+    while self.offset < len {
+      strat.next(self.offset, self, broker);
+      self.offset += 1;
+    }
+    false
+  }
+}
+
+impl SingleDataSource for CsvTickDataSource {
+  /// 按最近一个已经处理的 tick 计算仓位的现金价值，多仓按 bid 价、空仓按 ask 价，即平仓时能够成交的价格
+  fn calc_position_value(&self, position_size: isize) -> f64 {
+    if position_size == 0 || self.is_empty() {
+      return 0.;
+    }
+    let index = self.offset.clamp(1, self.len()) - 1;
+    let price = if position_size > 0 {
+      self.bid.at(index)
+    } else {
+      self.ask.at(index)
+    };
+    position_size as f64 * price.or_else(|| self.price(index)).unwrap()
+  }
+}
+
+/// CsvTickDataSource 的构建器，time 列的配置和 CsvDataSourceBuilder 一致。
+pub struct CsvTickDataSourceBuilder {
+  time: CsvDataSourceBuilder,
+  bid_field: String,
+  ask_field: String,
+  last_field: String,
+  size_field: String,
+}
+
+macro_rules! gen_field {
+  ($($name: ident : $default_value: literal), +) => {
+    $(
+    #[doc = concat!("配置 ", stringify!($name)," 字段，忽略大小写，默认为 ", $default_value)]
+    pub fn $name(mut self, field: &str) -> Self {
+      self.$name = field.to_lowercase();
+      self
+    }
+    )*
+  };
+}
+
+impl CsvTickDataSourceBuilder {
+  pub fn new() -> Self {
+    Self {
+      time: CsvDataSourceBuilder::new(),
+      bid_field: "bid".to_string(),
+      ask_field: "ask".to_string(),
+      last_field: "last".to_string(),
+      size_field: "size".to_string(),
+    }
+  }
+  gen_field!(bid_field: "bid", ask_field: "ask", last_field: "last", size_field: "size");
+  /// 配置 time 字段，忽略大小写
+  pub fn time_field(mut self, field: &str) -> Self {
+    self.time = self.time.time_field(field);
+    self
+  }
+  /// 参见 CsvDataSourceBuilder::time_type
+  pub fn time_type(mut self, time_type: CsvTimeType) -> Self {
+    self.time = self.time.time_type(time_type);
+    self
+  }
+  /// 参见 CsvDataSourceBuilder::timezone
+  pub fn timezone(mut self, tz: Tz) -> Self {
+    self.time = self.time.timezone(tz);
+    self
+  }
+  /// 参见 CsvDataSourceBuilder::ambiguous_time
+  pub fn ambiguous_time(mut self, policy: CsvAmbiguousTime) -> Self {
+    self.time = self.time.ambiguous_time(policy);
+    self
+  }
+  /// 参见 CsvDataSourceBuilder::nonexistent_time
+  pub fn nonexistent_time(mut self, policy: CsvNonexistentTime) -> Self {
+    self.time = self.time.nonexistent_time(policy);
+    self
+  }
+  /// 参见 CsvDataSourceBuilder::sort_rows
+  pub fn sort_rows(mut self, sort: bool) -> Self {
+    self.time = self.time.sort_rows(sort);
+    self
+  }
+  /// 指定时间重复的 tick 的处理策略，默认为 Keep（同一时间通常会有多笔 tick）。
+  pub fn duplicate_time(mut self, policy: CsvDuplicateTime) -> Self {
+    self.time = self.time.duplicate_time(policy);
+    self
+  }
+  /// 只加载时间在 [from, to) 区间内的数据。
+  pub fn between(mut self, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+    self.time = self.time.between(from, to);
+    self
+  }
+  pub fn load_from_file(self, file: &Path) -> io::Result<CsvTickDataSource> {
+    let content = read_to_string(file)?;
+    self.load_from_string(&content)
+  }
+  pub fn load_from_string(self, content: &str) -> io::Result<CsvTickDataSource> {
+    self.load_from_lines(content.lines())
+  }
+  pub fn load_from_lines<'a, T: Iterator<Item = &'a str>>(
+    self,
+    lines: T,
+  ) -> io::Result<CsvTickDataSource> {
+    if self.time.time_field.is_empty() {
+      return Err(new_io_err_str("time_field config missing"));
+    }
+    if matches!(self.time.time_type, CsvTimeType::Unknown) {
+      return Err(new_io_err_str("time_type config missing"));
+    }
+    let mut lines = numbered_lines(lines);
+    let (_, header_line) = lines
+      .next()
+      .ok_or_else(|| new_io_err_str("csv missing header line"))?;
+    let columns: Vec<&str> = header_line.split(',').map(|s| s.trim()).collect();
+    // field indeies of: timestamp, bid, ask, last, size
+    let fields = [
+      &self.time.time_field,
+      &self.bid_field,
+      &self.ask_field,
+      &self.last_field,
+      &self.size_field,
+    ];
+    let idx_arr = fields.map(|field| columns.iter().position(|c| c.to_lowercase().eq(field)));
+    if idx_arr[0].is_none() {
+      return Err(new_io_err_str("csv header miss timestamp field"));
+    }
+    if idx_arr[3].is_none() && (idx_arr[1].is_none() || idx_arr[2].is_none()) {
+      return Err(new_io_err_str(
+        "csv header miss price fields, either last or both bid and ask are required",
+      ));
+    }
+
+    let mut timestamp_vec = Vec::new();
+    // 对齐 CsvDataSource 的 data_vecs 以便复用排序和截取，只使用前 4 列：bid, ask, last, size
+    let mut data_vecs: DataVecs = vec![Vec::new(); COLUMN_COUNT];
+    for (row, line) in lines {
+      let segs: Vec<&str> = line.split(',').collect();
+      for (i, idx) in idx_arr.iter().enumerate() {
+        let idx = match idx {
+          Some(idx) => *idx,
+          None => continue,
+        };
+        let seg = segs.get(idx).ok_or_else(|| {
+          new_io_err(format!(
+            "row {}: expect at least {} columns but found {}",
+            row,
+            idx + 1,
+            segs.len()
+          ))
+        })?;
+        if i == 0 {
+          let v = parse_time_field(&self.time, seg).map_err(|e| {
+            new_row_err(
+              row,
+              columns[idx],
+              format!("{}, please check 'time_type' config.", e),
+            )
+          })?;
+          timestamp_vec.push(v);
+        } else {
+          data_vecs[i - 1].push(parse_f64(seg).map_err(|e| new_row_err(row, columns[idx], e))?);
+        }
+      }
+    }
+    let (timestamp_vec, data_vecs) =
+      order_rows(timestamp_vec, data_vecs, &self.time, &mut Vec::new())?;
+    let (timestamp_vec, data_vecs) = match self.time.between {
+      Some((from, to)) => filter_rows(timestamp_vec, data_vecs, from, to),
+      None => (timestamp_vec, data_vecs),
+    };
+    let mut lines = data_vecs.into_iter().map(CsvDataLine::new);
+    let [bid, ask, last, size] = [(); 4].map(|_| lines.next().unwrap());
+    Ok(CsvTickDataSource {
+      offset: 0,
+      timestamp: CsvTimeLine::new(timestamp_vec),
+      bid,
+      ask,
+      last,
+      size,
+    })
+  }
+}
+
+impl Default for CsvTickDataSourceBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
@@ -0,0 +1,170 @@
+use std::ops::Range;
+
+use super::{
+  resample::CsvResampler,
+  source::CsvDataSource,
+  tick::CsvTickDataSource,
+  util::{DataVecs, COLUMN_COUNT},
+};
+
+/// tick 聚合为 bar 的方式。
+#[derive(Debug, Clone)]
+pub enum TickBar {
+  /// 时间 bar，按 CsvResampler 的周期划分，以周期的开始时间作为 bar 的时间戳
+  Time(CsvResampler),
+  /// 每 N 个 tick 生成一个 bar
+  Ticks(usize),
+  /// 成交量累计达到阈值时生成一个 bar，需要 size 列
+  Volume(f64),
+  /// 成交额（price * size）累计达到阈值时生成一个 bar，需要 size 列
+  Dollar(f64),
+}
+
+impl CsvTickDataSource {
+  /// 将 tick 按 bar 的类型划分为连续的下标区间。
+  fn bar_ranges(&self, bar: &TickBar) -> Vec<Range<usize>> {
+    let len = self.len();
+    let threshold_ranges = |weight: &dyn Fn(usize) -> f64, threshold: f64| {
+      if threshold <= 0. {
+        panic!("bar threshold must be greater than zero");
+      }
+      if self.size.is_empty() {
+        panic!("volume and dollar bars require the size column");
+      }
+      let mut ranges = Vec::new();
+      let mut begin = 0;
+      let mut acc = 0.;
+      for i in 0..len {
+        acc += weight(i);
+        if acc >= threshold {
+          ranges.push(begin..i + 1);
+          begin = i + 1;
+          acc = 0.;
+        }
+      }
+      ranges
+    };
+    let mut ranges = match bar {
+      TickBar::Time(resampler) => {
+        return resampler
+          .periods(self.timestamp.as_slice())
+          .into_iter()
+          .map(|(_, range)| range)
+          .collect();
+      }
+      TickBar::Ticks(n) => {
+        if *n == 0 {
+          panic!("bar threshold must be greater than zero");
+        }
+        (0..len).step_by(*n).map(|i| i..(i + n).min(len)).collect()
+      }
+      TickBar::Volume(threshold) => threshold_ranges(&|i| self.size.as_slice()[i], *threshold),
+      TickBar::Dollar(threshold) => threshold_ranges(
+        &|i| self.size.as_slice()[i] * self.price(i).unwrap(),
+        *threshold,
+      ),
+    };
+    // 最后未达到阈值的 tick 也作为一个 bar
+    let end = ranges.last().map_or(0, |r| r.end);
+    if end < len {
+      ranges.push(end..len);
+    }
+    ranges
+  }
+
+  /// 将 tick 聚合为 bar 数据源，所有基于 CsvDataSource 的指标都可以直接使用聚合后的数据。
+  /// open、close、high、low 基于 tick 的价格（参见 price）计算，volume 为 size 的合计，
+  /// 没有 size 列时为 tick 的数量。tick 有 bid、ask 列时，bar 同时包含 bid、ask 的 OHLC 报价。
+  /// 除时间 bar 外，bar 的时间戳为 bar 内第一个 tick 的时间。
+  pub fn to_bars(&self, bar: TickBar) -> CsvDataSource {
+    let ranges = self.bar_ranges(&bar);
+    let labels: Vec<_> = match &bar {
+      TickBar::Time(resampler) => resampler
+        .periods(self.timestamp.as_slice())
+        .into_iter()
+        .map(|(label, _)| label)
+        .collect(),
+      _ => ranges.iter().map(|r| self.timestamp[r.start]).collect(),
+    };
+    let prices: Vec<f64> = (0..self.len()).map(|i| self.price(i).unwrap()).collect();
+    let has_quotes = !(self.bid.is_empty() || self.ask.is_empty());
+    let count = ranges.len();
+    let mut data_vecs: DataVecs = (0..COLUMN_COUNT)
+      .map(|_| Vec::with_capacity(count))
+      .collect();
+    // 按 open, close, high, low 的顺序写入 data_vecs 中从 start 开始的 4 列
+    let push_ohlc = |data_vecs: &mut DataVecs, start: usize, values: &[f64]| {
+      data_vecs[start].push(values[0]);
+      data_vecs[start + 1].push(values[values.len() - 1]);
+      data_vecs[start + 2].push(values.iter().copied().fold(f64::MIN, f64::max));
+      data_vecs[start + 3].push(values.iter().copied().fold(f64::MAX, f64::min));
+    };
+    for range in ranges {
+      push_ohlc(&mut data_vecs, 0, &prices[range.clone()]);
+      if has_quotes {
+        push_ohlc(&mut data_vecs, 7, &self.bid.as_slice()[range.clone()]);
+        push_ohlc(&mut data_vecs, 11, &self.ask.as_slice()[range.clone()]);
+      }
+      data_vecs[4].push(if self.size.is_empty() {
+        range.len() as f64
+      } else {
+        self.size.as_slice()[range].iter().sum()
+      });
+    }
+    CsvDataSource::inner_new(labels, data_vecs, &[], None)
+  }
+}
+
+#[test]
+fn test_tick_bars() {
+  use crate::{CsvTimeType, DataLine, Timeframe};
+  use chrono::{Duration, TimeZone, Utc};
+
+  let mut content = String::from("time,bid,ask,size\n");
+  let begin = Utc.with_ymd_and_hms(2022, 3, 10, 0, 0, 0).unwrap();
+  // 每 20 秒一个 tick，共 10 个 tick，中间价为 1, 2, ..., 10
+  for i in 0..10 {
+    let t = begin + Duration::seconds(i * 20);
+    let mid = i as f64 + 1.;
+    content.push_str(&format!(
+      "{},{},{},{}\n",
+      t.timestamp_millis(),
+      mid - 0.5,
+      mid + 0.5,
+      i + 1
+    ));
+  }
+  let ticks = CsvTickDataSource::builder()
+    .time_field("time")
+    .time_type(CsvTimeType::Millsecond)
+    .load_from_string(&content)
+    .unwrap();
+  assert_eq!(ticks.len(), 10);
+  assert!(ticks.last.is_empty());
+  assert_eq!(ticks.price(3), Some(4.));
+
+  let bars = ticks.to_bars(TickBar::Time(CsvResampler::new(Timeframe::Minutes(1))));
+  assert_eq!(bars.len(), 4);
+  assert_eq!(bars.timestamp[1], begin + Duration::minutes(1));
+  assert_eq!(bars.open.as_slice(), &[1., 4., 7., 10.]);
+  assert_eq!(bars.close.as_slice(), &[3., 6., 9., 10.]);
+  assert_eq!(bars.volume.at(0), Some(6.));
+  assert_eq!(bars.bid.open.as_slice(), &[0.5, 3.5, 6.5, 9.5]);
+  assert_eq!(bars.ask.high.at(0), Some(3.5));
+
+  let bars = ticks.to_bars(TickBar::Ticks(4));
+  assert_eq!(bars.len(), 3);
+  assert_eq!(bars.high.as_slice(), &[4., 8., 10.]);
+  assert_eq!(bars.low.as_slice(), &[1., 5., 9.]);
+  assert_eq!(bars.timestamp[1], begin + Duration::seconds(80));
+
+  // 累计成交量 1, 3, 6, 10 | 5, 11 | 7, 15 | 9, 19
+  let bars = ticks.to_bars(TickBar::Volume(10.));
+  assert_eq!(bars.volume.as_slice(), &[10., 11., 15., 19.]);
+
+  // 成交额 1, 4, 9, 16, 25, ...
+  let bars = ticks.to_bars(TickBar::Dollar(30.));
+  assert_eq!(bars.volume.as_slice(), &[10., 11., 7., 8., 9., 10.]);
+  let bars = ticks.to_bars(TickBar::Dollar(50.));
+  assert_eq!(bars.close.as_slice(), &[5., 7., 8., 9., 10.]);
+}
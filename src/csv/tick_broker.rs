@@ -0,0 +1,157 @@
+use chrono::{DateTime, Utc};
+
+use crate::{
+  broker::{Broker, SingleBroker},
+  data::SingleDataSource,
+  CsvTickDataSource, DataLine, Order, OrderStatus, Position, Strategy, Trade,
+};
+
+/// 直接在 tick 上运行策略的 broker。
+/// 订单以下一个 tick 成交，买入以 ask 价成交，卖出以 bid 价成交（没有 bid、ask 列时以 last 价成交）。
+pub struct CsvTickBroker {
+  pub(crate) cash: f64,
+  pub(crate) position: Position,
+  pub(crate) trade: Trade,
+}
+
+impl Broker for CsvTickBroker {
+  type DS = CsvTickDataSource;
+
+  /// 获取当前剩余现金
+  #[inline]
+  fn cash(&self) -> f64 {
+    self.cash
+  }
+  #[inline]
+  fn position_value(&self, data: &CsvTickDataSource) -> f64 {
+    data.calc_position_value(self.position.size)
+  }
+}
+
+impl SingleBroker for CsvTickBroker {
+  /// 获取当前仓位持仓量
+  #[inline]
+  fn position_size(&self) -> isize {
+    self.position.size
+  }
+  #[inline]
+  fn position(&self) -> &Position {
+    &self.position
+  }
+  /// 建买仓（多仓）
+  #[inline]
+  fn buy<S: Strategy<BK = Self, DS = CsvTickDataSource>>(
+    &mut self,
+    size: isize,
+    data: &CsvTickDataSource,
+    strat: &S,
+  ) {
+    self.submit_order(Order::buy(size, data.timestamp[data.offset]), data, strat);
+  }
+  /// 建卖仓（空仓）
+  #[inline]
+  fn sell<S: Strategy<BK = Self, DS = CsvTickDataSource>>(
+    &mut self,
+    size: isize,
+    data: &CsvTickDataSource,
+    strat: &S,
+  ) {
+    self.submit_order(Order::sell(size, data.timestamp[data.offset]), data, strat);
+  }
+}
+
+impl CsvTickBroker {
+  pub fn new(cash: f64) -> Self {
+    Self {
+      cash,
+      position: Position::new(),
+      trade: Trade::new(),
+    }
+  }
+  #[inline]
+  pub fn trade(&self) -> &Trade {
+    &self.trade
+  }
+  fn submit_order<S: Strategy<BK = Self, DS = CsvTickDataSource>>(
+    &mut self,
+    mut order: Order,
+    data: &CsvTickDataSource,
+    strat: &S,
+  ) -> Order {
+    if order.size <= 0 {
+      panic!("order size must be greater than zero");
+    }
+    // 以下一个 tick 成交，如果当前已经是最后一个 tick，则以当前 tick 成交。
+    let index = (data.offset + 1).min(data.len() - 1);
+    let quote = if order.is_buy() {
+      data.ask.at(index)
+    } else {
+      data.bid.at(index)
+    };
+    order.exe_price = quote.or_else(|| data.price(index)).unwrap();
+    order.exe_size = order.size;
+
+    self.complete_order(&mut order, strat, data.timestamp[index]);
+    order
+  }
+  #[inline]
+  fn complete_order<S: Strategy<BK = Self, DS = CsvTickDataSource>>(
+    &mut self,
+    order: &mut Order,
+    strat: &S,
+    completed_at: DateTime<Utc>,
+  ) {
+    order.settle(strat.calc_commission(order.exe_size, order.exe_price));
+    self.cash -= order.cost + order.comm;
+    let pre_s = self.position.deal(order);
+    let post_s = self.position.size;
+
+    order.status = OrderStatus::Completed(completed_at);
+    strat.on_order(order, self);
+
+    for trade in self.trade.deal(order, pre_s, post_s, completed_at) {
+      strat.on_trade(&trade, self);
+    }
+  }
+}
+
+#[test]
+fn test_tick_broker() {
+  use crate::{CsvTimeType, Engine};
+
+  let content = "time,bid,ask\n1,1.0,1.2\n2,1.1,1.3\n3,1.4,1.6\n4,1.5,1.7";
+  let data = CsvTickDataSource::builder()
+    .time_field("time")
+    .time_type(CsvTimeType::Second)
+    .load_from_string(content)
+    .unwrap();
+
+  struct Strat;
+  impl Strategy for Strat {
+    type DS = CsvTickDataSource;
+    type BK = CsvTickBroker;
+    fn feed(&mut self, _data: &Self::DS) {}
+    fn next(&mut self, index: usize, data: &Self::DS, broker: &mut Self::BK) {
+      match index {
+        0 => broker.buy(10, data, self),
+        2 => {
+          // 多仓按最近一个已经处理的 tick 的 bid 价计算价值
+          assert_eq!(broker.value(data), 100. - 13. + 11.);
+          broker.sell(10, data, self);
+        }
+        _ => {}
+      }
+    }
+    fn calc_commission(&self, _size: isize, _price: f64) -> f64 {
+      0.
+    }
+    fn on_finish(&self, data: &Self::DS, broker: &Self::BK) {
+      // 以下一个 tick 的 ask 价 1.3 买入，以下一个 tick 的 bid 价 1.5 卖出
+      assert!(broker.is_position_empty());
+      assert!((broker.cash() - 102.).abs() < 1e-9);
+      assert_eq!(broker.value(data), broker.cash());
+    }
+  }
+  let mut engine = Engine::new(data, Strat, CsvTickBroker::new(100.));
+  engine.run();
+}
@@ -0,0 +1,246 @@
+use chrono::Duration;
+
+use crate::{indicator::util::get_vec_at, DataLine, DataLineFeed};
+
+use super::{
+  resample::{CsvResampler, Timeframe},
+  source::CsvDataSource,
+};
+
+/// 同一品种的更大周期数据，通过 CsvDataSource::add_timeframe 或者 Engine::add_timeframe 注册。
+/// 基于更大周期数据计算的指标，需要通过 project 投影到基础周期的下标上再使用，
+/// 投影后的数据在基础周期的每个 bar 上只能看到已经走完的大周期 bar，不会引入未来数据。
+pub struct CsvTimeframe {
+  resampler: CsvResampler,
+  /// 大周期的数据源
+  pub data: CsvDataSource,
+  /// 基础周期的每个 bar 走完时，已经走完的大周期 bar 的数量
+  completed: Vec<usize>,
+}
+
+impl CsvTimeframe {
+  fn new(resampler: CsvResampler, base: &CsvDataSource) -> Self {
+    let timestamps = base.timestamp.as_slice();
+    // 基础周期的 bar 间隔取相邻 bar 的最小时间差，用于判断 bar 的结束时间
+    let interval = timestamps
+      .windows(2)
+      .map(|w| w[1] - w[0])
+      .filter(|d| *d > Duration::zero())
+      .min();
+    let periods = resampler.periods(timestamps);
+    let mut completed = vec![0; timestamps.len()];
+    for (k, (_, range)) in periods.iter().enumerate() {
+      let last = range.end - 1;
+      // 周期内最后一个 bar 走完时，如果已经到了周期的结束时间，或者周期结束之前不会再有交易（比如周五的日线之后是周末），
+      // 周期也随之走完；否则周期末尾的 bar 缺失，只有下一个周期的第一个 bar 走完时才能确认周期已经走完。
+      let has_next = range.end < timestamps.len();
+      let closed_at = match interval {
+        Some(interval) if !resampler.is_closed_after(&timestamps[last], interval, has_next) => {
+          range.end
+        }
+        _ => last,
+      };
+      if let Some(v) = completed.get_mut(closed_at) {
+        *v = k + 1;
+      }
+    }
+    for i in 1..completed.len() {
+      completed[i] = completed[i].max(completed[i - 1]);
+    }
+    let data = resampler.resample(base);
+    Self {
+      resampler,
+      data,
+      completed,
+    }
+  }
+  #[inline]
+  pub fn timeframe(&self) -> Timeframe {
+    self.resampler.timeframe()
+  }
+  /// 获取基础周期第 index 个 bar 走完时，最近一个已经走完的大周期 bar 的下标。
+  #[inline]
+  pub fn index_at(&self, index: usize) -> Option<usize> {
+    match self.completed.get(index) {
+      Some(count) if *count > 0 => Some(count - 1),
+      _ => None,
+    }
+  }
+  /// 将大周期的数据（比如基于 self.data 计算的指标）投影到基础周期的下标上。
+  pub fn project<D: DataLineFeed + ?Sized>(&self, line: &D) -> ProjectedDataLine {
+    let (src_data, _) = line.inner();
+    assert_eq!(src_data.len(), self.data.len());
+    ProjectedDataLine::new(line, self.completed.len(), |i| self.index_at(i))
+  }
+}
+
+/// 投影到基础周期下标上的大周期数据，长度和基础周期的数据源一致，可以直接作为指标的输入。
+pub struct ProjectedDataLine {
+  start_pos: usize,
+  data: Vec<f64>,
+}
+
+impl ProjectedDataLine {
+  /// 按 index_at 将 line 投影到长度为 len 的基础周期的下标上
+  pub(super) fn new<D: DataLineFeed + ?Sized>(
+    line: &D,
+    len: usize,
+    index_at: impl Fn(usize) -> Option<usize>,
+  ) -> Self {
+    let (src_data, src_start_pos) = line.inner();
+    let mut data = vec![0.; len];
+    let mut start_pos = len;
+    for (i, v) in data.iter_mut().enumerate() {
+      if let Some(idx) = index_at(i) {
+        if idx >= src_start_pos {
+          *v = src_data[idx];
+          start_pos = start_pos.min(i);
+        }
+      }
+    }
+    Self { start_pos, data }
+  }
+}
+
+impl DataLineFeed for ProjectedDataLine {
+  #[inline(always)]
+  fn inner(&self) -> (&[f64], usize) {
+    (&self.data, self.start_pos)
+  }
+}
+impl DataLine for ProjectedDataLine {
+  #[inline(always)]
+  fn at(&self, index: usize) -> Option<f64> {
+    get_vec_at(&self.data, self.start_pos, index)
+  }
+}
+
+impl CsvDataSource {
+  /// 注册同一品种的更大周期，大周期的数据由当前数据重采样得到。已经注册过的同一周期会被替换。
+  pub fn add_timeframe(&mut self, resampler: CsvResampler) {
+    let timeframe = CsvTimeframe::new(resampler, self);
+    self
+      .timeframes
+      .retain(|tf| tf.timeframe() != timeframe.timeframe());
+    self.timeframes.push(timeframe);
+  }
+  /// 获取已经注册的更大周期。
+  pub fn timeframe(&self, timeframe: Timeframe) -> Option<&CsvTimeframe> {
+    self
+      .timeframes
+      .iter()
+      .find(|tf| tf.timeframe() == timeframe)
+  }
+}
+
+#[test]
+fn test_timeframe_projection() {
+  use crate::{CsvTimeType, TradingCalendar};
+  use chrono::{NaiveTime, TimeZone, Utc, Weekday};
+
+  let mut content = String::from("date,close\n");
+  let begin = Utc.with_ymd_and_hms(2022, 3, 10, 0, 0, 0).unwrap();
+  for i in 0..12 {
+    // 缺失 07:00 的 bar
+    if i == 7 {
+      continue;
+    }
+    let t = begin + Duration::hours(i);
+    content.push_str(&format!("{},{}\n", t.format("%Y-%m-%d %H:%M"), i));
+  }
+  let mut data = CsvDataSource::builder()
+    .time_field("date")
+    .time_type(CsvTimeType::Datetime("%Y-%m-%d %H:%M"))
+    .load_from_string(&content)
+    .unwrap();
+  data.add_timeframe(CsvResampler::new(Timeframe::Hours(2)));
+  let h2 = data.timeframe(Timeframe::Hours(2)).unwrap();
+  assert_eq!(h2.data.len(), 6);
+  assert!(data.timeframe(Timeframe::Hours(4)).is_none());
+
+  // 没有交易日历时，06:00 之后的 bar 属于下一个周期，06:00 ~ 08:00 的周期在 06:00 的 bar 走完时走完
+  let indexies: Vec<Option<usize>> = (0..data.len()).map(|i| h2.index_at(i)).collect();
+  assert_eq!(
+    indexies,
+    vec![
+      None,
+      Some(0),
+      Some(0),
+      Some(1),
+      Some(1),
+      Some(2),
+      Some(3),
+      Some(3),
+      Some(4),
+      Some(4),
+      Some(5)
+    ]
+  );
+  let projected = h2.project(&h2.data.close);
+  assert_eq!(projected.at(0), None);
+  assert_eq!(projected.at(2), Some(1.));
+  assert_eq!(projected.at(6), Some(6.));
+  assert_eq!(projected.inner().0.len(), data.len());
+
+  // 全天交易的日历下 07:00 仍在交易时段，需要等到下一个周期的 bar 走完才能确认周期走完
+  let midnight = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+  let calendar = TradingCalendar::new(chrono_tz::UTC)
+    .weekdays(midnight, midnight)
+    .session(Weekday::Sat, midnight, midnight)
+    .session(Weekday::Sun, midnight, midnight);
+  data.add_timeframe(CsvResampler::new(Timeframe::Hours(2)).calendar(calendar));
+  let h2 = data.timeframe(Timeframe::Hours(2)).unwrap();
+  assert_eq!(h2.index_at(5), Some(2));
+  assert_eq!(h2.index_at(6), Some(2));
+  assert_eq!(h2.index_at(7), Some(3));
+  // 数据的最后一个周期在结束时间走完
+  assert_eq!(h2.index_at(10), Some(5));
+}
+
+#[test]
+fn test_timeframe_period_end() {
+  use crate::{CsvTimeType, TradingCalendar};
+
+  // 2022-03-07 为周一，周五的日线走完时周线随之走完，不需要等到下周一
+  let content = "date,close
+2022-03-07,1
+2022-03-08,2
+2022-03-09,3
+2022-03-10,4
+2022-03-11,5
+2022-03-14,6
+2022-03-15,7";
+  let mut data = CsvDataSource::builder()
+    .time_field("date")
+    .time_type(CsvTimeType::Date("%Y-%m-%d"))
+    .load_from_string(content)
+    .unwrap();
+  data.add_timeframe(CsvResampler::new(Timeframe::Weekly));
+  let weekly = data.timeframe(Timeframe::Weekly).unwrap();
+  assert_eq!(weekly.index_at(3), None);
+  assert_eq!(weekly.index_at(4), Some(0));
+  // 数据在周二结束，第二周没有走完
+  assert_eq!(weekly.index_at(6), Some(0));
+
+  // 交易日历下周末休市，结果相同；周五为节假日时周四的日线走完时周线就已经走完
+  data.add_timeframe(CsvResampler::new(Timeframe::Weekly).calendar(TradingCalendar::nyse()));
+  let weekly = data.timeframe(Timeframe::Weekly).unwrap();
+  assert_eq!(weekly.index_at(4), Some(0));
+  let content = content.replace("2022-03-11,5\n", "");
+  let mut data = CsvDataSource::builder()
+    .time_field("date")
+    .time_type(CsvTimeType::Date("%Y-%m-%d"))
+    .load_from_string(&content)
+    .unwrap();
+  let holiday = chrono::NaiveDate::from_ymd_opt(2022, 3, 11).unwrap();
+  data.add_timeframe(
+    CsvResampler::new(Timeframe::Weekly).calendar(TradingCalendar::nyse().holiday(holiday)),
+  );
+  let weekly = data.timeframe(Timeframe::Weekly).unwrap();
+  assert_eq!(weekly.index_at(3), Some(0));
+  // 没有节假日时周五仍有交易，周线要等到下周一的 bar 走完
+  data.add_timeframe(CsvResampler::new(Timeframe::Weekly).calendar(TradingCalendar::nyse()));
+  let weekly = data.timeframe(Timeframe::Weekly).unwrap();
+  assert_eq!(weekly.index_at(3), None);
+  assert_eq!(weekly.index_at(4), Some(0));
+}
@@ -1,47 +1,83 @@
-use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use chrono::{DateTime, Duration, LocalResult, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc};
 use std::{
+  fmt::Display,
   fs::read_to_string,
-  io::{self, Error, ErrorKind},
+  io::{self, Error},
   path::Path,
 };
 
-use super::builder::{CsvDataSourceBuilder, CsvTimeType};
+use super::builder::{
+  CsvAmbiguousTime, CsvDataSourceBuilder, CsvDuplicateTime, CsvNonexistentTime, CsvTimeType,
+};
+use super::validate::{validate_rows, CsvValidationReport};
 
 #[inline(always)]
 pub(super) fn parse_f64(v: &str) -> io::Result<f64> {
   v.parse::<f64>().map_err(|e| new_io_err(e.to_string()))
 }
 #[inline(always)]
-fn new_io_err(e: String) -> Error {
-  Error::new(ErrorKind::Other, e)
+pub(super) fn new_io_err(e: String) -> Error {
+  Error::other(e)
 }
 #[inline(always)]
 pub(super) fn new_io_err_str(e: &'static str) -> Error {
-  Error::new(ErrorKind::Other, e)
+  Error::other(e)
 }
-pub(super) fn parse_time_field(ty: &CsvTimeType, v: &str) -> io::Result<DateTime<Utc>> {
-  match ty {
+/// 将 time 列配置的时区下的本地时间换算为 UTC 时间。
+pub(super) fn local_to_utc(
+  builder: &CsvDataSourceBuilder,
+  v: NaiveDateTime,
+) -> io::Result<DateTime<Utc>> {
+  match builder.timezone.from_local_datetime(&v) {
+    LocalResult::Single(dt) => Ok(dt.with_timezone(&Utc)),
+    LocalResult::Ambiguous(earliest, latest) => match builder.ambiguous_time {
+      CsvAmbiguousTime::Earliest => Ok(earliest.with_timezone(&Utc)),
+      CsvAmbiguousTime::Latest => Ok(latest.with_timezone(&Utc)),
+      CsvAmbiguousTime::Error => Err(new_io_err(format!(
+        "local time {} is ambiguous in timezone {}",
+        v, builder.timezone
+      ))),
+    },
+    LocalResult::None => match builder.nonexistent_time {
+      CsvNonexistentTime::Shift => {
+        // 夏令时跳变不会在 24 小时内发生两次，因此一天前的偏移量就是跳变前的偏移量。
+        let offset = builder
+          .timezone
+          .offset_from_utc_datetime(&(v - Duration::days(1)))
+          .fix();
+        Ok(Utc.from_utc_datetime(&(v - offset)))
+      }
+      CsvNonexistentTime::Error => Err(new_io_err(format!(
+        "local time {} does not exist in timezone {}",
+        v, builder.timezone
+      ))),
+    },
+  }
+}
+
+pub(super) fn parse_time_field(
+  builder: &CsvDataSourceBuilder,
+  v: &str,
+) -> io::Result<DateTime<Utc>> {
+  match builder.time_type {
     CsvTimeType::Millsecond => v
       .parse::<i64>()
       .map(|v| {
         let secs = v / 1000;
         let nsecs = (v % 1000) * 1_000_000;
-        DateTime::from_utc(
-          NaiveDateTime::from_timestamp_opt(secs, nsecs as u32).unwrap(),
-          Utc,
-        )
+        Utc.timestamp_opt(secs, nsecs as u32).unwrap()
       })
       .map_err(|e| new_io_err(e.to_string())),
     CsvTimeType::Second => v
       .parse::<i64>()
-      .map(|v| DateTime::from_utc(NaiveDateTime::from_timestamp_opt(v, 0).unwrap(), Utc))
+      .map(|v| Utc.timestamp_opt(v, 0).unwrap())
       .map_err(|e| new_io_err(e.to_string())),
     CsvTimeType::Date(fmt) => NaiveDate::parse_from_str(v, fmt)
       .map_err(|e| new_io_err(e.to_string()))
-      .map(|v| DateTime::from_utc(v.and_hms_opt(0, 0, 0).unwrap(), Utc)),
+      .and_then(|v| local_to_utc(builder, v.and_hms_opt(0, 0, 0).unwrap())),
     CsvTimeType::Datetime(fmt) => NaiveDateTime::parse_from_str(v, fmt)
-      .map(|v| DateTime::from_utc(v, Utc))
-      .map_err(|e| new_io_err(e.to_string())),
+      .map_err(|e| new_io_err(e.to_string()))
+      .and_then(|v| local_to_utc(builder, v)),
     _ => panic!("impossible"),
   }
 }
@@ -65,31 +101,17 @@ pub(super) fn parse_time_field(ty: &CsvTimeType, v: &str) -> io::Result<DateTime
 //   // .map(|l| l.trim()).filter(|l| !l.is_empty());
 // }
 
-fn get_column_indexies(builder: &CsvDataSourceBuilder, header_line: &str) -> io::Result<[i8; 8]> {
-  // field indeies of: timestamp, open, close, high, low, volumn, openintrest, adjustclose
-  let mut idx_arr = [-1i8, -1, -1, -1, -1, -1, -1, -1];
+fn get_column_indexies(builder: &CsvDataSourceBuilder, header_line: &str) -> io::Result<Vec<i8>> {
+  // field indeies of: timestamp, 以及按 data_vecs 顺序排列的各个数据列
+  let fields = builder.fields();
+  let mut idx_arr = vec![-1i8; fields.len()];
   header_line
     .split(',')
     .map(|s| s.trim().to_lowercase())
     .enumerate()
     .for_each(|(idx, s)| {
-      // FIXME: 使用 macro 或者表驱动简化代码？？
-      if builder.time_field.eq(&s) {
-        idx_arr[0] = idx as i8;
-      } else if builder.open_field.eq(&s) {
-        idx_arr[1] = idx as i8;
-      } else if builder.close_field.eq(&s) {
-        idx_arr[2] = idx as i8;
-      } else if builder.high_field.eq(&s) {
-        idx_arr[3] = idx as i8;
-      } else if builder.low_field.eq(&s) {
-        idx_arr[4] = idx as i8;
-      } else if builder.volume_field.eq(&s) {
-        idx_arr[5] = idx as i8;
-      } else if builder.openintrest_field.eq(&s) {
-        idx_arr[6] = idx as i8;
-      } else if builder.adjustclose_field.eq(&s) {
-        idx_arr[7] = idx as i8;
+      if let Some(i) = fields.iter().position(|field| **field == s) {
+        idx_arr[i] = idx as i8;
       }
     });
   if idx_arr[0] < 0 {
@@ -99,44 +121,210 @@ fn get_column_indexies(builder: &CsvDataSourceBuilder, header_line: &str) -> io:
   }
 }
 
-type LoadResult = (Vec<DateTime<Utc>>, [Vec<f64>; 7]);
+/// data_vecs 中固定的列数，按顺序为：open, close, high, low, volume, openintrest, adjustclose,
+/// bid open, bid close, bid high, bid low, ask open, ask close, ask high, ask low, spread
+pub(super) const COLUMN_COUNT: usize = 16;
+/// 固定的列之后是按 CsvDataSourceBuilder::extra_field 配置顺序排列的额外列。
+/// 没有配置或者不存在的列是空的 Vec。
+pub(super) type DataVecs = Vec<Vec<f64>>;
+
+type Rows = (Vec<DateTime<Utc>>, DataVecs);
+pub(super) type LoadResult = (Vec<DateTime<Utc>>, DataVecs, Option<CsvValidationReport>);
+
+#[inline(always)]
+pub(super) fn new_row_err(row: usize, column: &str, e: impl Display) -> Error {
+  new_io_err(format!("row {}, column '{}': {}", row, column, e))
+}
+
+/// 为 csv 的各行编号并去掉空行，行号为文件中的行号，从 1 开始，包括 header 行和空行。
+pub(super) fn numbered_lines<'a, T: Iterator<Item = &'a str>>(
+  lines: T,
+) -> impl Iterator<Item = (usize, &'a str)> {
+  lines
+    .enumerate()
+    .map(|(i, l)| (i + 1, l.trim()))
+    .filter(|(_, l)| !l.is_empty())
+}
+
 pub(super) fn load_csv_from_lines<'a, T: Iterator<Item = &'a str>>(
-  mut lines: T,
+  lines: T,
   builder: &CsvDataSourceBuilder,
 ) -> io::Result<LoadResult> {
-  let header_line = lines.next().unwrap();
-  // field indeies of: timestamp, open, close, high, low, volumn, openintrest, adjustclose
+  let mut lines = numbered_lines(lines);
+  let (_, header_line) = lines
+    .next()
+    .ok_or_else(|| new_io_err_str("csv missing header line"))?;
   let idx_arr = get_column_indexies(builder, header_line)?;
+  let columns: Vec<&str> = header_line.split(',').map(|s| s.trim()).collect();
+  let column_count = idx_arr.iter().filter(|idx| **idx >= 0).count();
 
   let mut timestamp_vec = Vec::new();
-  let mut data_vecs: [Vec<f64>; 7] = [(); 7].map(|_| Vec::new());
-  for line in lines {
-    // let mut dp = DataPoint::default();
-    for (idx, seg) in line.trim().split(',').enumerate() {
+  let mut data_vecs: DataVecs = vec![Vec::new(); idx_arr.len() - 1];
+  for (row, line) in lines {
+    let mut found = 0;
+    for (idx, seg) in line.split(',').enumerate() {
+      let column = columns.get(idx).copied().unwrap_or_default();
       let idx = idx as i8; // 不考虑处理 csv 的 column 大于 127 列的 csv，as i8 直接 panic
       if idx == idx_arr[0] {
-        match parse_time_field(&builder.time_type, seg) {
-          Ok(v) => {
-            timestamp_vec.push(v);
-          }
-          Err(e) => {
-            panic!(
-              "timestamp parse failed due to {}, please check 'time_type' config.",
-              e
-            );
-          }
-        }
+        let v = parse_time_field(builder, seg).map_err(|e| {
+          new_row_err(
+            row,
+            column,
+            format!("{}, please check 'time_type' config.", e),
+          )
+        })?;
+        timestamp_vec.push(v);
+        found += 1;
         continue;
       }
 
       for (i, vec) in data_vecs.iter_mut().enumerate() {
         if idx == idx_arr[i + 1] {
-          vec.push(parse_f64(seg)?);
+          vec.push(parse_f64(seg).map_err(|e| new_row_err(row, column, e))?);
+          found += 1;
+        }
+      }
+    }
+    if found != column_count {
+      return Err(new_io_err(format!(
+        "row {}: expect {} columns but found {}",
+        row, column_count, found
+      )));
+    }
+  }
+  process_rows(timestamp_vec, data_vecs, builder)
+}
+
+/// 对解析出的数据进行排序、去重、截取和校验。
+pub(super) fn process_rows(
+  timestamp_vec: Vec<DateTime<Utc>>,
+  data_vecs: DataVecs,
+  builder: &CsvDataSourceBuilder,
+) -> io::Result<LoadResult> {
+  let mut report = CsvValidationReport::new();
+  let (timestamp_vec, data_vecs) =
+    order_rows(timestamp_vec, data_vecs, builder, &mut report.duplicates)?;
+  let (timestamp_vec, data_vecs) = match builder.between {
+    Some((from, to)) => filter_rows(timestamp_vec, data_vecs, from, to),
+    None => (timestamp_vec, data_vecs),
+  };
+  match &builder.fix_policy {
+    Some(policy) => {
+      let (timestamp_vec, data_vecs) =
+        validate_rows(timestamp_vec, data_vecs, builder, policy, &mut report)?;
+      Ok((timestamp_vec, data_vecs, Some(report)))
+    }
+    None => Ok((timestamp_vec, data_vecs, None)),
+  }
+}
+
+/// 只保留时间在 [from, to) 区间内的行，数据需要已经按时间升序排列。
+pub(super) fn filter_rows(
+  mut timestamp_vec: Vec<DateTime<Utc>>,
+  mut data_vecs: DataVecs,
+  from: DateTime<Utc>,
+  to: DateTime<Utc>,
+) -> Rows {
+  let len = timestamp_vec.len();
+  let start = timestamp_vec.partition_point(|t| *t < from);
+  let end = timestamp_vec.partition_point(|t| *t < to).max(start);
+  timestamp_vec.truncate(end);
+  timestamp_vec.drain(..start);
+  for vec in data_vecs.iter_mut().filter(|vec| vec.len() == len) {
+    vec.truncate(end);
+    vec.drain(..start);
+  }
+  (timestamp_vec, data_vecs)
+}
+
+/// 只保留时间满足 keep 的行。
+pub(super) fn retain_rows(
+  timestamp_vec: Vec<DateTime<Utc>>,
+  data_vecs: DataVecs,
+  keep: impl Fn(&DateTime<Utc>) -> bool,
+) -> Rows {
+  let len = timestamp_vec.len();
+  let rows: Vec<usize> = (0..len).filter(|&i| keep(&timestamp_vec[i])).collect();
+  if rows.len() == len {
+    return (timestamp_vec, data_vecs);
+  }
+  let timestamp = rows.iter().map(|&i| timestamp_vec[i]).collect();
+  let data_vecs = data_vecs
+    .into_iter()
+    .map(|vec| {
+      if vec.len() == len {
+        rows.iter().map(|&i| vec[i]).collect()
+      } else {
+        vec
+      }
+    })
+    .collect();
+  (timestamp, data_vecs)
+}
+
+/// 将数据整理为按时间升序排列，并按配置处理时间重复的行，重复的时间会记录到 duplicates 中。
+/// 降序的数据总是会被反转，乱序的数据只有在开启 sort_rows 时才会被排序，否则保持文件中的顺序，
+/// 此时只有相邻的行才会被当作时间重复。
+pub(super) fn order_rows(
+  timestamp_vec: Vec<DateTime<Utc>>,
+  data_vecs: DataVecs,
+  builder: &CsvDataSourceBuilder,
+  duplicates: &mut Vec<DateTime<Utc>>,
+) -> io::Result<Rows> {
+  let len = timestamp_vec.len();
+  let mut rows: Vec<usize> = (0..len).collect();
+  let mut reordered = false;
+  if !timestamp_vec.windows(2).all(|w| w[0] <= w[1]) {
+    let is_desc = timestamp_vec.windows(2).all(|w| w[0] >= w[1]);
+    if is_desc || builder.sort_rows {
+      // 稳定排序，时间相同的行保持其在文件中的先后顺序。
+      rows.sort_by_key(|&i| timestamp_vec[i]);
+      reordered = true;
+    }
+  }
+
+  let mut kept = Vec::with_capacity(len);
+  let mut i = 0;
+  while i < len {
+    let t = timestamp_vec[rows[i]];
+    let mut j = i + 1;
+    while j < len && timestamp_vec[rows[j]] == t {
+      j += 1;
+    }
+    if j - i == 1 {
+      kept.push(rows[i]);
+    } else {
+      duplicates.push(t);
+      match builder.duplicate_time {
+        CsvDuplicateTime::Keep => kept.extend_from_slice(&rows[i..j]),
+        CsvDuplicateTime::First => kept.push(rows[i]),
+        CsvDuplicateTime::Last => kept.push(rows[j - 1]),
+        CsvDuplicateTime::Error => {
+          return Err(new_io_err(format!("duplicated timestamp {} found", t)));
         }
       }
     }
+    i = j;
+  }
+  reordered = reordered || kept.len() != len;
+  let rows = kept;
+
+  if !reordered {
+    return Ok((timestamp_vec, data_vecs));
   }
-  Ok((timestamp_vec, data_vecs))
+  let timestamp = rows.iter().map(|&i| timestamp_vec[i]).collect();
+  // 没有配置或者不存在的列是空的 Vec，保持原样。
+  let data_vecs = data_vecs
+    .into_iter()
+    .map(|vec| {
+      if vec.len() == len {
+        rows.iter().map(|&i| vec[i]).collect()
+      } else {
+        vec
+      }
+    })
+    .collect();
+  Ok((timestamp, data_vecs))
 }
 
 #[inline]
@@ -153,6 +341,126 @@ pub(super) fn load_csv_from_string(
   content: &str,
   builder: &CsvDataSourceBuilder,
 ) -> io::Result<LoadResult> {
-  let lines = content.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
-  load_csv_from_lines(lines, builder)
+  load_csv_from_lines(content.lines(), builder)
+}
+
+#[test]
+fn test_order_rows() {
+  let builder = || {
+    CsvDataSourceBuilder::new()
+      .time_field("date")
+      .time_type(CsvTimeType::Date("%Y-%m-%d"))
+  };
+  let date = |v: &str| {
+    Utc.from_utc_datetime(
+      &NaiveDate::parse_from_str(v, "%Y-%m-%d")
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap(),
+    )
+  };
+
+  let desc = "date,close\n2022-01-03,3\n2022-01-02,2\n2022-01-01,1";
+  let (ts, data, _) = load_csv_from_string(desc, &builder()).unwrap();
+  assert_eq!(
+    ts,
+    vec![date("2022-01-01"), date("2022-01-02"), date("2022-01-03")]
+  );
+  assert_eq!(data[1], vec![1., 2., 3.]);
+
+  let unsorted = "date,close\n2022-01-02,2\n2022-01-03,3\n2022-01-01,1\n2022-01-02,4";
+  // 默认保持文件中的顺序
+  let (_, data, _) = load_csv_from_string(unsorted, &builder()).unwrap();
+  assert_eq!(data[1], vec![2., 3., 1., 4.]);
+  let (_, data, _) = load_csv_from_string(unsorted, &builder().sort_rows(true)).unwrap();
+  assert_eq!(data[1], vec![1., 2., 4., 3.]);
+  let (ts, data, _) = load_csv_from_string(
+    unsorted,
+    &builder()
+      .sort_rows(true)
+      .duplicate_time(CsvDuplicateTime::Last),
+  )
+  .unwrap();
+  assert_eq!(ts.len(), 3);
+  assert_eq!(data[1], vec![1., 4., 3.]);
+  assert!(load_csv_from_string(
+    unsorted,
+    &builder()
+      .sort_rows(true)
+      .duplicate_time(CsvDuplicateTime::Error),
+  )
+  .is_err());
+}
+
+#[test]
+fn test_load_csv_errors() {
+  let builder = CsvDataSourceBuilder::new()
+    .time_field("date")
+    .time_type(CsvTimeType::Date("%Y-%m-%d"));
+  let err = |content: &str| {
+    load_csv_from_string(content, &builder)
+      .err()
+      .unwrap()
+      .to_string()
+  };
+  assert!(err("date,Close\n2022-01-01,1\n2022/01/02,2").starts_with("row 3, column 'date': "));
+  assert!(err("date,Close\n2022-01-01,1\n2022-01-02,x").starts_with("row 3, column 'Close': "));
+  assert_eq!(
+    err("date,Close\n2022-01-01"),
+    "row 2: expect 2 columns but found 1"
+  );
+  // 行号和文件中的行号一致，包括空行
+  assert!(
+    err("\ndate,Close\n\n2022-01-01,1\n\n2022-01-02,x").starts_with("row 6, column 'Close': ")
+  );
+}
+
+#[test]
+fn test_parse_time_field_with_timezone() {
+  use chrono_tz::America::New_York;
+
+  let builder = CsvDataSourceBuilder::new()
+    .time_type(CsvTimeType::Datetime("%Y-%m-%d %H:%M"))
+    .timezone(New_York);
+  let utc = |v: &str| NaiveDateTime::parse_from_str(v, "%Y-%m-%d %H:%M").unwrap();
+  // EST(-5) 与 EDT(-4)
+  assert_eq!(
+    parse_time_field(&builder, "2022-01-10 17:00")
+      .unwrap()
+      .naive_utc(),
+    utc("2022-01-10 22:00")
+  );
+  assert_eq!(
+    parse_time_field(&builder, "2022-07-10 17:00")
+      .unwrap()
+      .naive_utc(),
+    utc("2022-07-10 21:00")
+  );
+  // 夏令时跳变，02:30 不存在
+  assert!(parse_time_field(&builder, "2022-03-13 02:30").is_err());
+  // 夏令时回拨，01:30 出现两次
+  assert!(parse_time_field(&builder, "2022-11-06 01:30").is_err());
+
+  let builder = builder
+    .nonexistent_time(CsvNonexistentTime::Shift)
+    .ambiguous_time(CsvAmbiguousTime::Latest);
+  assert_eq!(
+    parse_time_field(&builder, "2022-03-13 02:30")
+      .unwrap()
+      .naive_utc(),
+    utc("2022-03-13 07:30")
+  );
+  assert_eq!(
+    parse_time_field(&builder, "2022-11-06 01:30")
+      .unwrap()
+      .naive_utc(),
+    utc("2022-11-06 06:30")
+  );
+  let builder = builder.ambiguous_time(CsvAmbiguousTime::Earliest);
+  assert_eq!(
+    parse_time_field(&builder, "2022-11-06 01:30")
+      .unwrap()
+      .naive_utc(),
+    utc("2022-11-06 05:30")
+  );
 }
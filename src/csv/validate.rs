@@ -0,0 +1,411 @@
+use std::{fmt::Display, io};
+
+use chrono::{DateTime, Utc};
+
+use super::{
+  builder::CsvDataSourceBuilder,
+  util::{new_io_err, new_io_err_str, DataVecs, COLUMN_COUNT},
+};
+
+/// 数据校验发现问题时的处理策略。
+#[derive(Debug, Clone, Copy)]
+pub enum CsvFixPolicy {
+  /// 使用上一个 bar 的数据填充有问题的数据，缺失的 bar 使用上一个 bar 的 close 价填充（成交量为 0）
+  ForwardFill,
+  /// 丢弃有问题的 bar，缺失的 bar 只记录不填充
+  Drop,
+  /// 返回错误，保留下来的时间重复的行（CsvDuplicateTime::Keep）也会返回错误
+  Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CsvIssueKind {
+  /// 数值为 NaN 或无穷大，或者价格小于等于 0、成交量小于 0。参数为列名
+  InvalidValue(&'static str),
+  /// high 小于 low，或者 open、close 不在 [low, high] 区间内
+  Inconsistent,
+  /// close 相对上一个 bar 的变化率超过了阈值。参数为变化率
+  Outlier(f64),
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvIssue {
+  pub timestamp: DateTime<Utc>,
+  pub kind: CsvIssueKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvGap {
+  /// 缺失前的最后一个 bar 的时间
+  pub from: DateTime<Utc>,
+  /// 缺失后的第一个 bar 的时间
+  pub to: DateTime<Utc>,
+  /// 按预期周期计算的缺失 bar 数量
+  pub missing: usize,
+}
+
+/// 数据校验报告。
+#[derive(Debug, Clone, Default)]
+pub struct CsvValidationReport {
+  /// 重复出现的时间，每个重复的时间只记录一次
+  pub duplicates: Vec<DateTime<Utc>>,
+  pub gaps: Vec<CsvGap>,
+  pub issues: Vec<CsvIssue>,
+  /// 被填充的 bar 数量（包括修复的 bar 和补齐的缺失 bar）
+  pub filled: usize,
+  /// 被丢弃的 bar 数量
+  pub dropped: usize,
+}
+
+impl CsvValidationReport {
+  pub fn new() -> Self {
+    Self::default()
+  }
+  /// 是否没有发现任何问题
+  pub fn is_clean(&self) -> bool {
+    self.duplicates.is_empty() && self.gaps.is_empty() && self.issues.is_empty()
+  }
+}
+
+impl Display for CsvValidationReport {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "duplicates: {}, gaps: {}, issues: {}, filled: {}, dropped: {}",
+      self.duplicates.len(),
+      self.gaps.len(),
+      self.issues.len(),
+      self.filled,
+      self.dropped
+    )?;
+    if let Some(gap) = self.gaps.first() {
+      write!(
+        f,
+        ", first gap: {} ~ {} ({} bars missing)",
+        gap.from, gap.to, gap.missing
+      )?;
+    }
+    if let Some(issue) = self.issues.first() {
+      write!(f, ", first issue: {:?} at {}", issue.kind, issue.timestamp)?;
+    }
+    Ok(())
+  }
+}
+
+// 列名顺序对齐 data_vecs
+const COLUMN_NAMES: [&str; COLUMN_COUNT] = [
+  "open",
+  "close",
+  "high",
+  "low",
+  "volume",
+  "openintrest",
+  "adjustclose",
+  "bid open",
+  "bid close",
+  "bid high",
+  "bid low",
+  "ask open",
+  "ask close",
+  "ask high",
+  "ask low",
+  "spread",
+];
+const PRICE_COLUMNS: [usize; 13] = [0, 1, 2, 3, 6, 7, 8, 9, 10, 11, 12, 13, 14];
+
+type Rows = (Vec<DateTime<Utc>>, DataVecs);
+
+#[inline]
+fn is_invalid(column: usize, v: f64) -> bool {
+  if PRICE_COLUMNS.contains(&column) {
+    !v.is_finite() || v <= 0.
+  } else {
+    !v.is_finite() || v < 0.
+  }
+}
+
+/// 填充数据时列对应的 close 列：mid、bid 和 ask 的 OHLC 分别使用各自的 close 填充
+#[inline]
+fn close_column(column: usize) -> Option<usize> {
+  match column {
+    0..=3 => Some(1),
+    7..=10 => Some(8),
+    11..=14 => Some(12),
+    _ => None,
+  }
+}
+
+#[inline]
+fn check_values(data_vecs: &DataVecs, row: usize) -> Option<usize> {
+  // 额外的数值列不做检查
+  (0..COLUMN_COUNT).find(|&i| matches!(data_vecs[i].get(row), Some(v) if is_invalid(i, *v)))
+}
+
+#[inline]
+fn is_inconsistent(data_vecs: &DataVecs, row: usize) -> bool {
+  let (high, low) = match (data_vecs[2].get(row), data_vecs[3].get(row)) {
+    (Some(high), Some(low)) => (*high, *low),
+    _ => return false,
+  };
+  high < low
+    || [0, 1].iter().any(|&i| match data_vecs[i].get(row) {
+      Some(v) => *v > high || *v < low,
+      None => false,
+    })
+}
+
+/// 使用第 p 行数据填充时各列的值：价格列取对应的 close 价，其余列取原值
+fn fill_values(out_vecs: &DataVecs, present: &[bool], p: usize) -> Vec<f64> {
+  let mut fill = vec![0.; out_vecs.len()];
+  for (i, v) in fill.iter_mut().enumerate() {
+    if !present[i] {
+      continue;
+    }
+    *v = match close_column(i) {
+      Some(c) if present[c] => out_vecs[c][p],
+      _ => out_vecs[i][p],
+    };
+  }
+  fill
+}
+
+/// 对已经按时间升序排列的数据进行校验，并按照 policy 处理发现的问题。
+/// 时间重复的行由 CsvDuplicateTime 处理，ForwardFill 和 Drop 不会修改保留下来的重复行。
+pub(super) fn validate_rows(
+  timestamp_vec: Vec<DateTime<Utc>>,
+  data_vecs: DataVecs,
+  builder: &CsvDataSourceBuilder,
+  policy: &CsvFixPolicy,
+  report: &mut CsvValidationReport,
+) -> io::Result<Rows> {
+  let len = timestamp_vec.len();
+  let has_duplicates = timestamp_vec.windows(2).any(|w| w[0] == w[1]);
+  // 不存在的列保持为空的 Vec
+  let present: Vec<bool> = data_vecs.iter().map(|v| v.len() == len).collect();
+  let mut out_ts = Vec::with_capacity(len);
+  let mut out_vecs: DataVecs = (0..data_vecs.len())
+    .map(|_| Vec::with_capacity(len))
+    .collect();
+
+  for row in 0..len {
+    let ts = timestamp_vec[row];
+    // 缺失的 bar 按原始数据中相邻的两个 bar 计算，不受丢弃的 bar 影响
+    if let (Some(interval), Some(prev_ts)) = (builder.expected_interval, row.checked_sub(1)) {
+      let prev_ts = timestamp_vec[prev_ts];
+      let diff = ts - prev_ts;
+      let is_closure = matches!(builder.max_gap, Some(max_gap) if diff > max_gap);
+      if diff > interval && !is_closure {
+        let missing = (diff.num_milliseconds() / interval.num_milliseconds()) as usize;
+        let missing = if prev_ts + interval * (missing as i32) == ts {
+          missing - 1
+        } else {
+          missing
+        };
+        if missing > 0 {
+          report.gaps.push(CsvGap {
+            from: prev_ts,
+            to: ts,
+            missing,
+          });
+          let prev = out_ts.len().checked_sub(1);
+          if let (CsvFixPolicy::ForwardFill, Some(p)) = (policy, prev) {
+            let fill = fill_values(&out_vecs, &present, p);
+            for n in 1..=missing {
+              out_ts.push(prev_ts + interval * (n as i32));
+              for (i, vec) in out_vecs.iter_mut().enumerate() {
+                if present[i] {
+                  vec.push(if i == 4 { 0. } else { fill[i] });
+                }
+              }
+            }
+            report.filled += missing;
+          }
+        }
+      }
+    }
+    let prev = out_ts.len().checked_sub(1);
+
+    let kind = if let Some(i) = check_values(&data_vecs, row) {
+      Some(CsvIssueKind::InvalidValue(COLUMN_NAMES[i]))
+    } else if is_inconsistent(&data_vecs, row) {
+      Some(CsvIssueKind::Inconsistent)
+    } else {
+      match (builder.outlier_threshold, prev) {
+        (Some(threshold), Some(p)) if present[1] => {
+          // 相对上一个保留的 bar 和上一个原始的 bar 都超过阈值时才是异常值，
+          // 这样价格的持续跳变只有第一个 bar 被视为异常值，之后的 bar 不会被全部丢弃
+          let change = data_vecs[1][row] / out_vecs[1][p] - 1.;
+          let raw_change = data_vecs[1][row] / data_vecs[1][row - 1] - 1.;
+          if change.abs() > threshold && (raw_change.is_nan() || raw_change.abs() > threshold) {
+            Some(CsvIssueKind::Outlier(change))
+          } else {
+            None
+          }
+        }
+        _ => None,
+      }
+    };
+
+    let kind = match kind {
+      None => {
+        out_ts.push(ts);
+        for (i, vec) in out_vecs.iter_mut().enumerate() {
+          if present[i] {
+            vec.push(data_vecs[i][row]);
+          }
+        }
+        continue;
+      }
+      Some(kind) => kind,
+    };
+    report.issues.push(CsvIssue {
+      timestamp: ts,
+      kind: kind.clone(),
+    });
+    match (policy, prev) {
+      (CsvFixPolicy::ForwardFill, Some(p)) => {
+        out_ts.push(ts);
+        let fill = fill_values(&out_vecs, &present, p);
+        for (i, vec) in out_vecs.iter_mut().enumerate() {
+          if !present[i] {
+            continue;
+          }
+          let v = match &kind {
+            // 只填充有问题的列
+            CsvIssueKind::InvalidValue(_)
+              if i < COLUMN_COUNT && is_invalid(i, data_vecs[i][row]) =>
+            {
+              vec[p]
+            }
+            CsvIssueKind::InvalidValue(_) => data_vecs[i][row],
+            // OHLC 整体有问题，使用上一个 close 价生成一个平的 bar
+            _ if i == 4 || i == 5 => data_vecs[i][row],
+            _ => fill[i],
+          };
+          vec.push(v);
+        }
+        report.filled += 1;
+      }
+      // 第一个 bar 没有可以用来填充的数据，只能丢弃
+      (CsvFixPolicy::ForwardFill, None) | (CsvFixPolicy::Drop, _) => {
+        report.dropped += 1;
+      }
+      (CsvFixPolicy::Error, _) => {}
+    }
+  }
+
+  if matches!(policy, CsvFixPolicy::Error)
+    && (has_duplicates || !(report.gaps.is_empty() && report.issues.is_empty()))
+  {
+    return Err(new_io_err(format!("csv validation failed, {}", report)));
+  }
+  if out_ts.is_empty() && len > 0 {
+    return Err(new_io_err_str("csv validation dropped all rows"));
+  }
+  Ok((out_ts, out_vecs))
+}
+
+#[test]
+fn test_validate_rows() {
+  use super::{CsvDataSourceBuilder, CsvDuplicateTime, CsvTimeType};
+  use chrono::Duration;
+
+  let content = "date,open,high,low,close,volume
+2022-01-03 00:00,1,1,1,1,10
+2022-01-03 01:00,1,2,0.5,1.5,10
+2022-01-03 03:00,1.5,1.6,1.4,nan,10
+2022-01-03 04:00,1.5,1.4,1.6,1.5,10
+2022-01-03 05:00,1.5,1.6,1.4,1.5,-1
+2022-01-03 06:00,1.5,9,1.4,9,10
+2022-01-04 06:00,1.5,1.6,1.4,1.5,10";
+  let builder = || {
+    CsvDataSourceBuilder::new()
+      .time_field("date")
+      .time_type(CsvTimeType::Datetime("%Y-%m-%d %H:%M"))
+      .expected_interval(Duration::hours(1))
+      .max_gap(Duration::hours(12))
+      .outlier_threshold(0.5)
+  };
+  let load = |builder: CsvDataSourceBuilder| {
+    super::util::load_csv_from_string(content, &builder).map(|(ts, data, report)| {
+      let report = report.unwrap();
+      (ts, data, report)
+    })
+  };
+
+  let e = load(builder().validate(CsvFixPolicy::Error)).err().unwrap();
+  assert!(e.to_string().starts_with("csv validation failed"));
+
+  let (ts, data, report) = load(builder().validate(CsvFixPolicy::Drop)).unwrap();
+  assert!(!report.is_clean());
+  assert_eq!(report.gaps.len(), 1);
+  assert_eq!(report.gaps[0].missing, 1);
+  assert_eq!(
+    report
+      .issues
+      .iter()
+      .map(|issue| issue.kind.clone())
+      .collect::<Vec<_>>(),
+    vec![
+      CsvIssueKind::InvalidValue("close"),
+      CsvIssueKind::Inconsistent,
+      CsvIssueKind::InvalidValue("volume"),
+      CsvIssueKind::Outlier(5.)
+    ]
+  );
+  assert_eq!(report.dropped, 4);
+  assert_eq!(ts.len(), 3);
+  assert_eq!(data[1], vec![1., 1.5, 1.5]);
+
+  let (ts, data, report) = load(builder().validate(CsvFixPolicy::ForwardFill)).unwrap();
+  assert_eq!(report.filled, 5);
+  assert_eq!(ts.len(), 8);
+  // 02:00 补齐的 bar，03:00 修复的 close，04:00 和 06:00 被替换为平的 bar
+  assert_eq!(data[1], vec![1., 1.5, 1.5, 1.5, 1.5, 1.5, 1.5, 1.5]);
+  assert_eq!(data[2], vec![1., 2., 1.5, 1.6, 1.5, 1.6, 1.5, 1.6]);
+  assert_eq!(data[4], vec![10., 10., 0., 10., 10., 10., 10., 10.]);
+
+  // 单个尖峰只丢弃一个 bar，价格的持续跳变只丢弃第一个 bar
+  let content = "date,open,high,low,close,volume
+2022-01-03 00:00,1,1,1,1,10
+2022-01-03 01:00,1,5,1,5,10
+2022-01-03 02:00,1,1,1,1,10
+2022-01-03 03:00,3,3,3,3,10
+2022-01-03 04:00,3,3,3,3,10
+2022-01-03 05:00,3,3.1,3,3.1,10";
+  let (_, data, report) =
+    super::util::load_csv_from_string(content, &builder().validate(CsvFixPolicy::Drop)).unwrap();
+  assert_eq!(
+    report
+      .unwrap()
+      .issues
+      .iter()
+      .map(|issue| issue.kind.clone())
+      .collect::<Vec<_>>(),
+    vec![CsvIssueKind::Outlier(4.), CsvIssueKind::Outlier(2.)]
+  );
+  assert_eq!(data[1], vec![1., 1., 3., 3.1]);
+
+  // 保留下来的时间重复的行在 Error 策略下返回错误，其他策略只记录
+  let content = "date,open,high,low,close,volume
+2022-01-03 00:00,1,1,1,1,10
+2022-01-03 00:00,1,1,1,1,10
+2022-01-03 01:00,1,1,1,1,10";
+  let load = |policy, duplicate_time| {
+    super::util::load_csv_from_string(
+      content,
+      &builder().validate(policy).duplicate_time(duplicate_time),
+    )
+  };
+  let e = load(CsvFixPolicy::Error, CsvDuplicateTime::Keep)
+    .err()
+    .unwrap();
+  assert!(e
+    .to_string()
+    .starts_with("csv validation failed, duplicates: 1"));
+  let (ts, _, report) = load(CsvFixPolicy::Drop, CsvDuplicateTime::Keep).unwrap();
+  assert_eq!(ts.len(), 3);
+  assert_eq!(report.unwrap().duplicates.len(), 1);
+  let (ts, _, _) = load(CsvFixPolicy::Error, CsvDuplicateTime::First).unwrap();
+  assert_eq!(ts.len(), 2);
+}
@@ -7,7 +7,11 @@ pub trait DataSource: Sized {
     strat: &mut S,
     broker: &mut B,
   ) -> bool;
-  /// 计算仓位的当前现金价值
+}
+
+/// 只有一个品种的数据源，比如 CsvDataSource 和 CsvTickDataSource。
+pub trait SingleDataSource: DataSource {
+  /// 按最近一个已经处理的 bar 计算仓位的当前现金价值：Strategy::next 中为上一个 bar，运行结束后为最后一个 bar
   fn calc_position_value(&self, position_size: isize) -> f64;
 }
 
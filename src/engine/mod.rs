@@ -1,5 +1,7 @@
 use crate::data::DataSource;
-use crate::{Broker, Strategy};
+use std::io;
+
+use crate::{Broker, CsvDataSource, CsvResampler, IndicatorGraph, Strategy, Timer};
 
 pub struct Engine<D: DataSource, B: Broker<DS = D>, S: Strategy<DS = D, BK = B>> {
   data: D,
@@ -26,3 +28,26 @@ where
     self.strategy.on_finish(&self.data, &self.broker);
   }
 }
+
+impl<B, S> Engine<CsvDataSource, B, S>
+where
+  B: Broker<DS = CsvDataSource>,
+  S: Strategy<DS = CsvDataSource, BK = B>,
+{
+  /// 注册同一品种的更大周期，策略可以通过 CsvDataSource::timeframe 获取大周期的数据，
+  /// 并通过 CsvTimeframe::project 将大周期的指标投影到基础周期的下标上。
+  pub fn add_timeframe(&mut self, resampler: CsvResampler) -> &mut Self {
+    self.data.add_timeframe(resampler);
+    self
+  }
+  /// 注册定时器，bar 跨过计划触发时间时调用 Strategy::on_timer。同名的定时器会被替换。
+  pub fn add_timer(&mut self, name: &str, timer: Timer) -> &mut Self {
+    self.data.add_timer(name, timer);
+    self
+  }
+  /// 注册指标图，指标在 Strategy::feed 之前按依赖关系自动计算，策略通过 CsvDataSource::indicator 获取指标的输出。
+  pub fn set_indicators(&mut self, graph: IndicatorGraph) -> io::Result<&mut Self> {
+    self.data.set_indicators(graph)?;
+    Ok(self)
+  }
+}
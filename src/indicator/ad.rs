@@ -0,0 +1,245 @@
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::{TA_AD, TA_ADOSC};
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  impl_indicator_trait, impl_indicator_without_period,
+  indicator::base::{check_inputs, check_update},
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// 累加一个 bar 的资金流量：volume 乘以 close 在 high 和 low 区间中的位置（-1 到 1）
+#[inline(always)]
+fn next_ad(ad: f64, high: f64, low: f64, close: f64, volume: f64) -> f64 {
+  let range = high - low;
+  if range > 0. {
+    ad + (((close - low) - (high - close)) / range) * volume
+  } else {
+    ad
+  }
+}
+
+/// 累积/派发线（Chaikin A/D Line）
+pub struct ADIndicator {
+  line: IndicatorLine,
+  state: f64,
+}
+impl_indicator_without_period!(ADIndicator, state);
+impl_indicator_trait!(ADIndicator);
+
+impl Indicator for ADIndicator {
+  fn name(&self) -> &'static str {
+    "AD"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["high", "low", "close", "volume"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    Vec::new()
+  }
+  fn lookback(&self) -> usize {
+    0
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_AD(
+          0, end, ins[0], ins[1], ins[2], ins[3], out_begin, out_size, outs[0],
+        )
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let value = check_update(self, inputs).map(|[high, low, close, volume]| {
+      self.state = next_ad(self.state, high, low, close, volume);
+      self.state
+    });
+    self.line.push(value);
+  }
+}
+
+/// Chaikin A/D 振荡指标：A/D 线的快速 EMA 减去慢速 EMA。
+/// EMA 以第一个 A/D 值作为初始值，输出之前的 lookback 个 bar 用于预热。
+pub struct ADOSCIndicator {
+  fast_period: usize,
+  slow_period: usize,
+  line: IndicatorLine,
+  state: ADOSCState,
+}
+impl_indicator_trait!(ADOSCIndicator);
+
+#[derive(Default)]
+struct ADOSCState {
+  count: usize,
+  ad: f64,
+  fast: f64,
+  slow: f64,
+}
+
+impl ADOSCIndicator {
+  /// 常用的参数为 (3, 10)
+  pub fn new(fast_period: usize, slow_period: usize) -> Self {
+    if fast_period < 2 || slow_period < 2 {
+      panic!("ADOSCIndicator periods must gte 2");
+    }
+    Self {
+      fast_period,
+      slow_period,
+      line: IndicatorLine::new(),
+      state: Default::default(),
+    }
+  }
+}
+
+impl Indicator for ADOSCIndicator {
+  fn name(&self) -> &'static str {
+    "ADOSC"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["high", "low", "close", "volume"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![
+      ("fast_period", self.fast_period as f64),
+      ("slow_period", self.slow_period as f64),
+    ]
+  }
+  fn lookback(&self) -> usize {
+    self.fast_period.max(self.slow_period) - 1
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let (fast, slow) = (self.fast_period as i32, self.slow_period as i32);
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_ADOSC(
+          0, end, ins[0], ins[1], ins[2], ins[3], fast, slow, out_begin, out_size, outs[0],
+        )
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let lookback = self.lookback();
+    let fast_k = 2. / (self.fast_period + 1) as f64;
+    let slow_k = 2. / (self.slow_period + 1) as f64;
+    let value = check_update(self, inputs).and_then(|[high, low, close, volume]| {
+      let state = &mut self.state;
+      state.ad = next_ad(state.ad, high, low, close, volume);
+      if state.count == 0 {
+        state.fast = state.ad;
+        state.slow = state.ad;
+      } else {
+        state.fast = (fast_k * state.ad) + ((1. - fast_k) * state.fast);
+        state.slow = (slow_k * state.ad) + ((1. - slow_k) * state.slow);
+      }
+      state.count += 1;
+      (state.count > lookback).then_some(state.fast - state.slow)
+    });
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_ad_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let bars = [
+    [4., 0., 3., 10.],
+    [4., 0., 1., 20.],
+    [2., 2., 2., 30.],
+    [5., 1., 5., 10.],
+  ];
+  let (mut ad, mut adosc) = (ADIndicator::new(), ADOSCIndicator::new(2, 3));
+  for [h, l, c, v] in bars {
+    ad.update(&[Some(h), Some(l), Some(c), Some(v)]);
+    adosc.update(&[Some(h), Some(l), Some(c), Some(v)]);
+  }
+  // 资金流量为 5、-10、0（high 等于 low）、10
+  assert_eq!(ad.line.as_slice(), &[5., -5., -5., 5.]);
+  // fast EMA: 5, -5/3, -35/9；slow EMA: 5, 0, -2.5
+  assert_eq!(adosc.line.start_pos(), 2);
+  assert!((adosc.at(2).unwrap() - (-35. / 9. + 2.5)).abs() < 1e-9);
+  let high = D(vec![5., 6., 7., 6., 8., 9., 8., 7., 6., 8., 10., 9.], 0);
+  let low = D(vec![3., 4., 5., 4., 5., 7., 6., 5., 4., 5., 7., 7.], 0);
+  let close = D(
+    vec![4.5, 5., 6.5, 4.5, 7.5, 8., 6.5, 6., 4.5, 7., 9.5, 8.],
+    0,
+  );
+  let volume = D(
+    vec![
+      100., 200., 150., 300., 250., 100., 200., 150., 300., 250., 100., 200.,
+    ],
+    3,
+  );
+  crate::indicator::base::assert_outputs(
+    ADIndicator::new,
+    &[&high, &low, &close, &volume],
+    &[(
+      3,
+      &[
+        -150.,
+        16.666666666666657,
+        16.666666666666657,
+        -83.33333333333334,
+        -83.33333333333334,
+        -233.33333333333334,
+        -150.,
+        -83.33333333333334,
+        -83.33333333333334,
+      ],
+    )],
+  );
+  crate::indicator::base::assert_outputs(
+    || ADOSCIndicator::new(2, 4),
+    &[&high, &low, &close, &volume],
+    &[(
+      6,
+      &[
+        3.1604938271605008,
+        -5.346502057613165,
+        -45.62216735253773,
+        -19.28916689529038,
+        8.898988812680955,
+        12.163556270893622,
+      ],
+    )],
+  );
+}
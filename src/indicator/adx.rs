@@ -0,0 +1,315 @@
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::{TA_ADX, TA_MINUS_DI, TA_PLUS_DI};
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  impl_indicator_trait, impl_indicator_with_period,
+  indicator::{
+    base::{check_inputs, check_update},
+    state::{is_zero, true_range},
+  },
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// +DM、-DM 和真实波幅的 Wilder 平滑：前 period - 1 个变化求和，之后每个 bar 减去 1 / period 再加上新的值
+#[derive(Default)]
+struct DMState {
+  count: usize,
+  prev: (f64, f64, f64),
+  plus_dm: f64,
+  minus_dm: f64,
+  tr: f64,
+}
+
+impl DMState {
+  /// 返回 (+DI, -DI)，从第 period 个 bar 开始有效
+  fn next(&mut self, period: usize, high: f64, low: f64, close: f64) -> Option<(f64, f64)> {
+    self.count += 1;
+    let (prev_high, prev_low, prev_close) = self.prev;
+    self.prev = (high, low, close);
+    if self.count == 1 {
+      return None;
+    }
+    let (diff_plus, diff_minus) = (high - prev_high, prev_low - low);
+    let (mut plus_dm, mut minus_dm) = (0., 0.);
+    if diff_minus > 0. && diff_plus < diff_minus {
+      minus_dm = diff_minus;
+    } else if diff_plus > 0. && diff_plus > diff_minus {
+      plus_dm = diff_plus;
+    }
+    let tr = true_range(high, low, prev_close);
+    if self.count <= period {
+      self.plus_dm += plus_dm;
+      self.minus_dm += minus_dm;
+      self.tr += tr;
+      return None;
+    }
+    let n = period as f64;
+    self.plus_dm = self.plus_dm - self.plus_dm / n + plus_dm;
+    self.minus_dm = self.minus_dm - self.minus_dm / n + minus_dm;
+    self.tr = self.tr - self.tr / n + tr;
+    if is_zero(self.tr) {
+      return Some((0., 0.));
+    }
+    Some((
+      100. * (self.plus_dm / self.tr),
+      100. * (self.minus_dm / self.tr),
+    ))
+  }
+}
+
+/// 平均趋向指数，DX 按 Wilder 的方式平滑，DX 的第一个平滑值为前 period 个 DX 的均值
+pub struct ADXIndicator {
+  period: usize,
+  line: IndicatorLine,
+  state: ADXState,
+}
+impl_indicator_with_period!(ADXIndicator);
+impl_indicator_trait!(ADXIndicator);
+
+#[derive(Default)]
+struct ADXState {
+  dm: DMState,
+  count: usize,
+  adx: f64,
+}
+
+impl Indicator for ADXIndicator {
+  fn name(&self) -> &'static str {
+    "ADX"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["high", "low", "close"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    2 * self.period - 1
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_ADX(
+          0, end, ins[0], ins[1], ins[2], period, out_begin, out_size, outs[0],
+        )
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let period = self.period;
+    let value = check_update(self, inputs).and_then(|[high, low, close]| {
+      let state = &mut self.state;
+      let (plus_di, minus_di) = state.dm.next(period, high, low, close)?;
+      let sum = plus_di + minus_di;
+      // DI 都为 0 时没有 DX，前 period 个 DX 计为 0，之后保持 ADX 不变
+      let dx = (!is_zero(sum)).then(|| 100. * ((minus_di - plus_di).abs() / sum));
+      let n = period as f64;
+      state.count += 1;
+      if state.count < period {
+        state.adx += dx.unwrap_or(0.);
+        return None;
+      }
+      if state.count == period {
+        state.adx = (state.adx + dx.unwrap_or(0.)) / n;
+      } else if let Some(dx) = dx {
+        state.adx = (state.adx * (n - 1.) + dx) / n;
+      }
+      Some(state.adx)
+    });
+    self.line.push(value);
+  }
+}
+
+/// 趋向指标，输出依次为 +DI 和 -DI
+pub struct DMIIndicator {
+  period: usize,
+  lines: [IndicatorLine; 2],
+  state: DMState,
+}
+
+impl DMIIndicator {
+  pub fn new(period: usize) -> Self {
+    if period < 2 {
+      panic!("DMIIndicator period must gte 2");
+    }
+    Self {
+      period,
+      lines: Default::default(),
+      state: Default::default(),
+    }
+  }
+  #[inline]
+  pub fn plus_di(&self) -> &IndicatorLine {
+    &self.lines[0]
+  }
+  #[inline]
+  pub fn minus_di(&self) -> &IndicatorLine {
+    &self.lines[1]
+  }
+}
+
+impl Indicator for DMIIndicator {
+  fn name(&self) -> &'static str {
+    "DMI"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["high", "low", "close"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    self.period
+  }
+  fn outputs(&self) -> &'static [&'static str] {
+    &["plus_di", "minus_di"]
+  }
+  fn output(&self, index: usize) -> &IndicatorLine {
+    &self.lines[index]
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    let [plus_di, minus_di] = &mut self.lines;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [plus_di],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_PLUS_DI(
+          0, end, ins[0], ins[1], ins[2], period, out_begin, out_size, outs[0],
+        )
+      },
+    );
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [minus_di],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_MINUS_DI(
+          0, end, ins[0], ins[1], ins[2], period, out_begin, out_size, outs[0],
+        )
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.lines = Default::default();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let period = self.period;
+    let values = check_update(self, inputs)
+      .and_then(|[high, low, close]| self.state.next(period, high, low, close));
+    self.lines[0].push(values.map(|(plus_di, _)| plus_di));
+    self.lines[1].push(values.map(|(_, minus_di)| minus_di));
+  }
+}
+
+#[test]
+fn test_adx_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  // 每个 bar 上涨 1，+DM 为 1，-DM 为 0，真实波幅为 2
+  let mut dmi = DMIIndicator::new(2);
+  let mut adx = ADXIndicator::new(2);
+  for i in 0..5 {
+    let c = i as f64;
+    let inputs = [Some(c + 1.), Some(c - 1.), Some(c)];
+    dmi.update(&inputs);
+    adx.update(&inputs);
+  }
+  assert_eq!(dmi.plus_di().start_pos(), 2);
+  assert_eq!(dmi.plus_di().at(2), Some(50.));
+  assert_eq!(dmi.minus_di().at(4), Some(0.));
+  assert_eq!(adx.line.start_pos(), 3);
+  assert_eq!(adx.at(3), Some(100.));
+  assert_eq!(adx.at(4), Some(100.));
+
+  let high = D(vec![5., 6., 7., 6., 8., 9., 8., 7., 6., 8., 10., 9.], 0);
+  let low = D(vec![3., 4., 5., 4., 5., 7., 6., 5., 4., 5., 7., 7.], 0);
+  let close = D(
+    vec![4.5, 5., 6.5, 4.5, 7.5, 8., 6.5, 6., 4.5, 7., 9.5, 8.],
+    0,
+  );
+  crate::indicator::base::assert_outputs(
+    || ADXIndicator::new(3),
+    &[&high, &low, &close],
+    &[(
+      5,
+      &[
+        50.137362637362635,
+        40.14227217649271,
+        32.69996298545547,
+        36.547806524930905,
+        33.2261533096905,
+        41.39140067436389,
+        46.83489891747948,
+      ],
+    )],
+  );
+  crate::indicator::base::assert_outputs(
+    || DMIIndicator::new(3),
+    &[&high, &low, &close],
+    &[
+      (
+        3,
+        &[
+          25.806451612903228,
+          41.6,
+          44.134078212290504,
+          30.384615384615387,
+          20.707732634338146,
+          14.013303769401336,
+          33.81210830909473,
+          46.00618494493891,
+          31.426243708600744,
+        ],
+      ),
+      (
+        3,
+        &[
+          19.35483870967742,
+          9.6,
+          6.703910614525141,
+          20.19230769230769,
+          29.68545216251638,
+          36.252771618625275,
+          19.61078293202195,
+          12.332176798913864,
+          8.423954170597952,
+        ],
+      ),
+    ],
+  );
+}
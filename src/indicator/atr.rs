@@ -0,0 +1,291 @@
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::{TA_ATR, TA_NATR, TA_TRANGE};
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  impl_indicator_trait, impl_indicator_with_period, impl_indicator_without_period,
+  indicator::{
+    base::{check_inputs, check_update},
+    state::{is_zero, true_range},
+  },
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// 真实波幅：high 和 low 以及前一个 bar 的 close 三者中的最大值减去最小值
+pub struct TRANGEIndicator {
+  line: IndicatorLine,
+  state: Option<f64>,
+}
+impl_indicator_without_period!(TRANGEIndicator, state);
+impl_indicator_trait!(TRANGEIndicator);
+
+impl Indicator for TRANGEIndicator {
+  fn name(&self) -> &'static str {
+    "TRANGE"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["high", "low", "close"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    Vec::new()
+  }
+  fn lookback(&self) -> usize {
+    1
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_TRANGE(0, end, ins[0], ins[1], ins[2], out_begin, out_size, outs[0])
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let value = check_update(self, inputs).and_then(|[high, low, close]| {
+      let prev_close = self.state.replace(close)?;
+      Some(true_range(high, low, prev_close))
+    });
+    self.line.push(value);
+  }
+}
+
+/// 真实波幅按 Wilder 的方式平滑，第一个值为前 period 个真实波幅的均值
+#[derive(Default)]
+struct ATRState {
+  prev_close: Option<f64>,
+  count: usize,
+  sum: f64,
+  atr: f64,
+}
+
+impl ATRState {
+  fn next(&mut self, period: usize, high: f64, low: f64, close: f64) -> Option<f64> {
+    let prev_close = self.prev_close.replace(close)?;
+    let tr = true_range(high, low, prev_close);
+    self.count += 1;
+    if self.count < period {
+      self.sum += tr;
+      return None;
+    }
+    self.atr = if self.count == period {
+      (self.sum + tr) / period as f64
+    } else {
+      (self.atr * (period - 1) as f64 + tr) / period as f64
+    };
+    Some(self.atr)
+  }
+}
+
+/// 平均真实波幅，常用于计算止损距离
+pub struct ATRIndicator {
+  period: usize,
+  line: IndicatorLine,
+  state: ATRState,
+}
+impl_indicator_with_period!(ATRIndicator);
+impl_indicator_trait!(ATRIndicator);
+
+impl Indicator for ATRIndicator {
+  fn name(&self) -> &'static str {
+    "ATR"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["high", "low", "close"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    self.period
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_ATR(
+          0, end, ins[0], ins[1], ins[2], period, out_begin, out_size, outs[0],
+        )
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let period = self.period;
+    let value = check_update(self, inputs)
+      .and_then(|[high, low, close]| self.state.next(period, high, low, close));
+    self.line.push(value);
+  }
+}
+
+/// 归一化的平均真实波幅：ATR 相对 close 的百分比，close 为 0 时为 0
+pub struct NATRIndicator {
+  period: usize,
+  line: IndicatorLine,
+  state: ATRState,
+}
+impl_indicator_with_period!(NATRIndicator);
+impl_indicator_trait!(NATRIndicator);
+
+impl Indicator for NATRIndicator {
+  fn name(&self) -> &'static str {
+    "NATR"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["high", "low", "close"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    self.period
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_NATR(
+          0, end, ins[0], ins[1], ins[2], period, out_begin, out_size, outs[0],
+        )
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let period = self.period;
+    let value = check_update(self, inputs).and_then(|[high, low, close]| {
+      let atr = self.state.next(period, high, low, close)?;
+      Some(if !is_zero(close) {
+        (atr / close) * 100.
+      } else {
+        0.
+      })
+    });
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_atr_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let bars = [[2., 1., 2.], [4., 3., 3.], [3., 1., 2.], [6., 4., 5.]];
+  let (mut trange, mut atr, mut natr) = (
+    TRANGEIndicator::new(),
+    ATRIndicator::new(2),
+    NATRIndicator::new(2),
+  );
+  for [h, l, c] in bars {
+    for ind in [
+      &mut trange as &mut dyn Indicator,
+      &mut atr as &mut dyn Indicator,
+      &mut natr as &mut dyn Indicator,
+    ] {
+      ind.update(&[Some(h), Some(l), Some(c)]);
+    }
+  }
+  // 真实波幅为 2、2、4
+  assert_eq!(trange.line.start_pos(), 1);
+  assert_eq!(trange.line.as_slice(), &[0., 2., 2., 4.]);
+  assert_eq!(atr.line.start_pos(), 2);
+  assert_eq!(atr.at(2), Some(2.));
+  assert_eq!(atr.at(3), Some(3.));
+  assert_eq!(natr.at(2), Some(100.));
+  assert_eq!(natr.at(3), Some(60.));
+  let high = D(vec![5., 6., 7., 6., 8., 9., 8., 7., 6., 8., 10., 9.], 1);
+  let low = D(vec![3., 4., 5., 4., 5., 7., 6., 5., 4., 5., 7., 7.], 0);
+  let close = D(
+    vec![4.5, 5., 6.5, 4.5, 7.5, 8., 6.5, 6., 4.5, 7., 9.5, 8.],
+    0,
+  );
+  crate::indicator::base::assert_outputs(
+    || ATRIndicator::new(3),
+    &[&high, &low, &close],
+    &[(
+      4,
+      &[
+        2.6666666666666665,
+        2.444444444444444,
+        2.2962962962962963,
+        2.197530864197531,
+        2.1316872427983538,
+        2.5877914951989025,
+        2.725194330132602,
+        2.6501295534217344,
+      ],
+    )],
+  );
+  crate::indicator::base::assert_outputs(
+    || NATRIndicator::new(3),
+    &[&high, &low, &close],
+    &[(
+      4,
+      &[
+        35.55555555555555,
+        30.555555555555554,
+        35.32763532763533,
+        36.62551440329218,
+        47.3708276177412,
+        36.96844993141289,
+        28.686256106658963,
+        33.12661941777168,
+      ],
+    )],
+  );
+  crate::indicator::base::assert_outputs(
+    TRANGEIndicator::new,
+    &[&high, &low, &close],
+    &[(2, &[2., 2.5, 3.5, 2., 2., 2., 2., 3.5, 3., 2.5])],
+  );
+}
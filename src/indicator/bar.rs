@@ -0,0 +1,141 @@
+use crate::{Bar, CsvDataLine, CsvDataSource, DataLineFeed};
+
+use super::{base::Indicator, graph::Series};
+
+/// 指标的输入对应的 bar 数据列，输入的名称不是 bar 数据列时 panic
+pub(crate) fn bar_inputs<I: Indicator + ?Sized>(indicator: &I) -> Vec<Series> {
+  indicator
+    .inputs()
+    .iter()
+    .map(|name| {
+      Series::from_input(name)
+        .unwrap_or_else(|| panic!("input {} of {} is not a bar column", name, indicator.name()))
+    })
+    .collect()
+}
+
+fn bar_line<'a>(data: &'a CsvDataSource, series: &Series) -> &'a CsvDataLine {
+  let line = match series {
+    Series::Open => &data.open,
+    Series::High => &data.high,
+    Series::Low => &data.low,
+    Series::Close => &data.close,
+    Series::Volume => &data.volume,
+    _ => unreachable!(),
+  };
+  if line.is_empty() && !data.is_empty() {
+    panic!("{:?} is not loaded", series);
+  }
+  line
+}
+
+fn bar_value(bar: &Bar, series: &Series) -> f64 {
+  match series {
+    Series::Open => bar.open,
+    Series::High => bar.high,
+    Series::Low => bar.low,
+    Series::Close => bar.close,
+    Series::Volume => bar.volume,
+    _ => unreachable!(),
+  }
+}
+
+/// 以 bar（OHLCV）作为输入的指标。输入按 Indicator::inputs 的名称取 bar 中对应的数据列，
+/// 参见 Series::from_input。所有的 Indicator 都自动实现，比如基于 ATR 计算止损距离：
+///
+/// ```ignore
+/// let mut atr = ATRIndicator::new(14);
+/// atr.compute_bars(&data);
+/// let stop = data.close.at(index)? - 2. * atr.at(index)?;
+/// ```
+pub trait BarIndicator: Indicator {
+  /// 基于数据源的全部 bar 计算输出
+  fn compute_bars(&mut self, data: &CsvDataSource);
+  /// 追加一个 bar 并增量计算输出
+  fn update_bar(&mut self, bar: &Bar);
+}
+
+impl<I: Indicator + ?Sized> BarIndicator for I {
+  fn compute_bars(&mut self, data: &CsvDataSource) {
+    let series = bar_inputs(self);
+    let lines: Vec<&dyn DataLineFeed> = series
+      .iter()
+      .map(|series| bar_line(data, series) as &dyn DataLineFeed)
+      .collect();
+    self.compute(&lines);
+  }
+  fn update_bar(&mut self, bar: &Bar) {
+    let values: Vec<Option<f64>> = bar_inputs(self)
+      .iter()
+      .map(|series| Some(bar_value(bar, series)))
+      .collect();
+    self.update(&values);
+  }
+}
+
+#[test]
+fn test_bar_indicator() {
+  use crate::{CsvTimeType, DataLine, IndicatorGraph, VWAPIndicator};
+  use chrono::{TimeZone, Utc};
+
+  let bars: Vec<Bar> = [
+    [2., 3., 1., 2., 10.],
+    [2., 4., 2., 3., 30.],
+    [3., 6., 3., 6., 20.],
+  ]
+  .into_iter()
+  .enumerate()
+  .map(|(i, [o, h, l, c, v])| {
+    let t = Utc.with_ymd_and_hms(2022, 1, 3, i as u32, 0, 0).unwrap();
+    Bar::new(t, o, h, l, c, v)
+  })
+  .collect();
+  let data = CsvDataSource::builder()
+    .load_from_bars(bars.clone())
+    .unwrap();
+
+  // 典型价格为 2、3、5
+  let mut batch = VWAPIndicator::new(2);
+  batch.compute_bars(&data);
+  assert_eq!(batch.at(0), None);
+  assert_eq!(batch.at(1), Some(2.75));
+  assert_eq!(batch.at(2), Some(3.8));
+  let mut stream = VWAPIndicator::new(2);
+  for bar in &bars {
+    stream.update_bar(bar);
+  }
+  assert_eq!(stream.inner(), batch.inner());
+
+  let mut graph = IndicatorGraph::new();
+  let vwap = graph.add_bars(VWAPIndicator::new(2));
+  assert_eq!(
+    graph.add(
+      VWAPIndicator::new(2),
+      (Series::High, Series::Low, Series::Close, Series::Volume)
+    ),
+    vwap
+  );
+  graph.compute(&data).unwrap();
+  assert_eq!(graph.line(vwap).at(2), Some(3.8));
+
+  // 没有 volume 列
+  let content = "date,close\n2022-01-03 00:00,1";
+  let data = CsvDataSource::builder()
+    .time_field("date")
+    .time_type(CsvTimeType::Datetime("%Y-%m-%d %H:%M"))
+    .load_from_string(content)
+    .unwrap();
+  let e = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+    VWAPIndicator::new(2).compute_bars(&data)
+  }))
+  .unwrap_err();
+  assert_eq!(e.downcast_ref::<String>().unwrap(), "High is not loaded");
+  let e = std::panic::catch_unwind(|| {
+    IndicatorGraph::new().add_bars(crate::MaxIndicator::new());
+  })
+  .unwrap_err();
+  assert_eq!(
+    e.downcast_ref::<String>().unwrap(),
+    "input a of MAX is not a bar column"
+  );
+}
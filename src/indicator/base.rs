@@ -0,0 +1,253 @@
+use crate::{util::get_vec_at, DataLine, DataLineFeed};
+
+/// 指标的一个输出数据列。数据的长度和输入一致，有效初始位置之前的数据为 0。
+#[derive(Debug, Clone, Default)]
+pub struct IndicatorLine {
+  start_pos: usize,
+  data: Vec<f64>,
+}
+
+impl IndicatorLine {
+  pub fn new() -> Self {
+    Self {
+      start_pos: 0,
+      data: Vec::new(),
+    }
+  }
+  /// 有效初始位置
+  #[inline]
+  pub fn start_pos(&self) -> usize {
+    self.start_pos
+  }
+  #[inline]
+  pub fn as_slice(&self) -> &[f64] {
+    &self.data
+  }
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.data.len()
+  }
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+  /// 按输入的长度 len 重置数据（全部为 0）并指定有效初始位置，返回可写入的数据，用于实现 Indicator::compute。
+  pub fn reset(&mut self, len: usize, start_pos: usize) -> &mut [f64] {
+    self.start_pos = start_pos;
+    self.data.clear();
+    self.data.resize(len, 0.);
+    &mut self.data
+  }
+  /// 追加一个输出，None 表示尚无有效值，用于实现 Indicator::update。
+  /// 有效值必须连续，出现有效值之后再追加 None 时 panic。
+  pub fn push(&mut self, value: Option<f64>) {
+    match value {
+      Some(v) => self.data.push(v),
+      None => {
+        assert_eq!(
+          self.start_pos,
+          self.data.len(),
+          "indicator output must be contiguous"
+        );
+        self.data.push(0.);
+        self.start_pos += 1;
+      }
+    }
+  }
+}
+
+impl DataLineFeed for IndicatorLine {
+  #[inline(always)]
+  fn inner(&self) -> (&[f64], usize) {
+    (&self.data, self.start_pos)
+  }
+}
+impl DataLine for IndicatorLine {
+  #[inline(always)]
+  fn at(&self, index: usize) -> Option<f64> {
+    get_vec_at(&self.data, self.start_pos, index)
+  }
+}
+
+/// 指标的统一接口，指标可以保存在集合中（Box<dyn Indicator>）并以统一的方式计算。
+///
+/// compute 的输入按 inputs 的顺序传入，长度必须一致。输出的有效初始位置为
+/// 所有输入中最大的有效初始位置加上 lookback。
+///
+/// update 用于实时数据，每次追加一个 bar 的输入并增量计算该 bar 的输出，结果和 compute 一致。
+/// 代价和已经输入的 bar 数量无关，一般为 O(1)（单调队列为均摊 O(1)）；
+/// CCI 的平均绝对偏差需要遍历窗口，为 O(period)。compute 会覆盖之前的输出，两者不能混用。
+pub trait Indicator {
+  /// 指标的名称，比如 SMA
+  fn name(&self) -> &'static str;
+  /// 输入数据列的名称，比如 ["high", "low", "close"]
+  fn inputs(&self) -> &'static [&'static str] {
+    &["real"]
+  }
+  /// 参数的名称和取值
+  fn params(&self) -> Vec<(&'static str, f64)>;
+  /// 输出第一个有效值之前需要消耗的输入数据的数量
+  fn lookback(&self) -> usize;
+  /// 输出数据列的名称，比如 ["macd", "signal", "hist"]
+  fn outputs(&self) -> &'static [&'static str] {
+    &["real"]
+  }
+  /// 第 index 个输出数据列
+  fn output(&self, index: usize) -> &IndicatorLine;
+  /// 基于全部的输入数据计算输出
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]);
+  /// 追加一个 bar 的输入并增量计算输出，输入尚无有效值时传入 None
+  fn update(&mut self, inputs: &[Option<f64>]);
+}
+
+/// 检查输入的数量和长度，返回输入的长度和输出的有效初始位置，用于实现 Indicator::compute。
+pub fn check_inputs<I: Indicator + ?Sized>(
+  indicator: &I,
+  inputs: &[&dyn DataLineFeed],
+) -> (usize, usize) {
+  let expected = indicator.inputs();
+  if inputs.len() != expected.len() {
+    panic!(
+      "{} expects {} inputs ({}) but found {}",
+      indicator.name(),
+      expected.len(),
+      expected.join(", "),
+      inputs.len()
+    );
+  }
+  let len = inputs.first().map_or(0, |input| input.inner().0.len());
+  if inputs.iter().any(|input| input.inner().0.len() != len) {
+    panic!("{} inputs must have the same length", indicator.name());
+  }
+  let start_pos = inputs
+    .iter()
+    .map(|input| input.inner().1)
+    .max()
+    .unwrap_or(0);
+  (len, start_pos + indicator.lookback())
+}
+
+/// 检查增量输入的数量，所有输入都有效时返回输入的值，用于实现 Indicator::update。
+pub fn check_update<I: Indicator + ?Sized, const N: usize>(
+  indicator: &I,
+  inputs: &[Option<f64>],
+) -> Option<[f64; N]> {
+  let expected = indicator.inputs();
+  if inputs.len() != expected.len() || inputs.len() != N {
+    panic!(
+      "{} expects {} inputs ({}) but found {}",
+      indicator.name(),
+      expected.len(),
+      expected.join(", "),
+      inputs.len()
+    );
+  }
+  let mut values = [0.; N];
+  for (value, input) in values.iter_mut().zip(inputs) {
+    *value = (*input)?;
+  }
+  Some(values)
+}
+
+/// 逐个 bar 调用 update 计算全部的输出，输入按有效初始位置传入 None。
+/// 用于纯 Rust 实现 Indicator::compute，调用前需要重置指标的输出和状态。
+pub fn replay_update<I: Indicator + ?Sized>(indicator: &mut I, inputs: &[&dyn DataLineFeed]) {
+  let len = inputs.first().map_or(0, |input| input.inner().0.len());
+  for i in 0..len {
+    let values: Vec<Option<f64>> = inputs
+      .iter()
+      .map(|input| {
+        let (data, start_pos) = input.inner();
+        get_vec_at(data, start_pos, i)
+      })
+      .collect();
+    indicator.update(&values);
+  }
+}
+
+/// 分别用 compute 和逐个 bar 的 update 计算，检查每个输出都等于预先算好的 expected。
+/// expected 为每个输出的有效初始位置和从该位置开始的全部有效值。
+#[cfg(test)]
+pub(crate) fn assert_outputs<I: Indicator>(
+  new: impl Fn() -> I,
+  inputs: &[&dyn DataLineFeed],
+  expected: &[(usize, &[f64])],
+) {
+  let mut batch = new();
+  batch.compute(inputs);
+  let mut stream = new();
+  replay_update(&mut stream, inputs);
+  let len = inputs.first().map_or(0, |input| input.inner().0.len());
+  for (mode, indicator) in [("compute", &batch), ("update", &stream)] {
+    assert_eq!(
+      indicator.outputs().len(),
+      expected.len(),
+      "{}",
+      batch.name()
+    );
+    for (k, &(start_pos, values)) in expected.iter().enumerate() {
+      let actual = indicator.output(k);
+      let name = format!("{} {} output {}", batch.name(), mode, k);
+      assert_eq!(actual.start_pos(), start_pos, "{}", name);
+      assert_eq!(actual.len(), len, "{}", name);
+      assert_eq!(start_pos + values.len(), len, "{}", name);
+      for (i, &e) in values.iter().enumerate() {
+        let a = actual.as_slice()[start_pos + i];
+        assert!(
+          (e - a).abs() <= 1e-9 * e.abs().max(1.),
+          "{} at {}: {} != {}",
+          name,
+          start_pos + i,
+          e,
+          a
+        );
+      }
+    }
+  }
+}
+
+#[test]
+fn test_indicator_trait() {
+  use crate::{CrossOverIndicator, MaxIndicator, MinIndicator};
+
+  let a = IndicatorLine {
+    start_pos: 0,
+    data: vec![1., 3., 2., 5.],
+  };
+  let b = IndicatorLine {
+    start_pos: 1,
+    data: vec![2., 2., 4., 4.],
+  };
+  // 指标可以保存在集合中并以统一的方式计算
+  let mut indicators: Vec<Box<dyn Indicator>> = vec![
+    Box::new(MaxIndicator::new()),
+    Box::new(MinIndicator::new()),
+    Box::new(CrossOverIndicator::new()),
+  ];
+  for indicator in indicators.iter_mut() {
+    assert_eq!(indicator.inputs(), &["a", "b"]);
+    assert_eq!(indicator.outputs(), &["real"]);
+    assert!(indicator.params().is_empty());
+    indicator.compute(&[&a, &b]);
+  }
+  let outputs: Vec<&IndicatorLine> = indicators.iter().map(|i| i.output(0)).collect();
+  assert_eq!(outputs[0].as_slice(), &[0., 3., 4., 5.]);
+  assert_eq!(outputs[1].at(2), Some(2.));
+  assert_eq!(outputs[2].start_pos(), 2);
+  assert_eq!(outputs[2].as_slice(), &[0., 0., -1., 1.]);
+
+  // 输入的数据不足时没有任何有效值
+  let mut cross = CrossOverIndicator::new();
+  let short = IndicatorLine {
+    start_pos: 0,
+    data: vec![1.],
+  };
+  cross.compute(&[&short, &short]);
+  assert_eq!(cross.at(0), None);
+
+  let e = std::panic::catch_unwind(|| MaxIndicator::new().compute(&[&a])).unwrap_err();
+  assert_eq!(
+    e.downcast_ref::<String>().unwrap(),
+    "MAX expects 2 inputs (a, b) but found 1"
+  );
+}
@@ -0,0 +1,205 @@
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::{TA_MAType, TA_BBANDS};
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  indicator::{
+    base::{check_inputs, check_update},
+    state::SmaState,
+  },
+  DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// 布林带，输出依次为 upper、middle（SMA）和 lower，上下轨为 middle 加减总体标准差的倍数
+pub struct BBANDSIndicator {
+  period: usize,
+  dev_up: f64,
+  dev_down: f64,
+  lines: [IndicatorLine; 3],
+  state: BBANDSState,
+}
+
+/// 增量计算的状态：输入的 SMA 和输入的平方的 SMA
+#[derive(Default)]
+struct BBANDSState {
+  mean: SmaState,
+  mean_square: SmaState,
+}
+
+impl BBANDSIndicator {
+  /// 常用的参数为 (20, 2, 2)
+  pub fn new(period: usize, dev_up: f64, dev_down: f64) -> Self {
+    if period < 2 {
+      panic!("BBANDSIndicator period must gte 2");
+    }
+    Self {
+      period,
+      dev_up,
+      dev_down,
+      lines: Default::default(),
+      state: Default::default(),
+    }
+  }
+  #[inline]
+  pub fn upper(&self) -> &IndicatorLine {
+    &self.lines[0]
+  }
+  #[inline]
+  pub fn middle(&self) -> &IndicatorLine {
+    &self.lines[1]
+  }
+  #[inline]
+  pub fn lower(&self) -> &IndicatorLine {
+    &self.lines[2]
+  }
+}
+
+impl Indicator for BBANDSIndicator {
+  fn name(&self) -> &'static str {
+    "BBANDS"
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![
+      ("period", self.period as f64),
+      ("dev_up", self.dev_up),
+      ("dev_down", self.dev_down),
+    ]
+  }
+  fn lookback(&self) -> usize {
+    self.period - 1
+  }
+  fn outputs(&self) -> &'static [&'static str] {
+    &["upper", "middle", "lower"]
+  }
+  fn output(&self, index: usize) -> &IndicatorLine {
+    &self.lines[index]
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let (period, dev_up, dev_down) = (self.period as i32, self.dev_up, self.dev_down);
+    let [upper, middle, lower] = &mut self.lines;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [upper, middle, lower],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_BBANDS(
+          0,
+          end,
+          ins[0],
+          period,
+          dev_up,
+          dev_down,
+          TA_MAType::TA_MAType_SMA,
+          out_begin,
+          out_size,
+          outs[0],
+          outs[1],
+          outs[2],
+        )
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.lines = Default::default();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let (period, dev_up, dev_down) = (self.period, self.dev_up, self.dev_down);
+    let values = check_update(self, inputs).and_then(|[v]| {
+      let state = &mut self.state;
+      let mean_square = state.mean_square.next(period, v * v);
+      let middle = state.mean.next(period, v)?;
+      let variance = mean_square? - middle * middle;
+      // 和 ta-lib 一致，方差接近 0 或者由于误差为负时标准差为 0
+      let std_dev = if variance < 0.00000001 {
+        0.
+      } else {
+        variance.sqrt()
+      };
+      Some([
+        middle + std_dev * dev_up,
+        middle,
+        middle - std_dev * dev_down,
+      ])
+    });
+    for (k, line) in self.lines.iter_mut().enumerate() {
+      line.push(values.map(|values| values[k]));
+    }
+  }
+}
+
+#[test]
+fn test_bbands_indicator() {
+  use crate::DataLine;
+
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let mut ind = BBANDSIndicator::new(4, 2., 1.);
+  for v in [2., 4., 4., 6., 6.] {
+    ind.update(&[Some(v)]);
+  }
+  assert_eq!(ind.middle().start_pos(), 3);
+  // 2, 4, 4, 6 的均值为 4，总体标准差为 sqrt(2)
+  assert_eq!(ind.middle().at(3), Some(4.));
+  assert!((ind.upper().at(3).unwrap() - (4. + 2. * 2f64.sqrt())).abs() < 1e-9);
+  assert!((ind.lower().at(3).unwrap() - (4. - 2f64.sqrt())).abs() < 1e-9);
+  assert_eq!(ind.middle().at(4), Some(5.));
+  let data = D(
+    vec![
+      0., 1., 3., 2., 5., 4., 4., 8., 7., 9., 6., 10., 12., 11., 13., 12.,
+    ],
+    1,
+  );
+  crate::indicator::base::assert_outputs(
+    || BBANDSIndicator::new(5, 2., 1.),
+    &[&data],
+    &[
+      (
+        5,
+        &[
+          5.82842712474619,
+          5.639607805437114,
+          8.519183588453085,
+          8.849615361854383,
+          10.5182520563948,
+          10.24093010681705,
+          10.82842712474619,
+          13.070831300812525,
+          13.7182520563948,
+          15.23321838943783,
+          13.639607805437112,
+        ],
+      ),
+      (5, &[3., 3.6, 4.6, 5.6, 6.4, 6.8, 8., 8.8, 9.6, 10.4, 11.6]),
+      (
+        5,
+        &[
+          1.5857864376269049,
+          2.5801960972814433,
+          2.6404082057734573,
+          3.9751923190728076,
+          4.3408739718026,
+          5.079534946591474,
+          6.585786437626905,
+          6.6645843495937385,
+          7.5408739718026,
+          7.983390805281086,
+          10.580196097281442,
+        ],
+      ),
+    ],
+  );
+}
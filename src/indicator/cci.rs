@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::TA_CCI;
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  impl_indicator_trait, impl_indicator_with_period,
+  indicator::base::{check_inputs, check_update},
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// 顺势指标：典型价格 (high + low + close) / 3 与其 SMA 的差除以 0.015 倍的平均绝对偏差。
+/// 平均绝对偏差需要遍历窗口，增量计算的代价为 O(period)。
+pub struct CCIIndicator {
+  period: usize,
+  line: IndicatorLine,
+  /// 增量计算时最近 period 个典型价格
+  state: VecDeque<f64>,
+}
+impl_indicator_with_period!(CCIIndicator);
+impl_indicator_trait!(CCIIndicator);
+
+impl Indicator for CCIIndicator {
+  fn name(&self) -> &'static str {
+    "CCI"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["high", "low", "close"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    self.period - 1
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_CCI(
+          0, end, ins[0], ins[1], ins[2], period, out_begin, out_size, outs[0],
+        )
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let period = self.period;
+    let value = check_update(self, inputs).and_then(|[high, low, close]| {
+      let typical = (high + low + close) / 3.;
+      self.state.push_back(typical);
+      if self.state.len() > period {
+        self.state.pop_front();
+      }
+      if self.state.len() < period {
+        return None;
+      }
+      let n = period as f64;
+      let average = self.state.iter().sum::<f64>() / n;
+      let deviation = self.state.iter().map(|v| (v - average).abs()).sum::<f64>();
+      let diff = typical - average;
+      Some(if diff != 0. && deviation != 0. {
+        diff / (0.015 * (deviation / n))
+      } else {
+        0.
+      })
+    });
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_cci_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let mut ind = CCIIndicator::new(2);
+  for v in [1., 3., 3.] {
+    ind.update(&[Some(v), Some(v), Some(v)]);
+  }
+  // 典型价格 1, 3 的均值为 2，平均绝对偏差为 1
+  assert!((ind.at(1).unwrap() - 1. / 0.015).abs() < 1e-9);
+  assert_eq!(ind.at(2), Some(0.));
+  let high = D(vec![5., 6., 7., 6., 8., 9., 8., 7., 6., 8., 10., 9.], 0);
+  let low = D(vec![3., 4., 5., 4., 5., 7., 6., 5., 4., 5., 7., 7.], 0);
+  let close = D(
+    vec![4.5, 5., 6.5, 4.5, 7.5, 8., 6.5, 6., 4.5, 7., 9.5, 8.],
+    0,
+  );
+  crate::indicator::base::assert_outputs(
+    || CCIIndicator::new(3),
+    &[&high, &low, &close],
+    &[(
+      2,
+      &[
+        99.99999999999996,
+        -59.999999999999986,
+        80.00000000000001,
+        83.87096774193552,
+        -49.99999999999994,
+        -89.47368421052626,
+        -99.99999999999996,
+        83.33333333333336,
+        99.99999999999996,
+        14.285714285714308,
+      ],
+    )],
+  );
+}
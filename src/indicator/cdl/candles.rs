@@ -0,0 +1,156 @@
+use std::collections::VecDeque;
+
+/// K 线的比较基准，和 ta-lib 的默认设置（TA_CandleDefaultSettings）一致。
+/// 基准为之前 period 个 K 线的范围均值乘以 factor，period 为 0 时为当前 K 线的范围乘以 factor。
+#[derive(Debug, Clone, Copy)]
+pub(super) enum CandleSetting {
+  BodyLong,
+  BodyShort,
+  BodyDoji,
+  ShadowLong,
+  ShadowVeryLong,
+  ShadowShort,
+  ShadowVeryShort,
+  Near,
+  Far,
+  Equal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RangeType {
+  /// 实体的长度
+  RealBody,
+  /// high - low
+  HighLow,
+  /// 上影线和下影线的长度之和，均值再除以 2
+  Shadows,
+}
+
+impl CandleSetting {
+  fn params(self) -> (RangeType, usize, f64) {
+    use RangeType::*;
+    match self {
+      CandleSetting::BodyLong => (RealBody, 10, 1.),
+      CandleSetting::BodyShort => (RealBody, 10, 1.),
+      CandleSetting::BodyDoji => (HighLow, 10, 0.1),
+      CandleSetting::ShadowLong => (RealBody, 0, 1.),
+      CandleSetting::ShadowVeryLong => (RealBody, 0, 2.),
+      CandleSetting::ShadowShort => (Shadows, 10, 1.),
+      CandleSetting::ShadowVeryShort => (HighLow, 10, 0.1),
+      CandleSetting::Near => (HighLow, 5, 0.2),
+      CandleSetting::Far => (HighLow, 5, 0.6),
+      CandleSetting::Equal => (HighLow, 5, 0.05),
+    }
+  }
+}
+
+/// 最近的若干个 K 线（open、high、low、close），k 为距离最新的 K 线的偏移，0 为最新的 K 线
+pub(super) struct Candles<'a> {
+  bars: &'a VecDeque<[f64; 4]>,
+}
+
+impl<'a> Candles<'a> {
+  pub(super) fn new(bars: &'a VecDeque<[f64; 4]>) -> Self {
+    Self { bars }
+  }
+  #[inline(always)]
+  fn bar(&self, k: usize) -> &[f64; 4] {
+    &self.bars[self.bars.len() - 1 - k]
+  }
+  #[inline(always)]
+  pub(super) fn open(&self, k: usize) -> f64 {
+    self.bar(k)[0]
+  }
+  #[inline(always)]
+  pub(super) fn high(&self, k: usize) -> f64 {
+    self.bar(k)[1]
+  }
+  #[inline(always)]
+  pub(super) fn low(&self, k: usize) -> f64 {
+    self.bar(k)[2]
+  }
+  #[inline(always)]
+  pub(super) fn close(&self, k: usize) -> f64 {
+    self.bar(k)[3]
+  }
+  /// 阳线为 1，阴线为 -1，close 等于 open 时为阳线
+  #[inline(always)]
+  pub(super) fn color(&self, k: usize) -> i32 {
+    if self.close(k) >= self.open(k) {
+      1
+    } else {
+      -1
+    }
+  }
+  /// 实体的顶部
+  #[inline(always)]
+  pub(super) fn body_top(&self, k: usize) -> f64 {
+    self.open(k).max(self.close(k))
+  }
+  /// 实体的底部
+  #[inline(always)]
+  pub(super) fn body_bottom(&self, k: usize) -> f64 {
+    self.open(k).min(self.close(k))
+  }
+  #[inline(always)]
+  pub(super) fn body(&self, k: usize) -> f64 {
+    (self.close(k) - self.open(k)).abs()
+  }
+  #[inline(always)]
+  pub(super) fn upper_shadow(&self, k: usize) -> f64 {
+    self.high(k) - self.body_top(k)
+  }
+  #[inline(always)]
+  pub(super) fn lower_shadow(&self, k: usize) -> f64 {
+    self.body_bottom(k) - self.low(k)
+  }
+  #[inline(always)]
+  pub(super) fn high_low(&self, k: usize) -> f64 {
+    self.high(k) - self.low(k)
+  }
+  /// 第 k 个 K 线的实体向上跳空于第 j 个 K 线的实体
+  #[inline(always)]
+  pub(super) fn body_gap_up(&self, k: usize, j: usize) -> bool {
+    self.body_bottom(k) > self.body_top(j)
+  }
+  /// 第 k 个 K 线的实体向下跳空于第 j 个 K 线的实体
+  #[inline(always)]
+  pub(super) fn body_gap_down(&self, k: usize, j: usize) -> bool {
+    self.body_top(k) < self.body_bottom(j)
+  }
+  /// 第 k 个 K 线向上跳空于第 j 个 K 线（包括影线）
+  #[inline(always)]
+  pub(super) fn gap_up(&self, k: usize, j: usize) -> bool {
+    self.low(k) > self.high(j)
+  }
+  /// 第 k 个 K 线向下跳空于第 j 个 K 线（包括影线）
+  #[inline(always)]
+  pub(super) fn gap_down(&self, k: usize, j: usize) -> bool {
+    self.high(k) < self.low(j)
+  }
+  fn range(&self, range_type: RangeType, k: usize) -> f64 {
+    match range_type {
+      RangeType::RealBody => self.body(k),
+      RangeType::HighLow => self.high_low(k),
+      RangeType::Shadows => self.upper_shadow(k) + self.lower_shadow(k),
+    }
+  }
+  /// 第 k 个 K 线的比较基准，参见 CandleSetting
+  pub(super) fn avg(&self, setting: CandleSetting, k: usize) -> f64 {
+    let (range_type, period, factor) = setting.params();
+    let range = if period != 0 {
+      let sum = (k + 1..=k + period)
+        .rev()
+        .fold(0., |sum, j| sum + self.range(range_type, j));
+      sum / period as f64
+    } else {
+      self.range(range_type, k)
+    };
+    let div = if range_type == RangeType::Shadows {
+      2.
+    } else {
+      1.
+    };
+    factor * range / div
+  }
+}
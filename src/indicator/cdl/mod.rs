@@ -0,0 +1,521 @@
+mod candles;
+mod pattern;
+
+use std::collections::VecDeque;
+
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::*;
+
+use crate::{
+  impl_indicator_trait,
+  indicator::base::{check_inputs, check_update},
+  Bar, BarIndicator, CsvDataSource, DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+
+use candles::Candles;
+use pattern::HikkakeState;
+
+macro_rules! candle_patterns {
+  (
+    plain { $($(#[$doc: meta])* $plain: ident => ($plain_func: ident, $plain_lookback: literal, $plain_detect: ident);)+ }
+    penetration { $($(#[$pen_doc: meta])* $pen: ident => ($pen_func: ident, $pen_lookback: literal, $pen_detect: ident, $pen_default: literal);)+ }
+    stateful { $($(#[$st_doc: meta])* $st: ident => ($st_func: ident, $st_lookback: literal, $st_modified: literal);)+ }
+  ) => {
+    /// K 线形态，对应 ta-lib 的 TA_CDL* 函数
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum CandlePattern {
+      $($(#[$doc])* $plain,)+
+      $($(#[$pen_doc])* $pen,)+
+      $($(#[$st_doc])* $st,)+
+    }
+
+    impl CandlePattern {
+      /// 全部的 K 线形态
+      pub const ALL: &'static [CandlePattern] = &[
+        $(CandlePattern::$plain,)+
+        $(CandlePattern::$pen,)+
+        $(CandlePattern::$st,)+
+      ];
+      /// ta-lib 的函数名，比如 CDLENGULFING
+      pub fn name(self) -> &'static str {
+        match self {
+          $(CandlePattern::$plain => &stringify!($plain_func)[3..],)+
+          $(CandlePattern::$pen => &stringify!($pen_func)[3..],)+
+          $(CandlePattern::$st => &stringify!($st_func)[3..],)+
+        }
+      }
+      /// ta-lib 默认 K 线设置下的 lookback
+      pub fn lookback(self) -> usize {
+        match self {
+          $(CandlePattern::$plain => $plain_lookback,)+
+          $(CandlePattern::$pen => $pen_lookback,)+
+          $(CandlePattern::$st => $st_lookback,)+
+        }
+      }
+      /// 默认的穿透比例，只有晨星、暮星等形态有这个参数
+      pub fn default_penetration(self) -> Option<f64> {
+        match self {
+          $(CandlePattern::$pen => Some($pen_default),)+
+          _ => None,
+        }
+      }
+      /// 需要跨 K 线保存状态的 Hikkake 形态，返回是否为修正的 Hikkake
+      fn hikkake(self) -> Option<bool> {
+        match self {
+          $(CandlePattern::$st => Some($st_modified),)+
+          _ => None,
+        }
+      }
+      fn detect(self, c: &Candles, penetration: f64) -> i32 {
+        match self {
+          $(CandlePattern::$plain => pattern::$plain_detect(c),)+
+          $(CandlePattern::$pen => pattern::$pen_detect(c, penetration),)+
+          $(CandlePattern::$st => unreachable!(),)+
+        }
+      }
+      /// 调用 ta-lib 识别形态，输入依次为 open、high、low 和 close
+      #[cfg(feature = "ta-lib")]
+      unsafe fn ta_call(
+        self,
+        end: TA_Integer,
+        ins: &[*const f64],
+        out_begin: &mut TA_Integer,
+        out_size: &mut TA_Integer,
+        out: *mut TA_Integer,
+        penetration: f64,
+      ) -> TA_RetCode {
+        match self {
+          $(CandlePattern::$plain => $plain_func(0, end, ins[0], ins[1], ins[2], ins[3], out_begin, out_size, out),)+
+          $(CandlePattern::$pen => $pen_func(0, end, ins[0], ins[1], ins[2], ins[3], penetration, out_begin, out_size, out),)+
+          $(CandlePattern::$st => $st_func(0, end, ins[0], ins[1], ins[2], ins[3], out_begin, out_size, out),)+
+        }
+      }
+    }
+  };
+}
+
+candle_patterns! {
+  plain {
+    /// 两只乌鸦
+    TwoCrows => (TA_CDL2CROWS, 12, two_crows);
+    /// 三只乌鸦
+    ThreeBlackCrows => (TA_CDL3BLACKCROWS, 13, three_black_crows);
+    /// 三内部上涨和下跌
+    ThreeInside => (TA_CDL3INSIDE, 12, three_inside);
+    /// 三线打击
+    ThreeLineStrike => (TA_CDL3LINESTRIKE, 8, three_line_strike);
+    /// 三外部上涨和下跌
+    ThreeOutside => (TA_CDL3OUTSIDE, 3, three_outside);
+    /// 南方三星
+    ThreeStarsInSouth => (TA_CDL3STARSINSOUTH, 12, three_stars_in_south);
+    /// 三个白兵
+    ThreeWhiteSoldiers => (TA_CDL3WHITESOLDIERS, 12, three_white_soldiers);
+    /// 大敌当前
+    AdvanceBlock => (TA_CDLADVANCEBLOCK, 12, advance_block);
+    /// 捉腰带线
+    BeltHold => (TA_CDLBELTHOLD, 10, belt_hold);
+    /// 脱离
+    Breakaway => (TA_CDLBREAKAWAY, 14, breakaway);
+    /// 收盘缺影线
+    ClosingMarubozu => (TA_CDLCLOSINGMARUBOZU, 10, closing_marubozu);
+    /// 藏婴吞没
+    ConcealBabySwallow => (TA_CDLCONCEALBABYSWALL, 13, conceal_baby_swallow);
+    /// 反击线
+    Counterattack => (TA_CDLCOUNTERATTACK, 11, counterattack);
+    /// 十字
+    Doji => (TA_CDLDOJI, 10, doji);
+    /// 十字星
+    DojiStar => (TA_CDLDOJISTAR, 11, doji_star);
+    /// 蜻蜓十字
+    DragonflyDoji => (TA_CDLDRAGONFLYDOJI, 10, dragonfly_doji);
+    /// 吞没
+    Engulfing => (TA_CDLENGULFING, 2, engulfing);
+    /// 向上（下）跳空并列阳线
+    GapSideSideWhite => (TA_CDLGAPSIDESIDEWHITE, 7, gap_side_side_white);
+    /// 墓碑十字
+    GravestoneDoji => (TA_CDLGRAVESTONEDOJI, 10, gravestone_doji);
+    /// 锤头
+    Hammer => (TA_CDLHAMMER, 11, hammer);
+    /// 上吊线
+    HangingMan => (TA_CDLHANGINGMAN, 11, hanging_man);
+    /// 母子线
+    Harami => (TA_CDLHARAMI, 11, harami);
+    /// 十字孕线
+    HaramiCross => (TA_CDLHARAMICROSS, 11, harami_cross);
+    /// 风高浪大线
+    HighWave => (TA_CDLHIGHWAVE, 10, high_wave);
+    /// 家鸽
+    HomingPigeon => (TA_CDLHOMINGPIGEON, 11, homing_pigeon);
+    /// 三胞胎乌鸦
+    IdenticalThreeCrows => (TA_CDLIDENTICAL3CROWS, 12, identical_three_crows);
+    /// 颈内线
+    InNeck => (TA_CDLINNECK, 11, in_neck);
+    /// 倒锤头
+    InvertedHammer => (TA_CDLINVERTEDHAMMER, 11, inverted_hammer);
+    /// 反冲
+    Kicking => (TA_CDLKICKING, 11, kicking);
+    /// 反冲，方向由较长的 K 线决定
+    KickingByLength => (TA_CDLKICKINGBYLENGTH, 11, kicking_by_length);
+    /// 梯底
+    LadderBottom => (TA_CDLLADDERBOTTOM, 14, ladder_bottom);
+    /// 长脚十字
+    LongLeggedDoji => (TA_CDLLONGLEGGEDDOJI, 10, long_legged_doji);
+    /// 长蜡烛
+    LongLine => (TA_CDLLONGLINE, 10, long_line);
+    /// 光头光脚
+    Marubozu => (TA_CDLMARUBOZU, 10, marubozu);
+    /// 相同低价
+    MatchingLow => (TA_CDLMATCHINGLOW, 6, matching_low);
+    /// 颈上线
+    OnNeck => (TA_CDLONNECK, 11, on_neck);
+    /// 刺透
+    Piercing => (TA_CDLPIERCING, 11, piercing);
+    /// 黄包车夫
+    RickshawMan => (TA_CDLRICKSHAWMAN, 10, rickshaw_man);
+    /// 上升（下降）三法
+    RiseFallThreeMethods => (TA_CDLRISEFALL3METHODS, 14, rise_fall_three_methods);
+    /// 分离线
+    SeparatingLines => (TA_CDLSEPARATINGLINES, 11, separating_lines);
+    /// 射击之星
+    ShootingStar => (TA_CDLSHOOTINGSTAR, 11, shooting_star);
+    /// 短蜡烛
+    ShortLine => (TA_CDLSHORTLINE, 10, short_line);
+    /// 纺锤
+    SpinningTop => (TA_CDLSPINNINGTOP, 10, spinning_top);
+    /// 停顿
+    StalledPattern => (TA_CDLSTALLEDPATTERN, 12, stalled_pattern);
+    /// 条形三明治
+    StickSandwich => (TA_CDLSTICKSANDWICH, 7, stick_sandwich);
+    /// 探水竿
+    Takuri => (TA_CDLTAKURI, 10, takuri);
+    /// 跳空并列阴阳线
+    TasukiGap => (TA_CDLTASUKIGAP, 7, tasuki_gap);
+    /// 插入
+    Thrusting => (TA_CDLTHRUSTING, 11, thrusting);
+    /// 三星
+    Tristar => (TA_CDLTRISTAR, 12, tristar);
+    /// 奇特三河床
+    UniqueThreeRiver => (TA_CDLUNIQUE3RIVER, 12, unique_three_river);
+    /// 向上跳空的两只乌鸦
+    UpsideGapTwoCrows => (TA_CDLUPSIDEGAP2CROWS, 12, upside_gap_two_crows);
+    /// 上升（下降）跳空三法
+    XSideGapThreeMethods => (TA_CDLXSIDEGAP3METHODS, 2, x_side_gap_three_methods);
+  }
+  penetration {
+    /// 弃婴
+    AbandonedBaby => (TA_CDLABANDONEDBABY, 12, abandoned_baby, 0.3);
+    /// 乌云压顶
+    DarkCloudCover => (TA_CDLDARKCLOUDCOVER, 11, dark_cloud_cover, 0.5);
+    /// 十字暮星
+    EveningDojiStar => (TA_CDLEVENINGDOJISTAR, 12, evening_doji_star, 0.3);
+    /// 暮星
+    EveningStar => (TA_CDLEVENINGSTAR, 12, evening_star, 0.3);
+    /// 铺垫
+    MatHold => (TA_CDLMATHOLD, 14, mat_hold, 0.5);
+    /// 十字晨星
+    MorningDojiStar => (TA_CDLMORNINGDOJISTAR, 12, morning_doji_star, 0.3);
+    /// 晨星
+    MorningStar => (TA_CDLMORNINGSTAR, 12, morning_star, 0.3);
+  }
+  stateful {
+    /// 陷阱，形态出现后 3 个 K 线内确认时信号为 ±200
+    Hikkake => (TA_CDLHIKKAKE, 5, false);
+    /// 修正的陷阱，形态出现后 3 个 K 线内确认时信号为 ±200
+    HikkakeMod => (TA_CDLHIKKAKEMOD, 10, true);
+  }
+}
+
+/// K 线形态识别，输出为 100（看涨）、-100（看跌）或者 0（没有出现形态），
+/// Hikkake 形态被确认时为 ±200。K 线的长短等比较基准和 ta-lib 的默认设置一致。
+pub struct CDLIndicator {
+  pattern: CandlePattern,
+  penetration: f64,
+  line: IndicatorLine,
+  state: CDLState,
+}
+impl_indicator_trait!(CDLIndicator);
+
+#[derive(Default)]
+struct CDLState {
+  /// 最近 lookback + 1 个 K 线
+  bars: VecDeque<[f64; 4]>,
+  count: usize,
+  hikkake: HikkakeState,
+}
+
+impl CDLIndicator {
+  pub fn new(pattern: CandlePattern) -> Self {
+    Self {
+      pattern,
+      penetration: pattern.default_penetration().unwrap_or(0.),
+      line: IndicatorLine::new(),
+      state: Default::default(),
+    }
+  }
+  /// 穿透比例，比如晨星的第三个 K 线的 close 需要超过第一个 K 线实体的比例
+  pub fn penetration(mut self, penetration: f64) -> Self {
+    if self.pattern.default_penetration().is_none() {
+      panic!("{} has no penetration", self.pattern.name());
+    }
+    if penetration < 0. {
+      panic!("CDLIndicator penetration must gte 0");
+    }
+    self.penetration = penetration;
+    self
+  }
+  #[inline]
+  pub fn pattern(&self) -> CandlePattern {
+    self.pattern
+  }
+}
+
+impl Indicator for CDLIndicator {
+  fn name(&self) -> &'static str {
+    self.pattern.name()
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["open", "high", "low", "close"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    match self.pattern.default_penetration() {
+      Some(_) => vec![("penetration", self.penetration)],
+      None => Vec::new(),
+    }
+  }
+  fn lookback(&self) -> usize {
+    self.pattern.lookback()
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let (pattern, penetration) = (self.pattern, self.penetration);
+    // ta-lib 输出整数的信号
+    let mut signals: Vec<TA_Integer> = vec![0; len.saturating_sub(start_pos)];
+    let out = signals.as_mut_ptr();
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, _| unsafe {
+        pattern.ta_call(end, ins, out_begin, out_size, out, penetration)
+      },
+    );
+    let data = self.line.reset(len, start_pos);
+    for (v, signal) in data.iter_mut().skip(start_pos).zip(signals) {
+      *v = signal as f64;
+    }
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let (pattern, penetration, lookback) = (self.pattern, self.penetration, self.lookback());
+    let value = check_update(self, inputs).and_then(|bar| {
+      let state = &mut self.state;
+      state.bars.push_back(bar);
+      if state.bars.len() > lookback + 1 {
+        state.bars.pop_front();
+      }
+      let i = state.count;
+      state.count += 1;
+      let candles = Candles::new(&state.bars);
+      let signal = match pattern.hikkake() {
+        // 和 ta-lib 一致，从第一个输出之前的 3 个 K 线开始识别形态
+        Some(modified) => {
+          if i + 3 < lookback {
+            return None;
+          }
+          state.hikkake.next(&candles, i, modified)
+        }
+        None => {
+          if i < lookback {
+            return None;
+          }
+          pattern.detect(&candles, penetration)
+        }
+      };
+      (i >= lookback).then_some(signal as f64)
+    });
+    self.line.push(value);
+  }
+}
+
+/// 同时识别多个 K 线形态
+pub struct CandleScanner {
+  indicators: Vec<CDLIndicator>,
+}
+
+impl CandleScanner {
+  pub fn new(patterns: &[CandlePattern]) -> Self {
+    Self {
+      indicators: patterns.iter().map(|p| CDLIndicator::new(*p)).collect(),
+    }
+  }
+  /// 识别全部的 K 线形态
+  pub fn all() -> Self {
+    Self::new(CandlePattern::ALL)
+  }
+  /// 基于全部的输入（open、high、low、close）识别形态
+  pub fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    for indicator in self.indicators.iter_mut() {
+      indicator.compute(inputs);
+    }
+  }
+  /// 追加一个 K 线并增量识别形态
+  pub fn update(&mut self, inputs: &[Option<f64>]) {
+    for indicator in self.indicators.iter_mut() {
+      indicator.update(inputs);
+    }
+  }
+  /// 基于数据源的全部 bar 识别形态
+  pub fn compute_bars(&mut self, data: &CsvDataSource) {
+    for indicator in self.indicators.iter_mut() {
+      indicator.compute_bars(data);
+    }
+  }
+  /// 追加一个 bar 并增量识别形态
+  pub fn update_bar(&mut self, bar: &Bar) {
+    for indicator in self.indicators.iter_mut() {
+      indicator.update_bar(bar);
+    }
+  }
+  pub fn indicator(&self, pattern: CandlePattern) -> Option<&CDLIndicator> {
+    self.indicators.iter().find(|i| i.pattern == pattern)
+  }
+  /// 第 index 个 bar 上出现的形态及其信号
+  pub fn matches(&self, index: usize) -> Vec<(CandlePattern, f64)> {
+    self
+      .indicators
+      .iter()
+      .filter_map(|i| match i.at(index) {
+        Some(signal) if signal != 0. => Some((i.pattern, signal)),
+        _ => None,
+      })
+      .collect()
+  }
+}
+
+#[test]
+fn test_cdl_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  /// compute 和 update 的输出都从 start_pos 开始等于 expected
+  fn check(new: impl Fn() -> CDLIndicator, bars: &[[f64; 4]], start_pos: usize, expected: &[f64]) {
+    let columns: [D; 4] = std::array::from_fn(|k| D(bars.iter().map(|bar| bar[k]).collect(), 0));
+    let [open, high, low, close] = &columns;
+    crate::indicator::base::assert_outputs(
+      new,
+      &[open, high, low, close],
+      &[(start_pos, expected)],
+    );
+  }
+  // 实体为 1，high - low 为 2 的阳线
+  let base = [10., 11.5, 9.5, 11.];
+
+  assert_eq!(CandlePattern::ALL.len(), 61);
+  assert_eq!(CandlePattern::Engulfing.name(), "CDLENGULFING");
+  check(
+    || CDLIndicator::new(CandlePattern::Engulfing),
+    &[base, [11., 11.5, 9.5, 10.], [9.8, 11.8, 9.5, 11.5]],
+    2,
+    &[100.],
+  );
+
+  // 十字：实体不超过之前 10 个 K 线的 high - low 的均值的 0.1
+  let doji = [[base; 10].as_slice(), &[[10., 11., 9., 10.05], base]].concat();
+  check(
+    || CDLIndicator::new(CandlePattern::Doji),
+    &doji,
+    10,
+    &[100., 0.],
+  );
+
+  // 锤头：小实体、长下影线、几乎没有上影线，实体接近前一个 K 线的最低价
+  let hammer = [9.6, 9.95, 8.1, 9.9];
+  let bars = [[base; 11].as_slice(), &[hammer]].concat();
+  check(
+    || CDLIndicator::new(CandlePattern::Hammer),
+    &bars,
+    11,
+    &[100.],
+  );
+  check(
+    || CDLIndicator::new(CandlePattern::HangingMan),
+    &bars,
+    11,
+    &[0.],
+  );
+
+  // Hikkake：第 4 个 K 线形成形态（在第一个输出之前），第 5 个 K 线确认
+  check(
+    || CDLIndicator::new(CandlePattern::Hikkake),
+    &[
+      base,
+      base,
+      base,
+      [10.2, 11., 10., 10.8],
+      [10.5, 10.8, 9.8, 10.],
+      [10.5, 11.6, 10.4, 11.4],
+    ],
+    5,
+    &[200.],
+  );
+
+  // 启明星：长阴线、向下跳空的小实体、收盘价超过第一个实体的 penetration 比例的阳线
+  let bars = [
+    [base; 10].as_slice(),
+    &[
+      [12., 12.2, 8.8, 9.],
+      [8.3, 8.7, 8.1, 8.5],
+      [8.8, 10.4, 8.6, 10.2],
+    ],
+  ]
+  .concat();
+  let morning_star = || CDLIndicator::new(CandlePattern::MorningStar);
+  check(morning_star, &bars, 12, &[100.]);
+  // 9 + 3 * 0.5 = 10.5，收盘价 10.2 不足
+  check(|| morning_star().penetration(0.5), &bars, 12, &[0.]);
+
+  let ind = CDLIndicator::new(CandlePattern::MorningStar);
+  assert_eq!(ind.params(), vec![("penetration", 0.3)]);
+  let ind = ind.penetration(0.5);
+  assert_eq!(ind.params(), vec![("penetration", 0.5)]);
+  assert!(CDLIndicator::new(CandlePattern::Doji).params().is_empty());
+  let e = std::panic::catch_unwind(|| CDLIndicator::new(CandlePattern::Doji).penetration(0.5))
+    .err()
+    .unwrap();
+  assert_eq!(
+    e.downcast_ref::<String>().unwrap(),
+    "CDLDOJI has no penetration"
+  );
+
+  let mut scanner = CandleScanner::all();
+  for bar in [base; 11].iter().chain([&hammer]) {
+    scanner.update(&bar.map(Some));
+  }
+  let matches = scanner.matches(11);
+  assert!(matches.contains(&(CandlePattern::Hammer, 100.)));
+  assert!(!matches.iter().any(|(p, _)| *p == CandlePattern::HangingMan));
+  assert_eq!(
+    scanner.indicator(CandlePattern::Hammer).unwrap().at(11),
+    Some(100.)
+  );
+  assert!(scanner.matches(0).is_empty());
+}
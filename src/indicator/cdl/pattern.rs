@@ -0,0 +1,992 @@
+//! K 线形态的识别规则，和 ta-lib 的 TA_CDL* 一致，返回最新的 K 线上的信号。
+
+use super::candles::{CandleSetting::*, Candles};
+
+pub(super) fn two_crows(c: &Candles) -> i32 {
+  if c.color(2) == 1
+    && c.body(2) > c.avg(BodyLong, 2)
+    && c.color(1) == -1
+    && c.body_gap_up(1, 2)
+    && c.color(0) == -1
+    && c.open(0) < c.open(1)
+    && c.open(0) > c.close(1)
+    && c.close(0) > c.open(2)
+    && c.close(0) < c.close(2)
+  {
+    -100
+  } else {
+    0
+  }
+}
+
+pub(super) fn three_black_crows(c: &Candles) -> i32 {
+  if c.color(3) == 1
+    && c.color(2) == -1
+    && c.lower_shadow(2) < c.avg(ShadowVeryShort, 2)
+    && c.color(1) == -1
+    && c.lower_shadow(1) < c.avg(ShadowVeryShort, 1)
+    && c.color(0) == -1
+    && c.lower_shadow(0) < c.avg(ShadowVeryShort, 0)
+    && c.open(1) < c.open(2)
+    && c.open(1) > c.close(2)
+    && c.open(0) < c.open(1)
+    && c.open(0) > c.close(1)
+    && c.high(3) > c.close(2)
+    && c.close(2) > c.close(1)
+    && c.close(1) > c.close(0)
+  {
+    -100
+  } else {
+    0
+  }
+}
+
+pub(super) fn three_inside(c: &Candles) -> i32 {
+  if c.body(2) > c.avg(BodyLong, 2)
+    && c.body(1) <= c.avg(BodyShort, 1)
+    && c.body_top(1) < c.body_top(2)
+    && c.body_bottom(1) > c.body_bottom(2)
+    && ((c.color(2) == 1 && c.color(0) == -1 && c.close(0) < c.open(2))
+      || (c.color(2) == -1 && c.color(0) == 1 && c.close(0) > c.open(2)))
+  {
+    -c.color(2) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn three_line_strike(c: &Candles) -> i32 {
+  if c.color(3) == c.color(2)
+    && c.color(2) == c.color(1)
+    && c.color(0) == -c.color(1)
+    && c.open(2) >= c.body_bottom(3) - c.avg(Near, 3)
+    && c.open(2) <= c.body_top(3) + c.avg(Near, 3)
+    && c.open(1) >= c.body_bottom(2) - c.avg(Near, 2)
+    && c.open(1) <= c.body_top(2) + c.avg(Near, 2)
+    && ((c.color(1) == 1
+      && c.close(1) > c.close(2)
+      && c.close(2) > c.close(3)
+      && c.open(0) > c.close(1)
+      && c.close(0) < c.open(3))
+      || (c.color(1) == -1
+        && c.close(1) < c.close(2)
+        && c.close(2) < c.close(3)
+        && c.open(0) < c.close(1)
+        && c.close(0) > c.open(3)))
+  {
+    c.color(1) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn three_outside(c: &Candles) -> i32 {
+  if c.color(1) == 1
+    && c.color(2) == -1
+    && c.close(1) > c.open(2)
+    && c.open(1) < c.close(2)
+    && c.close(0) > c.close(1)
+  {
+    100
+  } else if c.color(1) == -1
+    && c.color(2) == 1
+    && c.open(1) > c.close(2)
+    && c.close(1) < c.open(2)
+    && c.close(0) < c.close(1)
+  {
+    -100
+  } else {
+    0
+  }
+}
+
+pub(super) fn three_stars_in_south(c: &Candles) -> i32 {
+  if c.color(2) == -1
+    && c.color(1) == -1
+    && c.color(0) == -1
+    // 第一个为长阴线且有长下影线
+    && c.body(2) > c.avg(BodyLong, 2)
+    && c.lower_shadow(2) > c.avg(ShadowLong, 2)
+    // 第二个更短，开盘价在第一个的范围内，最低价高于第一个的最低价
+    && c.body(1) < c.body(2)
+    && c.open(1) > c.close(2)
+    && c.open(1) <= c.high(2)
+    && c.low(1) < c.close(2)
+    && c.low(1) >= c.low(2)
+    && c.lower_shadow(1) > c.avg(ShadowVeryShort, 1)
+    // 第三个为没有影线的小阴线，在第二个的范围内
+    && c.body(0) < c.avg(BodyShort, 0)
+    && c.lower_shadow(0) < c.avg(ShadowVeryShort, 0)
+    && c.upper_shadow(0) < c.avg(ShadowVeryShort, 0)
+    && c.low(0) > c.low(1)
+    && c.high(0) < c.high(1)
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn three_white_soldiers(c: &Candles) -> i32 {
+  if c.color(2) == 1
+    && c.upper_shadow(2) < c.avg(ShadowVeryShort, 2)
+    && c.color(1) == 1
+    && c.upper_shadow(1) < c.avg(ShadowVeryShort, 1)
+    && c.color(0) == 1
+    && c.upper_shadow(0) < c.avg(ShadowVeryShort, 0)
+    && c.close(0) > c.close(1)
+    && c.close(1) > c.close(2)
+    && c.open(1) > c.open(2)
+    && c.open(1) <= c.close(2) + c.avg(Near, 2)
+    && c.open(0) > c.open(1)
+    && c.open(0) <= c.close(1) + c.avg(Near, 1)
+    && c.body(1) > c.body(2) - c.avg(Far, 2)
+    && c.body(0) > c.body(1) - c.avg(Far, 1)
+    && c.body(0) > c.avg(BodyShort, 0)
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn abandoned_baby(c: &Candles, penetration: f64) -> i32 {
+  if c.body(2) > c.avg(BodyLong, 2)
+    && c.body(1) <= c.avg(BodyDoji, 1)
+    && c.body(0) > c.avg(BodyShort, 0)
+    && ((c.color(2) == 1
+      && c.color(0) == -1
+      && c.close(0) < c.close(2) - c.body(2) * penetration
+      && c.gap_up(1, 2)
+      && c.gap_down(0, 1))
+      || (c.color(2) == -1
+        && c.color(0) == 1
+        && c.close(0) > c.close(2) + c.body(2) * penetration
+        && c.gap_down(1, 2)
+        && c.gap_up(0, 1)))
+  {
+    c.color(0) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn advance_block(c: &Candles) -> i32 {
+  if c.color(2) == 1
+    && c.color(1) == 1
+    && c.color(0) == 1
+    && c.close(0) > c.close(1)
+    && c.close(1) > c.close(2)
+    && c.open(1) > c.open(2)
+    && c.open(1) <= c.close(2) + c.avg(Near, 2)
+    && c.open(0) > c.open(1)
+    && c.open(0) <= c.close(1) + c.avg(Near, 1)
+    && c.body(2) > c.avg(BodyLong, 2)
+    && c.upper_shadow(2) < c.avg(ShadowShort, 2)
+    // 上涨乏力：实体逐渐变短或者上影线变长
+    && ((c.body(1) < c.body(2) - c.avg(Far, 2) && c.body(0) < c.body(1) + c.avg(Near, 1))
+      || c.body(0) < c.body(1) - c.avg(Far, 1)
+      || (c.body(0) < c.body(1)
+        && c.body(1) < c.body(2)
+        && (c.upper_shadow(0) > c.avg(ShadowShort, 0)
+          || c.upper_shadow(1) > c.avg(ShadowShort, 1)))
+      || (c.body(0) < c.body(1) && c.upper_shadow(0) > c.avg(ShadowLong, 0)))
+  {
+    -100
+  } else {
+    0
+  }
+}
+
+pub(super) fn belt_hold(c: &Candles) -> i32 {
+  if c.body(0) > c.avg(BodyLong, 0)
+    && ((c.color(0) == 1 && c.lower_shadow(0) < c.avg(ShadowVeryShort, 0))
+      || (c.color(0) == -1 && c.upper_shadow(0) < c.avg(ShadowVeryShort, 0)))
+  {
+    c.color(0) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn breakaway(c: &Candles) -> i32 {
+  if c.body(4) > c.avg(BodyLong, 4)
+    && c.color(4) == -c.color(3)
+    && c.color(3) == c.color(2)
+    && c.color(2) == c.color(1)
+    && c.color(1) == -c.color(0)
+    && ((c.color(4) == -1
+      && c.body_gap_down(3, 4)
+      && c.high(2) < c.high(3)
+      && c.low(2) < c.low(3)
+      && c.high(1) < c.high(2)
+      && c.low(1) < c.low(2)
+      && c.close(0) > c.open(3)
+      && c.close(0) < c.close(4))
+      || (c.color(4) == 1
+        && c.body_gap_up(3, 4)
+        && c.high(2) > c.high(3)
+        && c.low(2) > c.low(3)
+        && c.high(1) > c.high(2)
+        && c.low(1) > c.low(2)
+        && c.close(0) < c.open(3)
+        && c.close(0) > c.close(4)))
+  {
+    c.color(0) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn closing_marubozu(c: &Candles) -> i32 {
+  if c.body(0) > c.avg(BodyLong, 0)
+    && ((c.color(0) == 1 && c.upper_shadow(0) < c.avg(ShadowVeryShort, 0))
+      || (c.color(0) == -1 && c.lower_shadow(0) < c.avg(ShadowVeryShort, 0)))
+  {
+    c.color(0) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn conceal_baby_swallow(c: &Candles) -> i32 {
+  if c.color(3) == -1
+    && c.color(2) == -1
+    && c.color(1) == -1
+    && c.color(0) == -1
+    // 前两个为光头光脚的阴线
+    && c.lower_shadow(3) < c.avg(ShadowVeryShort, 3)
+    && c.upper_shadow(3) < c.avg(ShadowVeryShort, 3)
+    && c.lower_shadow(2) < c.avg(ShadowVeryShort, 2)
+    && c.upper_shadow(2) < c.avg(ShadowVeryShort, 2)
+    // 第三个向下跳空且上影线伸入第二个的实体
+    && c.body_gap_down(1, 2)
+    && c.upper_shadow(1) > c.avg(ShadowVeryShort, 1)
+    && c.high(1) > c.close(2)
+    // 第四个完全吞没第三个
+    && c.high(0) > c.high(1)
+    && c.low(0) < c.low(1)
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn counterattack(c: &Candles) -> i32 {
+  if c.color(1) == -c.color(0)
+    && c.body(1) > c.avg(BodyLong, 1)
+    && c.body(0) > c.avg(BodyLong, 0)
+    && c.close(0) <= c.close(1) + c.avg(Equal, 1)
+    && c.close(0) >= c.close(1) - c.avg(Equal, 1)
+  {
+    c.color(0) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn dark_cloud_cover(c: &Candles, penetration: f64) -> i32 {
+  if c.color(1) == 1
+    && c.body(1) > c.avg(BodyLong, 1)
+    && c.color(0) == -1
+    && c.open(0) > c.high(1)
+    && c.close(0) > c.open(1)
+    && c.close(0) < c.close(1) - c.body(1) * penetration
+  {
+    -100
+  } else {
+    0
+  }
+}
+
+pub(super) fn doji(c: &Candles) -> i32 {
+  if c.body(0) <= c.avg(BodyDoji, 0) {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn doji_star(c: &Candles) -> i32 {
+  if c.body(1) > c.avg(BodyLong, 1)
+    && c.body(0) <= c.avg(BodyDoji, 0)
+    && ((c.color(1) == 1 && c.body_gap_up(0, 1)) || (c.color(1) == -1 && c.body_gap_down(0, 1)))
+  {
+    -c.color(1) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn dragonfly_doji(c: &Candles) -> i32 {
+  if c.body(0) <= c.avg(BodyDoji, 0)
+    && c.upper_shadow(0) < c.avg(ShadowVeryShort, 0)
+    && c.lower_shadow(0) > c.avg(ShadowVeryShort, 0)
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn engulfing(c: &Candles) -> i32 {
+  if (c.color(0) == 1 && c.color(1) == -1 && c.close(0) > c.open(1) && c.open(0) < c.close(1))
+    || (c.color(0) == -1 && c.color(1) == 1 && c.open(0) > c.close(1) && c.close(0) < c.open(1))
+  {
+    c.color(0) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn evening_doji_star(c: &Candles, penetration: f64) -> i32 {
+  if c.body(2) > c.avg(BodyLong, 2)
+    && c.color(2) == 1
+    && c.body(1) <= c.avg(BodyDoji, 1)
+    && c.body_gap_up(1, 2)
+    && c.body(0) > c.avg(BodyShort, 0)
+    && c.color(0) == -1
+    && c.close(0) < c.close(2) - c.body(2) * penetration
+  {
+    -100
+  } else {
+    0
+  }
+}
+
+pub(super) fn evening_star(c: &Candles, penetration: f64) -> i32 {
+  if c.body(2) > c.avg(BodyLong, 2)
+    && c.color(2) == 1
+    && c.body(1) <= c.avg(BodyShort, 1)
+    && c.body_gap_up(1, 2)
+    && c.body(0) > c.avg(BodyShort, 0)
+    && c.color(0) == -1
+    && c.close(0) < c.close(2) - c.body(2) * penetration
+  {
+    -100
+  } else {
+    0
+  }
+}
+
+pub(super) fn gap_side_side_white(c: &Candles) -> i32 {
+  if ((c.body_gap_up(1, 2) && c.body_gap_up(0, 2))
+    || (c.body_gap_down(1, 2) && c.body_gap_down(0, 2)))
+    && c.color(1) == 1
+    && c.color(0) == 1
+    && c.body(0) >= c.body(1) - c.avg(Near, 1)
+    && c.body(0) <= c.body(1) + c.avg(Near, 1)
+    && c.open(0) >= c.open(1) - c.avg(Equal, 1)
+    && c.open(0) <= c.open(1) + c.avg(Equal, 1)
+  {
+    if c.body_gap_up(1, 2) {
+      100
+    } else {
+      -100
+    }
+  } else {
+    0
+  }
+}
+
+pub(super) fn gravestone_doji(c: &Candles) -> i32 {
+  if c.body(0) <= c.avg(BodyDoji, 0)
+    && c.lower_shadow(0) < c.avg(ShadowVeryShort, 0)
+    && c.upper_shadow(0) > c.avg(ShadowVeryShort, 0)
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn hammer(c: &Candles) -> i32 {
+  if c.body(0) < c.avg(BodyShort, 0)
+    && c.lower_shadow(0) > c.avg(ShadowLong, 0)
+    && c.upper_shadow(0) < c.avg(ShadowVeryShort, 0)
+    && c.body_bottom(0) <= c.low(1) + c.avg(Near, 1)
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn hanging_man(c: &Candles) -> i32 {
+  if c.body(0) < c.avg(BodyShort, 0)
+    && c.lower_shadow(0) > c.avg(ShadowLong, 0)
+    && c.upper_shadow(0) < c.avg(ShadowVeryShort, 0)
+    && c.body_bottom(0) >= c.high(1) - c.avg(Near, 1)
+  {
+    -100
+  } else {
+    0
+  }
+}
+
+pub(super) fn harami(c: &Candles) -> i32 {
+  if c.body(1) > c.avg(BodyLong, 1)
+    && c.body(0) <= c.avg(BodyShort, 0)
+    && c.body_top(0) < c.body_top(1)
+    && c.body_bottom(0) > c.body_bottom(1)
+  {
+    -c.color(1) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn harami_cross(c: &Candles) -> i32 {
+  if c.body(1) > c.avg(BodyLong, 1)
+    && c.body(0) <= c.avg(BodyDoji, 0)
+    && c.body_top(0) < c.body_top(1)
+    && c.body_bottom(0) > c.body_bottom(1)
+  {
+    -c.color(1) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn high_wave(c: &Candles) -> i32 {
+  if c.body(0) < c.avg(BodyShort, 0)
+    && c.upper_shadow(0) > c.avg(ShadowVeryLong, 0)
+    && c.lower_shadow(0) > c.avg(ShadowVeryLong, 0)
+  {
+    c.color(0) * 100
+  } else {
+    0
+  }
+}
+
+/// Hikkake 形态的状态：形态出现后的 3 个 K 线内 close 突破形态第二个 K 线的高点（低点）时确认
+#[derive(Default)]
+pub(super) struct HikkakeState {
+  /// 形态最后一个 K 线的序号
+  index: usize,
+  result: i32,
+  /// 形态第二个 K 线的 high 和 low
+  high: f64,
+  low: f64,
+}
+
+impl HikkakeState {
+  /// 识别第 i 个 K 线，确认时信号为 ±200
+  pub(super) fn next(&mut self, c: &Candles, i: usize, modified: bool) -> i32 {
+    let found = if modified { hikkake_mod(c) } else { hikkake(c) };
+    if found {
+      self.result = if c.high(0) < c.high(1) { 100 } else { -100 };
+      self.index = i;
+      self.high = c.high(1);
+      self.low = c.low(1);
+      self.result
+    } else if i <= self.index + 3
+      && ((self.result > 0 && c.close(0) > self.high) || (self.result < 0 && c.close(0) < self.low))
+    {
+      self.index = 0;
+      self.result + if self.result > 0 { 100 } else { -100 }
+    } else {
+      0
+    }
+  }
+}
+
+fn hikkake(c: &Candles) -> bool {
+  // 第二个 K 线为内包线，第三个 K 线的高点和低点同时低于（高于）第二个
+  c.high(1) < c.high(2)
+    && c.low(1) > c.low(2)
+    && ((c.high(0) < c.high(1) && c.low(0) < c.low(1))
+      || (c.high(0) > c.high(1) && c.low(0) > c.low(1)))
+}
+
+fn hikkake_mod(c: &Candles) -> bool {
+  // 连续两个内包线，第二个 K 线的 close 接近其低点（高点）
+  c.high(2) < c.high(3)
+    && c.low(2) > c.low(3)
+    && c.high(1) < c.high(2)
+    && c.low(1) > c.low(2)
+    && ((c.high(0) < c.high(1) && c.low(0) < c.low(1) && c.close(2) <= c.low(2) + c.avg(Near, 2))
+      || (c.high(0) > c.high(1) && c.low(0) > c.low(1) && c.close(2) >= c.high(2) - c.avg(Near, 2)))
+}
+
+pub(super) fn homing_pigeon(c: &Candles) -> i32 {
+  if c.color(1) == -1
+    && c.color(0) == -1
+    && c.body(1) > c.avg(BodyLong, 1)
+    && c.body(0) <= c.avg(BodyShort, 0)
+    && c.open(0) < c.open(1)
+    && c.close(0) > c.close(1)
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn identical_three_crows(c: &Candles) -> i32 {
+  if c.color(2) == -1
+    && c.lower_shadow(2) < c.avg(ShadowVeryShort, 2)
+    && c.color(1) == -1
+    && c.lower_shadow(1) < c.avg(ShadowVeryShort, 1)
+    && c.color(0) == -1
+    && c.lower_shadow(0) < c.avg(ShadowVeryShort, 0)
+    && c.close(2) > c.close(1)
+    && c.close(1) > c.close(0)
+    && c.open(1) <= c.close(2) + c.avg(Equal, 2)
+    && c.open(1) >= c.close(2) - c.avg(Equal, 2)
+    && c.open(0) <= c.close(1) + c.avg(Equal, 1)
+    && c.open(0) >= c.close(1) - c.avg(Equal, 1)
+  {
+    -100
+  } else {
+    0
+  }
+}
+
+pub(super) fn in_neck(c: &Candles) -> i32 {
+  if c.color(1) == -1
+    && c.body(1) > c.avg(BodyLong, 1)
+    && c.color(0) == 1
+    && c.open(0) < c.low(1)
+    && c.close(0) <= c.close(1) + c.avg(Equal, 1)
+    && c.close(0) >= c.close(1)
+  {
+    -100
+  } else {
+    0
+  }
+}
+
+pub(super) fn inverted_hammer(c: &Candles) -> i32 {
+  if c.body(0) < c.avg(BodyShort, 0)
+    && c.upper_shadow(0) > c.avg(ShadowLong, 0)
+    && c.lower_shadow(0) < c.avg(ShadowVeryShort, 0)
+    && c.body_gap_down(0, 1)
+  {
+    100
+  } else {
+    0
+  }
+}
+
+/// 两个颜色相反的光头光脚长 K 线，之间有跳空
+fn kicking_match(c: &Candles) -> bool {
+  c.color(1) == -c.color(0)
+    && c.body(1) > c.avg(BodyLong, 1)
+    && c.upper_shadow(1) < c.avg(ShadowVeryShort, 1)
+    && c.lower_shadow(1) < c.avg(ShadowVeryShort, 1)
+    && c.body(0) > c.avg(BodyLong, 0)
+    && c.upper_shadow(0) < c.avg(ShadowVeryShort, 0)
+    && c.lower_shadow(0) < c.avg(ShadowVeryShort, 0)
+    && ((c.color(1) == -1 && c.gap_up(0, 1)) || (c.color(1) == 1 && c.gap_down(0, 1)))
+}
+
+pub(super) fn kicking(c: &Candles) -> i32 {
+  if kicking_match(c) {
+    c.color(0) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn kicking_by_length(c: &Candles) -> i32 {
+  if kicking_match(c) {
+    // 方向由较长的 K 线决定
+    let k = if c.body(0) > c.body(1) { 0 } else { 1 };
+    c.color(k) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn ladder_bottom(c: &Candles) -> i32 {
+  if c.color(4) == -1
+    && c.color(3) == -1
+    && c.color(2) == -1
+    && c.open(4) > c.open(3)
+    && c.open(3) > c.open(2)
+    && c.close(4) > c.close(3)
+    && c.close(3) > c.close(2)
+    && c.color(1) == -1
+    && c.upper_shadow(1) > c.avg(ShadowVeryShort, 1)
+    && c.color(0) == 1
+    && c.open(0) > c.open(1)
+    && c.close(0) > c.high(1)
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn long_legged_doji(c: &Candles) -> i32 {
+  if c.body(0) <= c.avg(BodyDoji, 0)
+    && (c.lower_shadow(0) > c.avg(ShadowLong, 0) || c.upper_shadow(0) > c.avg(ShadowLong, 0))
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn long_line(c: &Candles) -> i32 {
+  if c.body(0) > c.avg(BodyLong, 0)
+    && c.upper_shadow(0) < c.avg(ShadowShort, 0)
+    && c.lower_shadow(0) < c.avg(ShadowShort, 0)
+  {
+    c.color(0) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn marubozu(c: &Candles) -> i32 {
+  if c.body(0) > c.avg(BodyLong, 0)
+    && c.upper_shadow(0) < c.avg(ShadowVeryShort, 0)
+    && c.lower_shadow(0) < c.avg(ShadowVeryShort, 0)
+  {
+    c.color(0) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn matching_low(c: &Candles) -> i32 {
+  if c.color(1) == -1
+    && c.color(0) == -1
+    && c.close(0) <= c.close(1) + c.avg(Equal, 1)
+    && c.close(0) >= c.close(1) - c.avg(Equal, 1)
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn mat_hold(c: &Candles, penetration: f64) -> i32 {
+  if c.body(4) > c.avg(BodyLong, 4)
+    && c.body(3) <= c.avg(BodyShort, 3)
+    && c.body(2) <= c.avg(BodyShort, 2)
+    && c.body(1) <= c.avg(BodyShort, 1)
+    && c.color(4) == 1
+    && c.color(3) == -1
+    && c.color(0) == 1
+    // 第二个向上跳空，第三、四个的实体部分在第一个的实体内
+    && c.body_gap_up(3, 4)
+    && c.body_bottom(2) < c.close(4)
+    && c.body_bottom(1) < c.close(4)
+    && c.body_bottom(2) > c.close(4) - c.body(4) * penetration
+    && c.body_bottom(1) > c.close(4) - c.body(4) * penetration
+    // 第二到第四个逐渐走低
+    && c.body_top(2) < c.open(3)
+    && c.body_top(1) < c.body_top(2)
+    // 第五个高开并且收盘于前三个的最高价之上
+    && c.open(0) > c.close(1)
+    && c.close(0) > c.high(3).max(c.high(2)).max(c.high(1))
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn morning_doji_star(c: &Candles, penetration: f64) -> i32 {
+  if c.body(2) > c.avg(BodyLong, 2)
+    && c.color(2) == -1
+    && c.body(1) <= c.avg(BodyDoji, 1)
+    && c.body_gap_down(1, 2)
+    && c.body(0) > c.avg(BodyShort, 0)
+    && c.color(0) == 1
+    && c.close(0) > c.close(2) + c.body(2) * penetration
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn morning_star(c: &Candles, penetration: f64) -> i32 {
+  if c.body(2) > c.avg(BodyLong, 2)
+    && c.color(2) == -1
+    && c.body(1) <= c.avg(BodyShort, 1)
+    && c.body_gap_down(1, 2)
+    && c.body(0) > c.avg(BodyShort, 0)
+    && c.color(0) == 1
+    && c.close(0) > c.close(2) + c.body(2) * penetration
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn on_neck(c: &Candles) -> i32 {
+  if c.color(1) == -1
+    && c.body(1) > c.avg(BodyLong, 1)
+    && c.color(0) == 1
+    && c.open(0) < c.low(1)
+    && c.close(0) <= c.low(1) + c.avg(Equal, 1)
+    && c.close(0) >= c.low(1) - c.avg(Equal, 1)
+  {
+    -100
+  } else {
+    0
+  }
+}
+
+pub(super) fn piercing(c: &Candles) -> i32 {
+  if c.color(1) == -1
+    && c.body(1) > c.avg(BodyLong, 1)
+    && c.color(0) == 1
+    && c.body(0) > c.avg(BodyLong, 0)
+    && c.open(0) < c.low(1)
+    && c.close(0) < c.open(1)
+    && c.close(0) > c.close(1) + c.body(1) * 0.5
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn rickshaw_man(c: &Candles) -> i32 {
+  let middle = c.low(0) + c.high_low(0) / 2.;
+  if c.body(0) <= c.avg(BodyDoji, 0)
+    && c.lower_shadow(0) > c.avg(ShadowLong, 0)
+    && c.upper_shadow(0) > c.avg(ShadowLong, 0)
+    && c.body_bottom(0) <= middle + c.avg(Near, 0)
+    && c.body_top(0) >= middle - c.avg(Near, 0)
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn rise_fall_three_methods(c: &Candles) -> i32 {
+  let color = c.color(4) as f64;
+  if c.body(4) > c.avg(BodyLong, 4)
+    && c.body(3) < c.avg(BodyShort, 3)
+    && c.body(2) < c.avg(BodyShort, 2)
+    && c.body(1) < c.avg(BodyShort, 1)
+    && c.body(0) > c.avg(BodyLong, 0)
+    && c.color(4) == -c.color(3)
+    && c.color(3) == c.color(2)
+    && c.color(2) == c.color(1)
+    && c.color(1) == -c.color(0)
+    // 中间三个的实体在第一个的范围内
+    && c.body_bottom(3) < c.high(4)
+    && c.body_top(3) > c.low(4)
+    && c.body_bottom(2) < c.high(4)
+    && c.body_top(2) > c.low(4)
+    && c.body_bottom(1) < c.high(4)
+    && c.body_top(1) > c.low(4)
+    // 中间三个逆着第一个的方向移动
+    && c.close(2) * color < c.close(3) * color
+    && c.close(1) * color < c.close(2) * color
+    // 最后一个开盘于前一个的收盘价之外并且收盘于第一个的收盘价之外
+    && c.open(0) * color > c.close(1) * color
+    && c.close(0) * color > c.close(4) * color
+  {
+    100 * c.color(4)
+  } else {
+    0
+  }
+}
+
+pub(super) fn separating_lines(c: &Candles) -> i32 {
+  if c.color(1) == -c.color(0)
+    && c.open(0) <= c.open(1) + c.avg(Equal, 1)
+    && c.open(0) >= c.open(1) - c.avg(Equal, 1)
+    && c.body(0) > c.avg(BodyLong, 0)
+    && ((c.color(0) == 1 && c.lower_shadow(0) < c.avg(ShadowVeryShort, 0))
+      || (c.color(0) == -1 && c.upper_shadow(0) < c.avg(ShadowVeryShort, 0)))
+  {
+    c.color(0) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn shooting_star(c: &Candles) -> i32 {
+  if c.body(0) < c.avg(BodyShort, 0)
+    && c.upper_shadow(0) > c.avg(ShadowLong, 0)
+    && c.lower_shadow(0) < c.avg(ShadowVeryShort, 0)
+    && c.body_gap_up(0, 1)
+  {
+    -100
+  } else {
+    0
+  }
+}
+
+pub(super) fn short_line(c: &Candles) -> i32 {
+  if c.body(0) < c.avg(BodyShort, 0)
+    && c.upper_shadow(0) < c.avg(ShadowShort, 0)
+    && c.lower_shadow(0) < c.avg(ShadowShort, 0)
+  {
+    c.color(0) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn spinning_top(c: &Candles) -> i32 {
+  if c.body(0) < c.avg(BodyShort, 0)
+    && c.upper_shadow(0) > c.body(0)
+    && c.lower_shadow(0) > c.body(0)
+  {
+    c.color(0) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn stalled_pattern(c: &Candles) -> i32 {
+  if c.color(2) == 1
+    && c.color(1) == 1
+    && c.color(0) == 1
+    && c.close(0) > c.close(1)
+    && c.close(1) > c.close(2)
+    && c.body(2) > c.avg(BodyLong, 2)
+    && c.body(1) > c.avg(BodyLong, 1)
+    && c.upper_shadow(1) < c.avg(ShadowVeryShort, 1)
+    && c.open(1) > c.open(2)
+    && c.open(1) <= c.close(2) + c.avg(Near, 2)
+    // 第三个为小实体，位于第二个的实体顶部附近
+    && c.body(0) < c.avg(BodyShort, 0)
+    && c.open(0) >= c.close(1) - c.body(0) - c.avg(Near, 1)
+  {
+    -100
+  } else {
+    0
+  }
+}
+
+pub(super) fn stick_sandwich(c: &Candles) -> i32 {
+  if c.color(2) == -1
+    && c.color(1) == 1
+    && c.color(0) == -1
+    && c.low(1) > c.close(2)
+    && c.close(0) <= c.close(2) + c.avg(Equal, 2)
+    && c.close(0) >= c.close(2) - c.avg(Equal, 2)
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn takuri(c: &Candles) -> i32 {
+  if c.body(0) <= c.avg(BodyDoji, 0)
+    && c.upper_shadow(0) < c.avg(ShadowVeryShort, 0)
+    && c.lower_shadow(0) > c.avg(ShadowVeryLong, 0)
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn tasuki_gap(c: &Candles) -> i32 {
+  let near = (c.body(1) - c.body(0)).abs() < c.avg(Near, 1);
+  if (c.body_gap_up(1, 2)
+    && c.color(1) == 1
+    && c.color(0) == -1
+    && c.open(0) < c.close(1)
+    && c.open(0) > c.open(1)
+    && c.close(0) < c.open(1)
+    && c.close(0) > c.body_top(2)
+    && near)
+    || (c.body_gap_down(1, 2)
+      && c.color(1) == -1
+      && c.color(0) == 1
+      && c.open(0) < c.open(1)
+      && c.open(0) > c.close(1)
+      && c.close(0) > c.open(1)
+      && c.close(0) < c.body_bottom(2)
+      && near)
+  {
+    c.color(1) * 100
+  } else {
+    0
+  }
+}
+
+pub(super) fn thrusting(c: &Candles) -> i32 {
+  if c.color(1) == -1
+    && c.body(1) > c.avg(BodyLong, 1)
+    && c.color(0) == 1
+    && c.open(0) < c.low(1)
+    && c.close(0) > c.close(1) + c.avg(Equal, 1)
+    && c.close(0) <= c.close(1) + c.body(1) * 0.5
+  {
+    -100
+  } else {
+    0
+  }
+}
+
+pub(super) fn tristar(c: &Candles) -> i32 {
+  // 三个十字星都和第一个的比较基准比较
+  let doji = c.avg(BodyDoji, 2);
+  if c.body(2) <= doji && c.body(1) <= doji && c.body(0) <= doji {
+    if c.body_gap_up(1, 2) && c.body_top(0) < c.body_top(1) {
+      return -100;
+    }
+    if c.body_gap_down(1, 2) && c.body_bottom(0) > c.body_bottom(1) {
+      return 100;
+    }
+  }
+  0
+}
+
+pub(super) fn unique_three_river(c: &Candles) -> i32 {
+  if c.body(2) > c.avg(BodyLong, 2)
+    && c.color(2) == -1
+    && c.color(1) == -1
+    && c.close(1) > c.close(2)
+    && c.open(1) <= c.open(2)
+    && c.low(1) < c.low(2)
+    && c.body(0) < c.avg(BodyShort, 0)
+    && c.color(0) == 1
+    && c.open(0) > c.low(1)
+  {
+    100
+  } else {
+    0
+  }
+}
+
+pub(super) fn upside_gap_two_crows(c: &Candles) -> i32 {
+  if c.color(2) == 1
+    && c.body(2) > c.avg(BodyLong, 2)
+    && c.color(1) == -1
+    && c.body(1) <= c.avg(BodyShort, 1)
+    && c.body_gap_up(1, 2)
+    && c.color(0) == -1
+    && c.open(0) > c.open(1)
+    && c.close(0) < c.close(1)
+    && c.close(0) > c.close(2)
+  {
+    -100
+  } else {
+    0
+  }
+}
+
+pub(super) fn x_side_gap_three_methods(c: &Candles) -> i32 {
+  if c.color(2) == c.color(1)
+    && c.color(0) == -c.color(1)
+    && c.open(0) < c.body_top(1)
+    && c.open(0) > c.body_bottom(1)
+    && c.close(0) < c.body_top(2)
+    && c.close(0) > c.body_bottom(2)
+    && ((c.color(2) == 1 && c.body_gap_up(1, 2)) || (c.color(2) == -1 && c.body_gap_down(1, 2)))
+  {
+    c.color(2) * 100
+  } else {
+    0
+  }
+}
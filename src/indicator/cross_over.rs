@@ -1,15 +1,17 @@
-extern crate ta_lib_wrapper;
-
 use crate::{
-  impl_indicator_trait, impl_indicator_without_period, indicator::util::get_vec_at, DataLine,
-  DataLineFeed,
+  impl_indicator_trait, impl_indicator_without_period, DataLine, DataLineFeed, Indicator,
+  IndicatorLine,
 };
 
+use super::base::{check_inputs, check_update};
+
+/// a 上穿 b 时为 1，下穿时为 -1，否则为 0。a 和 b 相等时不改变之前的方向。
 pub struct CrossOverIndicator {
-  start_pos: usize,
-  data: Vec<f64>,
+  line: IndicatorLine,
+  /// 增量计算时最近一个不为 0 的方向，第一个有效的输入之前为 None
+  state: Option<i8>,
 }
-impl_indicator_without_period!(CrossOverIndicator);
+impl_indicator_without_period!(CrossOverIndicator, state);
 impl_indicator_trait!(CrossOverIndicator);
 
 #[inline(always)]
@@ -26,53 +28,70 @@ fn calc_cross(a: f64, b: f64, lnzd: i8) -> (f64, i8) {
     new_lnzd,
   )
 }
-impl CrossOverIndicator {
-  pub fn feed<DA: DataLineFeed, DB: DataLineFeed>(&mut self, data_line_a: &DA, data_line_b: &DB) {
-    let (buf_a, start_pos_a) = data_line_a.inner();
-    let (buf_b, start_pos_b) = data_line_b.inner();
-    // println!("{} {}", start_pos_a, start_pos_b);
-    let buf_a_len = buf_a.len();
-    assert_eq!(buf_a_len, buf_b.len());
-    let data_len = self.data.len();
-    if data_len == 0 {
-      self.data = Vec::with_capacity(buf_a_len)
-    } else {
-      assert_eq!(buf_a_len, data_len);
+
+impl Indicator for CrossOverIndicator {
+  fn name(&self) -> &'static str {
+    "CROSSOVER"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["a", "b"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    Vec::new()
+  }
+  fn lookback(&self) -> usize {
+    1
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let (buf_a, buf_b) = (inputs[0].inner().0, inputs[1].inner().0);
+    let data = self.line.reset(len, start_pos);
+    if start_pos > len {
+      return;
     }
-    let start_pos = start_pos_a.max(start_pos_b) + 1;
-    self.start_pos = start_pos;
-    unsafe {
-      let mut pa = buf_a.as_ptr().add(start_pos - 1);
-      let mut pb = buf_b.as_ptr().add(start_pos - 1);
-      let mut lnzd = {
-        let a = *pa;
-        let b = *pb;
-        if a == b {
+    // 最近一个不为 0 的方向
+    let mut lnzd = {
+      let a = buf_a[start_pos - 1];
+      let b = buf_b[start_pos - 1];
+      if a == b {
+        0
+      } else if a > b {
+        1
+      } else {
+        -1
+      }
+    };
+    for i in start_pos..len {
+      let a = buf_a[i];
+      let b = buf_b[i];
+      if a != b {
+        (data[i], lnzd) = calc_cross(a, b, lnzd);
+      }
+    }
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let value = check_update(self, inputs).and_then(|[a, b]| match self.state {
+      None => {
+        self.state = Some(if a == b {
           0
         } else if a > b {
           1
         } else {
           -1
-        }
-      };
-      pa = pa.add(1);
-      pb = pb.add(1);
-      let mut pd = self.data.as_mut_ptr().add(start_pos);
-      for _ in start_pos..buf_a_len {
-        let a = *pa;
-        let b = *pb;
-        if a == b {
-          *pd = 0.;
-        } else {
-          (*pd, lnzd) = calc_cross(a, b, lnzd);
-        }
-        // println!("{}, {}, {}, {:?}", *pa, *pb, *pd, lnzd);
-        pa = pa.add(1);
-        pb = pb.add(1);
-        pd = pd.add(1);
+        });
+        None
       }
-      self.data.set_len(buf_a_len);
-    }
+      Some(_) if a == b => Some(0.),
+      Some(lnzd) => {
+        let (v, new_lnzd) = calc_cross(a, b, lnzd);
+        self.state = Some(new_lnzd);
+        Some(v)
+      }
+    });
+    self.line.push(value);
   }
 }
 
@@ -87,13 +106,14 @@ fn test_cross_over_indicator() {
   let d1 = D(vec![0.1, 0.3, 0.2, 0.3, 0.5, 0.6], 0);
   let d2 = D(vec![0., 0.1, 0.4, 0.5, 0.5, 0.3], 1);
   let mut ind = CrossOverIndicator::new();
-  ind.feed(&d1, &d2);
-  assert_eq!(ind.data.len(), d1.0.len());
-  assert_eq!(ind.start_pos, 1);
-  assert_eq!(ind.data, vec![0., 0., 1., 0., 0., -1.]);
-
-  // println!("{:?}", d1.0);
-  // println!("{:?}", d2.0);
-  // println!("{:?}", ind.data);
-  // panic!("")
+  ind.compute(&[&d1, &d2]);
+  assert_eq!(ind.line.len(), d1.0.len());
+  assert_eq!(ind.line.start_pos(), 2);
+  // 第 2 个数据 a 下穿 b，最后一个数据 a 上穿 b
+  assert_eq!(ind.line.as_slice(), &[0., 0., -1., 0., 0., 1.]);
+  crate::indicator::base::assert_outputs(
+    CrossOverIndicator::new,
+    &[&d1, &d2],
+    &[(2, &[-1., 0., 0., 1.])],
+  );
 }
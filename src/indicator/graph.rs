@@ -0,0 +1,474 @@
+use std::{
+  io::{Error, Result},
+  mem,
+};
+
+use crate::{CsvDataSource, DataLineFeed, Timeframe};
+
+use super::{
+  bar::bar_inputs,
+  base::{Indicator, IndicatorLine},
+};
+
+/// 指标图中某个节点的一个输出，由 IndicatorGraph::add 等方法返回。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IndicatorRef {
+  node: usize,
+  output: usize,
+}
+
+impl IndicatorRef {
+  /// 同一个节点的第 index 个输出，比如 MACD 的 signal
+  #[inline]
+  pub fn output(self, index: usize) -> Self {
+    Self {
+      node: self.node,
+      output: index,
+    }
+  }
+}
+
+/// 指标的输入数据列。
+#[derive(Debug, Clone, PartialEq)]
+pub enum Series {
+  Open,
+  High,
+  Low,
+  Close,
+  Volume,
+  OpenInterest,
+  AdjustClose,
+  Spread,
+  /// 通过 CsvDataSourceBuilder::extra_field 加载的额外数值列
+  Extra(String),
+  /// 已注册的更大周期的数据列，比如 Series::Timeframe(Timeframe::Hours(4), Box::new(Series::Close))，
+  /// 基于大周期数据的指标需要通过 IndicatorGraph::project 投影到基础周期上
+  Timeframe(Timeframe, Box<Series>),
+  /// 其他指标的输出
+  Indicator(IndicatorRef),
+}
+
+impl Series {
+  /// 指标输入名称对应的 bar 数据列：open、high、low、close 和 volume，real 对应 close
+  pub fn from_input(name: &str) -> Option<Self> {
+    match name {
+      "open" => Some(Series::Open),
+      "high" => Some(Series::High),
+      "low" => Some(Series::Low),
+      "close" | "real" => Some(Series::Close),
+      "volume" => Some(Series::Volume),
+      _ => None,
+    }
+  }
+}
+
+impl From<IndicatorRef> for Series {
+  fn from(r: IndicatorRef) -> Self {
+    Series::Indicator(r)
+  }
+}
+
+/// 可以作为指标输入的类型：单个数据列、数组、Vec 或者元组。
+pub trait IntoSeriesList {
+  fn into_series_list(self) -> Vec<Series>;
+}
+
+impl<T: Into<Series>> IntoSeriesList for T {
+  fn into_series_list(self) -> Vec<Series> {
+    vec![self.into()]
+  }
+}
+impl<T: Into<Series>, const N: usize> IntoSeriesList for [T; N] {
+  fn into_series_list(self) -> Vec<Series> {
+    self.into_iter().map(Into::into).collect()
+  }
+}
+impl IntoSeriesList for Vec<Series> {
+  fn into_series_list(self) -> Vec<Series> {
+    self
+  }
+}
+
+macro_rules! impl_into_series_list_for_tuple {
+  ($($t: ident),+) => {
+    impl<$($t: Into<Series>),+> IntoSeriesList for ($($t,)+) {
+      #[allow(non_snake_case)]
+      fn into_series_list(self) -> Vec<Series> {
+        let ($($t,)+) = self;
+        vec![$($t.into()),+]
+      }
+    }
+  };
+}
+impl_into_series_list_for_tuple!(A, B);
+impl_into_series_list_for_tuple!(A, B, C);
+impl_into_series_list_for_tuple!(A, B, C, D);
+
+enum NodeKind {
+  Indicator(Box<dyn Indicator>),
+  /// 将大周期的指标投影到基础周期上
+  Project(Timeframe, IndicatorLine),
+  /// 正在计算，计算期间暂时取出节点
+  Computing,
+}
+
+struct Node {
+  /// 名称和参数，比如 EMA(period=20)，用于识别相同的指标以及错误信息
+  key: String,
+  inputs: Vec<Series>,
+  kind: NodeKind,
+}
+
+/// 指标的依赖图。每个指标和它的输入只需要声明一次，计算时按依赖关系自动排序，
+/// 相同名称、参数和输入的指标只会计算一次。
+///
+/// ```ignore
+/// let mut graph = IndicatorGraph::new();
+/// let slow = graph.add(EMAIndicator::new(144), Series::Close);
+/// let slope = graph.add(LinearregSlopeIndicator::new(50), slow);
+/// engine.set_indicators(graph)?;
+/// // 在 Strategy::next 中
+/// let v = data.indicator(slope).at(index);
+/// ```
+///
+/// 通过 Engine::set_indicators 注册后，指标图在 Strategy::feed 之前计算。
+#[derive(Default)]
+pub struct IndicatorGraph {
+  nodes: Vec<Node>,
+}
+
+impl IndicatorGraph {
+  pub fn new() -> Self {
+    Self { nodes: Vec::new() }
+  }
+  /// 节点的数量
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.nodes.len()
+  }
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.nodes.is_empty()
+  }
+  /// 添加指标并指定输入，返回指标的第一个输出。已经存在名称、参数和输入都相同的指标时直接返回该指标。
+  pub fn add<I, S>(&mut self, indicator: I, inputs: S) -> IndicatorRef
+  where
+    I: Indicator + 'static,
+    S: IntoSeriesList,
+  {
+    let key = indicator_key(&indicator);
+    let inputs = inputs.into_series_list();
+    self.check_series(&inputs);
+    check_input_count(&indicator, inputs.len());
+    if let Some(node) = self
+      .nodes
+      .iter()
+      .position(|node| node.key == key && node.inputs == inputs)
+    {
+      return IndicatorRef { node, output: 0 };
+    }
+    self.push(key, inputs, NodeKind::Indicator(Box::new(indicator)))
+  }
+  /// 添加以 bar 数据列作为输入的指标，输入按名称匹配，参见 Series::from_input
+  pub fn add_bars<I: Indicator + 'static>(&mut self, indicator: I) -> IndicatorRef {
+    let inputs = bar_inputs(&indicator);
+    self.add(indicator, inputs)
+  }
+  /// 声明指标但暂不指定输入，之后通过 connect 指定，用于引用之后才添加的指标。
+  pub fn declare<I: Indicator + 'static>(&mut self, indicator: I) -> IndicatorRef {
+    let key = indicator_key(&indicator);
+    self.push(key, Vec::new(), NodeKind::Indicator(Box::new(indicator)))
+  }
+  /// 指定（或者替换）指标的输入
+  pub fn connect<S: IntoSeriesList>(&mut self, target: IndicatorRef, inputs: S) {
+    let inputs = inputs.into_series_list();
+    self.check_series(&inputs);
+    self.check_ref(target);
+    let node = &mut self.nodes[target.node];
+    match &node.kind {
+      NodeKind::Indicator(indicator) => check_input_count(indicator.as_ref(), inputs.len()),
+      _ => panic!("{} expects 1 input", node.key),
+    }
+    node.inputs = inputs;
+  }
+  /// 将基于大周期数据计算的指标投影到基础周期的下标上，参见 CsvTimeframe::project
+  pub fn project(&mut self, timeframe: Timeframe, source: IndicatorRef) -> IndicatorRef {
+    let key = format!("PROJECT({:?})", timeframe);
+    let inputs = vec![Series::Indicator(source)];
+    self.check_series(&inputs);
+    if let Some(node) = self
+      .nodes
+      .iter()
+      .position(|node| node.key == key && node.inputs == inputs)
+    {
+      return IndicatorRef { node, output: 0 };
+    }
+    let kind = NodeKind::Project(timeframe, IndicatorLine::new());
+    self.push(key, inputs, kind)
+  }
+  fn push(&mut self, key: String, inputs: Vec<Series>, kind: NodeKind) -> IndicatorRef {
+    self.nodes.push(Node { key, inputs, kind });
+    IndicatorRef {
+      node: self.nodes.len() - 1,
+      output: 0,
+    }
+  }
+  fn check_ref(&self, r: IndicatorRef) {
+    let node = self
+      .nodes
+      .get(r.node)
+      .unwrap_or_else(|| panic!("indicator {} not found in the graph", r.node));
+    let outputs = match &node.kind {
+      NodeKind::Indicator(indicator) => indicator.outputs().len(),
+      _ => 1,
+    };
+    if r.output >= outputs {
+      panic!("{} has no output {}", node.key, r.output);
+    }
+  }
+  fn check_series(&self, inputs: &[Series]) {
+    for series in inputs {
+      match series {
+        Series::Indicator(r) => self.check_ref(*r),
+        Series::Timeframe(_, column) => {
+          if matches!(**column, Series::Timeframe(..) | Series::Indicator(_)) {
+            panic!("timeframe series must be a data column");
+          }
+        }
+        _ => {}
+      }
+    }
+  }
+  /// 按依赖关系排序的节点下标，存在循环依赖时返回错误
+  fn order(&self) -> Result<Vec<usize>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+      New,
+      Visiting,
+      Done,
+    }
+    fn visit(
+      graph: &IndicatorGraph,
+      node: usize,
+      states: &mut [State],
+      path: &mut Vec<usize>,
+      order: &mut Vec<usize>,
+    ) -> Result<()> {
+      match states[node] {
+        State::Done => return Ok(()),
+        State::Visiting => {
+          let begin = path.iter().position(|n| *n == node).unwrap();
+          let cycle: Vec<&str> = path[begin..]
+            .iter()
+            .chain([&node])
+            .map(|n| graph.nodes[*n].key.as_str())
+            .collect();
+          return Err(Error::other(format!(
+            "indicator graph contains a cycle: {}",
+            cycle.join(" -> ")
+          )));
+        }
+        State::New => {}
+      }
+      states[node] = State::Visiting;
+      path.push(node);
+      for series in &graph.nodes[node].inputs {
+        if let Series::Indicator(r) = series {
+          visit(graph, r.node, states, path, order)?;
+        }
+      }
+      path.pop();
+      states[node] = State::Done;
+      order.push(node);
+      Ok(())
+    }
+    let mut states = vec![State::New; self.nodes.len()];
+    let mut order = Vec::with_capacity(self.nodes.len());
+    for node in 0..self.nodes.len() {
+      visit(self, node, &mut states, &mut Vec::new(), &mut order)?;
+    }
+    Ok(order)
+  }
+  /// 检查指标图是否存在循环依赖以及未指定输入的指标
+  pub fn validate(&self) -> Result<()> {
+    self.order()?;
+    for node in &self.nodes {
+      if let NodeKind::Indicator(indicator) = &node.kind {
+        if node.inputs.len() != indicator.inputs().len() {
+          return Err(Error::other(format!(
+            "inputs of {} are not connected",
+            node.key
+          )));
+        }
+      }
+    }
+    Ok(())
+  }
+  /// 按依赖关系基于 data 计算全部的指标，每个指标只计算一次
+  pub fn compute(&mut self, data: &CsvDataSource) -> Result<()> {
+    self.validate()?;
+    for node in self.order()? {
+      let mut kind = mem::replace(&mut self.nodes[node].kind, NodeKind::Computing);
+      let result = self.nodes[node]
+        .inputs
+        .iter()
+        .map(|series| self.resolve(series, data))
+        .collect::<Result<Vec<_>>>()
+        .and_then(|inputs| {
+          match &mut kind {
+            NodeKind::Indicator(indicator) => indicator.compute(&inputs),
+            NodeKind::Project(timeframe, line) => {
+              let projected = data
+                .timeframe(*timeframe)
+                .ok_or_else(|| timeframe_not_registered(*timeframe))?
+                .project(inputs[0]);
+              let (src, start_pos) = projected.inner();
+              line.reset(src.len(), start_pos).copy_from_slice(src);
+            }
+            NodeKind::Computing => unreachable!(),
+          }
+          Ok(())
+        });
+      self.nodes[node].kind = kind;
+      result?;
+    }
+    Ok(())
+  }
+  fn resolve<'a>(
+    &'a self,
+    series: &Series,
+    data: &'a CsvDataSource,
+  ) -> Result<&'a dyn DataLineFeed> {
+    let line = match series {
+      Series::Open => &data.open,
+      Series::High => &data.high,
+      Series::Low => &data.low,
+      Series::Close => &data.close,
+      Series::Volume => &data.volume,
+      Series::OpenInterest => &data.openintrest,
+      Series::AdjustClose => &data.adjustclose,
+      Series::Spread => &data.spread,
+      Series::Extra(name) => data
+        .extra(name)
+        .ok_or_else(|| Error::other(format!("extra field {} not found", name)))?,
+      Series::Timeframe(timeframe, column) => {
+        let timeframe = data
+          .timeframe(*timeframe)
+          .ok_or_else(|| timeframe_not_registered(*timeframe))?;
+        return self.resolve(column, &timeframe.data);
+      }
+      Series::Indicator(r) => return Ok(self.line(*r)),
+    };
+    if line.is_empty() && !data.is_empty() {
+      return Err(Error::other(format!("{:?} is not loaded", series)));
+    }
+    Ok(line)
+  }
+  /// 指标的输出，compute 之前为空
+  pub fn line(&self, r: IndicatorRef) -> &IndicatorLine {
+    self.check_ref(r);
+    match &self.nodes[r.node].kind {
+      NodeKind::Indicator(indicator) => indicator.output(r.output),
+      NodeKind::Project(_, line) => line,
+      NodeKind::Computing => panic!("{} is being computed", self.nodes[r.node].key),
+    }
+  }
+}
+
+fn indicator_key<I: Indicator + ?Sized>(indicator: &I) -> String {
+  let params: Vec<String> = indicator
+    .params()
+    .iter()
+    .map(|(name, value)| format!("{}={}", name, value))
+    .collect();
+  format!("{}({})", indicator.name(), params.join(", "))
+}
+
+fn check_input_count<I: Indicator + ?Sized>(indicator: &I, found: usize) {
+  let expected = indicator.inputs();
+  if found != expected.len() {
+    panic!(
+      "{} expects {} inputs ({}) but found {}",
+      indicator.name(),
+      expected.len(),
+      expected.join(", "),
+      found
+    );
+  }
+}
+
+fn timeframe_not_registered(timeframe: Timeframe) -> Error {
+  Error::other(format!("timeframe {:?} not registered", timeframe))
+}
+
+#[test]
+fn test_indicator_graph() {
+  use crate::{
+    CrossOverIndicator, CsvResampler, CsvTimeType, DataLine, MaxIndicator, MinIndicator,
+  };
+
+  let content = "date,close,volume
+2022-01-03 00:00,1,1
+2022-01-03 01:00,3,1
+2022-01-03 02:00,2,1
+2022-01-03 03:00,5,1
+2022-01-03 04:00,4,1
+2022-01-03 05:00,6,1";
+  let mut data = CsvDataSource::builder()
+    .time_field("date")
+    .time_type(CsvTimeType::Datetime("%Y-%m-%d %H:%M"))
+    .load_from_string(content)
+    .unwrap();
+  data.add_timeframe(CsvResampler::new(Timeframe::Hours(2)));
+
+  let mut graph = IndicatorGraph::new();
+  let max = graph.add(MaxIndicator::new(), (Series::Close, Series::Volume));
+  // 相同的指标和输入只会添加一次
+  assert_eq!(
+    graph.add(MaxIndicator::new(), [Series::Close, Series::Volume]),
+    max
+  );
+  // 引用之后才添加的指标
+  let cross = graph.declare(CrossOverIndicator::new());
+  let min = graph.add(MinIndicator::new(), (Series::Close, max));
+  graph.connect(cross, (Series::Close, min));
+  let h2 = Series::Timeframe(Timeframe::Hours(2), Box::new(Series::Close));
+  let max2h = graph.add(MaxIndicator::new(), (h2.clone(), h2));
+  let projected = graph.project(Timeframe::Hours(2), max2h);
+  assert_eq!(graph.len(), 5);
+
+  graph.compute(&data).unwrap();
+  assert_eq!(graph.line(max).as_slice(), &[1., 3., 2., 5., 4., 6.]);
+  assert_eq!(graph.line(min).as_slice(), &[1., 3., 2., 5., 4., 6.]);
+  assert_eq!(graph.line(cross).at(0), None);
+  assert_eq!(graph.line(cross).at(1), Some(0.));
+  assert_eq!(graph.line(max2h).as_slice(), &[3., 5., 6.]);
+  // 第 1 个 bar 走完时第一个 2 小时 bar 才走完
+  assert_eq!(graph.line(projected).at(0), None);
+  assert_eq!(graph.line(projected).at(1), Some(3.));
+  assert_eq!(graph.line(projected).at(4), Some(5.));
+
+  // 循环依赖
+  graph.connect(max, (Series::Close, cross));
+  let e = graph.compute(&data).unwrap_err();
+  assert_eq!(
+    e.to_string(),
+    "indicator graph contains a cycle: MAX() -> CROSSOVER() -> MIN() -> MAX()"
+  );
+
+  let mut graph = IndicatorGraph::new();
+  graph.declare(MaxIndicator::new());
+  assert_eq!(
+    graph.validate().unwrap_err().to_string(),
+    "inputs of MAX() are not connected"
+  );
+  let mut graph = IndicatorGraph::new();
+  graph.add(
+    MaxIndicator::new(),
+    (Series::Close, Series::Extra("x".into())),
+  );
+  assert_eq!(
+    graph.compute(&data).unwrap_err().to_string(),
+    "extra field x not found"
+  );
+}
@@ -1,68 +1,110 @@
-extern crate ta_lib_wrapper;
+use std::collections::VecDeque;
 
-use ta_lib_wrapper::{TA_Integer, TA_RetCode, TA_LINEARREG_SLOPE};
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::TA_LINEARREG_SLOPE;
 
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
 use crate::{
-  impl_indicator_trait, impl_indicator_with_period, indicator::util::get_vec_at, DataLine,
-  DataLineFeed,
+  impl_indicator_trait, impl_indicator_with_period,
+  indicator::base::{check_inputs, check_update},
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
 };
 
 pub struct LinearregSlopeIndicator {
   period: usize,
-  start_pos: usize,
-  data: Vec<f64>,
+  line: IndicatorLine,
+  state: LinearregSlopeState,
+}
+
+/// 增量计算的状态：最近 period 个输入，以及以最早的输入为 x = 0 时的 Σy 和 Σxy
+#[derive(Default)]
+struct LinearregSlopeState {
+  window: VecDeque<f64>,
+  sum_y: f64,
+  sum_xy: f64,
 }
 impl_indicator_with_period!(LinearregSlopeIndicator);
 impl_indicator_trait!(LinearregSlopeIndicator);
 
-impl LinearregSlopeIndicator {
-  pub fn feed<D: DataLineFeed>(&mut self, data_line: &D) {
-    let (src_data, src_start_pos) = data_line.inner();
-    self.start_pos = src_start_pos + self.period - 1;
-    // println!("SLOP: {} {} {}", src_start_pos, self.period, self.start_pos);
-
-    let src_len = src_data.len();
-    let data_len = self.data.len();
-    if data_len == 0 {
-      self.data = Vec::with_capacity(src_len)
-    } else {
-      assert_eq!(src_len, data_len);
-    }
-    let mut out_begin: TA_Integer = 0;
-    let mut out_size: TA_Integer = 0;
-
-    unsafe {
-      let ret_code = TA_LINEARREG_SLOPE(
-        0,
-        (src_len - src_start_pos - 1) as i32,
-        src_data.as_ptr().add(src_start_pos),
-        self.period as i32,
-        &mut out_begin,
-        &mut out_size,
-        self.data.as_mut_ptr().add(self.start_pos),
-      );
-      match ret_code {
-        // Indicator was computed correctly, since the vector was filled by TA-lib C library,
-        // Rust doesn't know what is the new length of the vector, so we set it manually
-        // to the number of values returned by the TA_MA call
-        TA_RetCode::TA_SUCCESS => {
-          assert_eq!(src_start_pos + out_begin as usize, self.start_pos);
-          assert_eq!(src_start_pos + (out_begin + out_size) as usize, src_len);
-          // 注意 prepare_opacity 函数只分配空间并不进行数据初始化（没有初始化的空间也可以被 c 函数使用），因此需要强行 set_len 指定长度。
-          self.data.set_len(src_len);
-        }
-        // An error occured
-        _ => panic!("Could not compute indicator, err: {:?}", ret_code),
+impl Indicator for LinearregSlopeIndicator {
+  fn name(&self) -> &'static str {
+    "LINEARREG_SLOPE"
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    self.period - 1
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_LINEARREG_SLOPE(0, end, ins[0], period, out_begin, out_size, outs[0])
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let n = self.period as f64;
+    let value = check_update(self, inputs).and_then(|[v]| {
+      let state = &mut self.state;
+      if state.window.len() == self.period {
+        // 移除最早的输入后其余输入的 x 都减 1
+        let first = state.window.pop_front().unwrap();
+        state.sum_y -= first;
+        state.sum_xy -= state.sum_y;
+      }
+      state.sum_xy += state.window.len() as f64 * v;
+      state.sum_y += v;
+      state.window.push_back(v);
+      if state.window.len() < self.period {
+        return None;
       }
-      // println!(
-      //   "sma: {} {} {} {}",
-      //   self.period,
-      //   out_begin,
-      //   out_size,
-      //   self.data.len()
-      // );
-      // println!("{:?}", self.data);
+      let sum_x = n * (n - 1.) / 2.;
+      let sum_x2 = n * (n - 1.) * (2. * n - 1.) / 6.;
+      Some((n * state.sum_xy - sum_x * state.sum_y) / (n * sum_x2 - sum_x * sum_x))
+    });
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_linearreg_slope_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
     }
   }
+  let mut ind = LinearregSlopeIndicator::new(3);
+  for v in [1., 3., 5., 4., 2.] {
+    ind.update(&[Some(v)]);
+  }
+  assert_eq!(ind.line.start_pos(), 2);
+  assert_eq!(ind.line.as_slice(), &[0., 0., 2., 0.5, -1.5]);
+  // 窗口 1, 3, 2, 5：x 的平方和为 5，协方差之和为 5.5
+  crate::indicator::base::assert_outputs(
+    || LinearregSlopeIndicator::new(4),
+    &[&D(vec![0., 1., 3., 2., 5., 4., 8., 7.], 1)],
+    &[(4, &[1.1, 0.6, 1.7, 1.])],
+  );
 }
-// impl Indicator for SMAIndicator {}
@@ -0,0 +1,101 @@
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::TA_DEMA;
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  impl_indicator_trait, impl_indicator_with_period,
+  indicator::{
+    base::{check_inputs, check_update},
+    state::EmaState,
+  },
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// 双重指数移动平均：2 * EMA - EMA(EMA)
+pub struct DEMAIndicator {
+  period: usize,
+  line: IndicatorLine,
+  state: [EmaState; 2],
+}
+
+impl_indicator_with_period!(DEMAIndicator);
+impl_indicator_trait!(DEMAIndicator);
+
+impl Indicator for DEMAIndicator {
+  fn name(&self) -> &'static str {
+    "DEMA"
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    2 * (self.period - 1)
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_DEMA(0, end, ins[0], period, out_begin, out_size, outs[0])
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let period = self.period;
+    let value = check_update(self, inputs).and_then(|[v]| {
+      let [ema1, ema2] = &mut self.state;
+      let e1 = ema1.next(period, v)?;
+      let e2 = ema2.next(period, e1)?;
+      Some(2. * e1 - e2)
+    });
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_dema_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let mut ind = DEMAIndicator::new(2);
+  for v in [1., 3., 5., 7.] {
+    ind.update(&[Some(v)]);
+  }
+  // EMA(2): 2, 4, 6；EMA(EMA): 3, 5
+  assert_eq!(ind.line.start_pos(), 2);
+  assert_eq!(ind.line.as_slice(), &[0., 0., 5., 7.]);
+  crate::indicator::base::assert_outputs(
+    || DEMAIndicator::new(3),
+    &[&D(vec![0., 1., 3., 2., 5., 4., 6., 8., 7.], 1)],
+    &[(
+      5,
+      &[
+        4.416666666666666,
+        5.770833333333333,
+        7.666666666666666,
+        7.473958333333333,
+      ],
+    )],
+  );
+}
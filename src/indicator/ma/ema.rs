@@ -1,69 +1,96 @@
-extern crate ta_lib_wrapper;
-
-use ta_lib_wrapper::{TA_Integer, TA_MAType, TA_RetCode, TA_MA};
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::{TA_MAType, TA_MA};
 
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
 use crate::{
-  impl_indicator_trait, impl_indicator_with_period, indicator::util::get_vec_at, DataLine,
-  DataLineFeed,
+  impl_indicator_trait, impl_indicator_with_period,
+  indicator::{
+    base::{check_inputs, check_update},
+    state::EmaState,
+  },
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
 };
 
 pub struct EMAIndicator {
   period: usize,
-  start_pos: usize,
-  data: Vec<f64>,
+  line: IndicatorLine,
+  state: EmaState,
 }
+
 impl_indicator_with_period!(EMAIndicator);
 impl_indicator_trait!(EMAIndicator);
 
-impl EMAIndicator {
-  pub fn feed<D: DataLineFeed>(&mut self, data_line: &D) {
-    let (src_data, src_start_pos) = data_line.inner();
-    self.start_pos = src_start_pos + self.period - 1;
-
-    let src_len = src_data.len();
-    let data_len = self.data.len();
-    if data_len == 0 {
-      self.data = Vec::with_capacity(src_len)
-    } else {
-      assert_eq!(src_len, data_len);
-    }
-
-    let mut out_begin: TA_Integer = 0;
-    let mut out_size: TA_Integer = 0;
+impl Indicator for EMAIndicator {
+  fn name(&self) -> &'static str {
+    "EMA"
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    self.period - 1
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_MA(
+          0,
+          end,
+          ins[0],
+          period,
+          TA_MAType::TA_MAType_EMA,
+          out_begin,
+          out_size,
+          outs[0],
+        )
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let value = check_update(self, inputs).and_then(|[v]| self.state.next(self.period, v));
+    self.line.push(value);
+  }
+}
 
-    unsafe {
-      let ret_code = TA_MA(
-        0,
-        (src_len - src_start_pos - 1) as i32,
-        src_data.as_ptr().add(src_start_pos),
-        self.period as i32,
-        TA_MAType::TA_MAType_EMA,
-        &mut out_begin,
-        &mut out_size,
-        self.data.as_mut_ptr().add(self.start_pos),
-      );
-      match ret_code {
-        // Indicator was computed correctly, since the vector was filled by TA-lib C library,
-        // Rust doesn't know what is the new length of the vector, so we set it manually
-        // to the number of values returned by the TA_MA call
-        TA_RetCode::TA_SUCCESS => {
-          assert_eq!(src_start_pos + out_begin as usize, self.start_pos);
-          assert_eq!(src_start_pos + (out_begin + out_size) as usize, src_len);
-          // 注意 prepare_opacity 函数只分配空间并不进行数据初始化（没有初始化的空间也可以被 c 函数使用），因此需要强行 set_len 指定长度。
-          self.data.set_len(src_len);
-        }
-        // An error occured
-        _ => panic!("Could not compute indicator, err: {:?}", ret_code),
-      }
-      // println!(
-      //   "ema: {} {} {} {}",
-      //   self.period,
-      //   out_begin,
-      //   out_size,
-      //   self.data.len()
-      // );
-      // println!("{:?}", self.data);
+#[test]
+fn test_ema_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
     }
   }
+  let mut ind = EMAIndicator::new(3);
+  for v in [None, Some(1.), Some(2.), Some(3.), Some(7.)] {
+    ind.update(&[v]);
+  }
+  assert_eq!(ind.line.start_pos(), 3);
+  // 第一个 EMA 为前 3 个输入的均值，之后 k = 2 / (3 + 1)
+  assert_eq!(ind.line.as_slice(), &[0., 0., 0., 2., 4.5]);
+  // 有效输入从 2 开始，第一个 EMA 为 (2 + 1 + 2) / 3
+  crate::indicator::base::assert_outputs(
+    || EMAIndicator::new(3),
+    &[&D(vec![1., 2., 1., 2., 1., 3., 4.], 1)],
+    &[(3, &[5. / 3., 4. / 3., 13. / 6., 37. / 12.])],
+  );
 }
-// impl Indicator for SMAIndicator {}
@@ -0,0 +1,147 @@
+use std::collections::VecDeque;
+
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::TA_KAMA;
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  impl_indicator_trait, impl_indicator_with_period,
+  indicator::{
+    base::{check_inputs, check_update},
+    state::is_zero,
+  },
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// Kaufman 自适应移动平均，平滑系数在 2 周期和 30 周期的 EMA 之间按效率比例调整
+pub struct KAMAIndicator {
+  period: usize,
+  line: IndicatorLine,
+  state: KAMAState,
+}
+
+/// 增量计算的状态：最近 period + 1 个输入、相邻输入的变化的绝对值之和以及上一个 KAMA
+#[derive(Default)]
+struct KAMAState {
+  window: VecDeque<f64>,
+  sum_roc: f64,
+  prev: Option<f64>,
+}
+
+impl_indicator_with_period!(KAMAIndicator);
+impl_indicator_trait!(KAMAIndicator);
+
+impl Indicator for KAMAIndicator {
+  fn name(&self) -> &'static str {
+    "KAMA"
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    self.period
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_KAMA(0, end, ins[0], period, out_begin, out_size, outs[0])
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    const FASTEST: f64 = 2. / (2. + 1.);
+    const SLOWEST: f64 = 2. / (30. + 1.);
+    let period = self.period;
+    let value = check_update(self, inputs).and_then(|[v]| {
+      let state = &mut self.state;
+      if state.window.len() == period + 1 {
+        let first = state.window.pop_front().unwrap();
+        state.sum_roc -= (first - state.window[0]).abs();
+      }
+      if let Some(last) = state.window.back() {
+        state.sum_roc += (v - last).abs();
+      }
+      state.window.push_back(v);
+      if state.window.len() <= period {
+        return None;
+      }
+      // 第一个 KAMA 以前一个输入作为初始值
+      let prev = state.prev.unwrap_or(state.window[period - 1]);
+      let change = v - state.window[0];
+      let efficiency = if state.sum_roc <= change || is_zero(state.sum_roc) {
+        1.
+      } else {
+        (change / state.sum_roc).abs()
+      };
+      let sc = efficiency * (FASTEST - SLOWEST) + SLOWEST;
+      let kama = (v - prev) * (sc * sc) + prev;
+      state.prev = Some(kama);
+      Some(kama)
+    });
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_kama_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let mut ind = KAMAIndicator::new(2);
+  for v in [1., 2., 4., 3.] {
+    ind.update(&[Some(v)]);
+  }
+  assert_eq!(ind.line.start_pos(), 2);
+  // 单向变化时效率为 1，平滑系数为 (2 / 3) ^ 2
+  assert!((ind.at(2).unwrap() - (2. + 2. * (4. / 9.))).abs() < 1e-12);
+  let data = D(
+    vec![
+      0., 1., 3., 2., 5., 4., 4., 8., 7., 9., 6., 10., 12., 11., 13., 12.,
+    ],
+    1,
+  );
+  crate::indicator::base::assert_outputs(
+    || KAMAIndicator::new(3),
+    &[&data],
+    &[(
+      4,
+      &[
+        2.6513277064785905,
+        2.697459191195364,
+        2.871552979492064,
+        3.8013975256453443,
+        4.381339768238013,
+        5.511306822279909,
+        5.545685669432377,
+        5.85904013911016,
+        6.291047644129787,
+        7.443104897571962,
+        8.45063185141906,
+        8.46540549615821,
+      ],
+    )],
+  );
+}
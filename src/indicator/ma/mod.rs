@@ -1,5 +1,15 @@
+mod dema;
 mod ema;
+mod kama;
 mod sma;
+mod t3;
+mod tema;
+mod wma;
 
+pub use dema::*;
 pub use ema::*;
+pub use kama::*;
 pub use sma::*;
+pub use t3::*;
+pub use tema::*;
+pub use wma::*;
@@ -0,0 +1,148 @@
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::TA_T3;
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  impl_indicator_trait,
+  indicator::{
+    base::{check_inputs, check_update},
+    state::EmaState,
+  },
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// Tillson T3：对输入连续做 6 次 EMA，再按 volume factor 组合后 4 次 EMA 的结果
+pub struct T3Indicator {
+  period: usize,
+  volume_factor: f64,
+  line: IndicatorLine,
+  state: [EmaState; 6],
+}
+impl_indicator_trait!(T3Indicator);
+
+impl T3Indicator {
+  /// volume_factor 通常为 0.7
+  pub fn new(period: usize, volume_factor: f64) -> Self {
+    if period < 2 {
+      panic!("T3Indicator period must gte 2");
+    }
+    if !(0. ..=1.).contains(&volume_factor) {
+      panic!("T3Indicator volume factor must be between 0 and 1");
+    }
+    Self {
+      period,
+      volume_factor,
+      line: IndicatorLine::new(),
+      state: Default::default(),
+    }
+  }
+}
+
+impl Indicator for T3Indicator {
+  fn name(&self) -> &'static str {
+    "T3"
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![
+      ("period", self.period as f64),
+      ("volume_factor", self.volume_factor),
+    ]
+  }
+  fn lookback(&self) -> usize {
+    6 * (self.period - 1)
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let (period, volume_factor) = (self.period as i32, self.volume_factor);
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_T3(
+          0,
+          end,
+          ins[0],
+          period,
+          volume_factor,
+          out_begin,
+          out_size,
+          outs[0],
+        )
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let (period, a) = (self.period, self.volume_factor);
+    let value = check_update(self, inputs).and_then(|[v]| {
+      let mut e = [0.; 6];
+      let mut input = v;
+      for (ema, e) in self.state.iter_mut().zip(e.iter_mut()) {
+        *e = ema.next(period, input)?;
+        input = *e;
+      }
+      let c1 = -a * a * a;
+      let c2 = 3. * a * a + 3. * a * a * a;
+      let c3 = -6. * a * a - 3. * a - 3. * a * a * a;
+      let c4 = 1. + 3. * a + a * a * a + 3. * a * a;
+      Some(c1 * e[5] + c2 * e[4] + c3 * e[3] + c4 * e[2])
+    });
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_t3_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  // 输入不变时每一层 EMA 都等于输入，系数之和为 1
+  let mut ind = T3Indicator::new(2, 0.7);
+  for _ in 0..8 {
+    ind.update(&[Some(3.)]);
+  }
+  assert_eq!(ind.line.start_pos(), 6);
+  assert!((ind.at(7).unwrap() - 3.).abs() < 1e-12);
+  let data = D(
+    vec![
+      0., 1., 3., 2., 5., 4., 4., 8., 7., 9., 6., 10., 12., 11., 13., 12.,
+    ],
+    1,
+  );
+  crate::indicator::base::assert_outputs(
+    || T3Indicator::new(2, 0.7),
+    &[&data],
+    &[(
+      7,
+      &[
+        6.359261088248747,
+        7.099781943809379,
+        8.298663008687697,
+        7.254813595488489,
+        8.612681987276108,
+        10.752908664053642,
+        11.272132876462301,
+        12.337577910847138,
+        12.361467408957814,
+      ],
+    )],
+  );
+}
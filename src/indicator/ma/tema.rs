@@ -0,0 +1,102 @@
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::TA_TEMA;
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  impl_indicator_trait, impl_indicator_with_period,
+  indicator::{
+    base::{check_inputs, check_update},
+    state::EmaState,
+  },
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// 三重指数移动平均：3 * EMA - 3 * EMA(EMA) + EMA(EMA(EMA))
+pub struct TEMAIndicator {
+  period: usize,
+  line: IndicatorLine,
+  state: [EmaState; 3],
+}
+
+impl_indicator_with_period!(TEMAIndicator);
+impl_indicator_trait!(TEMAIndicator);
+
+impl Indicator for TEMAIndicator {
+  fn name(&self) -> &'static str {
+    "TEMA"
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    3 * (self.period - 1)
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_TEMA(0, end, ins[0], period, out_begin, out_size, outs[0])
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let period = self.period;
+    let value = check_update(self, inputs).and_then(|[v]| {
+      let [ema1, ema2, ema3] = &mut self.state;
+      let e1 = ema1.next(period, v)?;
+      let e2 = ema2.next(period, e1)?;
+      let e3 = ema3.next(period, e2)?;
+      Some(3. * e1 - 3. * e2 + e3)
+    });
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_tema_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let mut ind = TEMAIndicator::new(2);
+  for v in [1., 3., 5., 7., 9.] {
+    ind.update(&[Some(v)]);
+  }
+  // 线性增长的输入每一层 EMA(2) 都滞后 1，TEMA 没有滞后
+  assert_eq!(ind.line.start_pos(), 3);
+  assert_eq!(ind.line.as_slice(), &[0., 0., 0., 7., 9.]);
+  crate::indicator::base::assert_outputs(
+    || TEMAIndicator::new(3),
+    &[&D(vec![0., 1., 3., 2., 5., 4., 6., 8., 7., 9., 10.], 1)],
+    &[(
+      7,
+      &[
+        7.777777777777776,
+        7.292534722222222,
+        8.812934027777779,
+        9.972222222222223,
+      ],
+    )],
+  );
+}
@@ -0,0 +1,108 @@
+use std::collections::VecDeque;
+
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::TA_WMA;
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  impl_indicator_trait, impl_indicator_with_period,
+  indicator::base::{check_inputs, check_update},
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// 加权移动平均，最新的输入权重为 period，最早的为 1
+pub struct WMAIndicator {
+  period: usize,
+  line: IndicatorLine,
+  state: WMAState,
+}
+
+/// 增量计算的状态：最近 period 个输入，它们的和以及加权和
+#[derive(Default)]
+struct WMAState {
+  window: VecDeque<f64>,
+  sum: f64,
+  weighted_sum: f64,
+}
+
+impl_indicator_with_period!(WMAIndicator);
+impl_indicator_trait!(WMAIndicator);
+
+impl Indicator for WMAIndicator {
+  fn name(&self) -> &'static str {
+    "WMA"
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    self.period - 1
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_WMA(0, end, ins[0], period, out_begin, out_size, outs[0])
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let period = self.period;
+    let value = check_update(self, inputs).and_then(|[v]| {
+      let state = &mut self.state;
+      if state.window.len() == period {
+        // 移除最早的输入后其余输入的权重都减 1
+        state.weighted_sum -= state.sum;
+        state.sum -= state.window.pop_front().unwrap();
+      }
+      state.window.push_back(v);
+      state.sum += v;
+      state.weighted_sum += state.window.len() as f64 * v;
+      if state.window.len() < period {
+        return None;
+      }
+      Some(state.weighted_sum / (period * (period + 1) / 2) as f64)
+    });
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_wma_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let mut ind = WMAIndicator::new(3);
+  for v in [1., 2., 3., 6.] {
+    ind.update(&[Some(v)]);
+  }
+  // (1 + 2 * 2 + 3 * 3) / 6，(2 + 3 * 2 + 6 * 3) / 6
+  assert_eq!(ind.line.as_slice(), &[0., 0., 14. / 6., 26. / 6.]);
+  crate::indicator::base::assert_outputs(
+    || WMAIndicator::new(3),
+    &[&D(vec![0., 1., 3., 2., 5., 4.], 1)],
+    &[(3, &[2.1666666666666665, 3.6666666666666665, 4.])],
+  );
+}
@@ -0,0 +1,215 @@
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::TA_MACD;
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  indicator::{
+    base::{check_inputs, check_update},
+    state::EmaState,
+  },
+  DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// MACD，输出依次为 macd（快线 EMA - 慢线 EMA）、signal（macd 的 EMA）和 hist（macd - signal）。
+/// 和 ta-lib 一致，快线 EMA 和慢线 EMA 从同一个 bar 开始输出，三个输出的有效初始位置相同。
+pub struct MACDIndicator {
+  fast_period: usize,
+  slow_period: usize,
+  signal_period: usize,
+  lines: [IndicatorLine; 3],
+  state: MACDState,
+}
+
+#[derive(Default)]
+struct MACDState {
+  count: usize,
+  fast: EmaState,
+  slow: EmaState,
+  signal: EmaState,
+}
+
+impl MACDIndicator {
+  /// 常用的参数为 (12, 26, 9)，fast_period 大于 slow_period 时两者交换
+  pub fn new(fast_period: usize, slow_period: usize, signal_period: usize) -> Self {
+    if fast_period < 2 || slow_period < 2 {
+      panic!("MACDIndicator fast and slow period must gte 2");
+    }
+    if signal_period < 1 {
+      panic!("MACDIndicator signal period must gte 1");
+    }
+    Self {
+      fast_period: fast_period.min(slow_period),
+      slow_period: fast_period.max(slow_period),
+      signal_period,
+      lines: Default::default(),
+      state: Default::default(),
+    }
+  }
+  #[inline]
+  pub fn macd(&self) -> &IndicatorLine {
+    &self.lines[0]
+  }
+  #[inline]
+  pub fn signal(&self) -> &IndicatorLine {
+    &self.lines[1]
+  }
+  #[inline]
+  pub fn hist(&self) -> &IndicatorLine {
+    &self.lines[2]
+  }
+}
+
+impl Indicator for MACDIndicator {
+  fn name(&self) -> &'static str {
+    "MACD"
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![
+      ("fast_period", self.fast_period as f64),
+      ("slow_period", self.slow_period as f64),
+      ("signal_period", self.signal_period as f64),
+    ]
+  }
+  fn lookback(&self) -> usize {
+    (self.slow_period - 1) + (self.signal_period - 1)
+  }
+  fn outputs(&self) -> &'static [&'static str] {
+    &["macd", "signal", "hist"]
+  }
+  fn output(&self, index: usize) -> &IndicatorLine {
+    &self.lines[index]
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let (fast, slow, signal) = (
+      self.fast_period as i32,
+      self.slow_period as i32,
+      self.signal_period as i32,
+    );
+    let [macd, signal_line, hist] = &mut self.lines;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [macd, signal_line, hist],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_MACD(
+          0, end, ins[0], fast, slow, signal, out_begin, out_size, outs[0], outs[1], outs[2],
+        )
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.lines = Default::default();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let (fast, slow, signal) = (self.fast_period, self.slow_period, self.signal_period);
+    let values = check_update(self, inputs).and_then(|[v]| {
+      let state = &mut self.state;
+      state.count += 1;
+      let slow_ema = state.slow.next(slow, v);
+      // 快线 EMA 的第一个值和慢线 EMA 的第一个值在同一个 bar 上
+      if state.count <= slow - fast {
+        return None;
+      }
+      let macd = state.fast.next(fast, v)? - slow_ema?;
+      let signal = state.signal.next(signal, macd)?;
+      Some([macd, signal, macd - signal])
+    });
+    for (k, line) in self.lines.iter_mut().enumerate() {
+      line.push(values.map(|values| values[k]));
+    }
+  }
+}
+
+#[test]
+fn test_macd_indicator() {
+  use crate::DataLine;
+
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let mut ind = MACDIndicator::new(3, 2, 2);
+  assert_eq!(ind.params()[0], ("fast_period", 2.));
+  // 快线 EMA(2) 从第 1 个输入开始：(2 + 4) / 2 = 3，之后 4 + 2 / 3 * (8 - 3) ...
+  for v in [1., 2., 4., 8., 16.] {
+    ind.update(&[Some(v)]);
+  }
+  assert_eq!(ind.macd().start_pos(), 3);
+  assert_eq!(ind.signal().start_pos(), 3);
+  // 慢线 EMA(3)：7 / 3，之后 + (8 - 7 / 3) / 2
+  let fast = [3., 3. + (8. - 3.) * 2. / 3.];
+  let slow = [7. / 3., 7. / 3. + (8. - 7. / 3.) / 2.];
+  let macd = [fast[0] - slow[0], fast[1] - slow[1]];
+  assert!((ind.macd().at(3).unwrap() - macd[1]).abs() < 1e-9);
+  assert!((ind.signal().at(3).unwrap() - (macd[0] + macd[1]) / 2.).abs() < 1e-9);
+  assert!((ind.hist().at(3).unwrap() - (macd[1] - macd[0]) / 2.).abs() < 1e-9);
+  let data = D(
+    vec![
+      0., 1., 3., 2., 5., 4., 4., 8., 7., 9., 6., 10., 12., 11., 13., 12.,
+    ],
+    1,
+  );
+  crate::indicator::base::assert_outputs(
+    || MACDIndicator::new(3, 5, 2),
+    &[&data],
+    &[
+      (
+        6,
+        &[
+          0.49999999999999956,
+          1.0277777777777768,
+          0.8657407407407405,
+          1.0007716049382713,
+          0.3789866255144032,
+          0.7752271947873801,
+          1.1114361854138082,
+          0.8715998180536495,
+          0.9797210592579884,
+          0.6858079631164369,
+        ],
+      ),
+      (
+        6,
+        &[
+          0.583333333333333,
+          0.8796296296296289,
+          0.8703703703703699,
+          0.9573045267489708,
+          0.5717592592592591,
+          0.7074045496113397,
+          0.976758973479652,
+          0.906652869862317,
+          0.9553649961260979,
+          0.7756603074529906,
+        ],
+      ),
+      (
+        6,
+        &[
+          -0.08333333333333348,
+          0.14814814814814792,
+          -0.004629629629629428,
+          0.04346707818930051,
+          -0.19277263374485587,
+          0.06782264517604042,
+          0.13467721193415616,
+          -0.03505305180866747,
+          0.024356063131890426,
+          -0.08985234433655376,
+        ],
+      ),
+    ],
+  );
+}
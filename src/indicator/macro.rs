@@ -4,13 +4,13 @@ macro_rules! impl_indicator_trait {
     impl DataLineFeed for $indicator {
       #[inline(always)]
       fn inner(&self) -> (&[f64], usize) {
-        (&self.data, self.start_pos)
+        self.line.inner()
       }
     }
     impl DataLine for $indicator {
       #[inline(always)]
       fn at(&self, index: usize) -> Option<f64> {
-        get_vec_at(&self.data, self.start_pos, index)
+        self.line.at(index)
       }
     }
   };
@@ -21,11 +21,24 @@ macro_rules! impl_indicator_without_period {
     impl $indicator {
       pub fn new() -> Self {
         Self {
-          data: Vec::new(),
-          start_pos: 0,
+          line: IndicatorLine::new(),
         }
       }
     }
+    impl_indicator_without_period!(@default $indicator);
+  };
+  ($indicator: ident, state) => {
+    impl $indicator {
+      pub fn new() -> Self {
+        Self {
+          line: IndicatorLine::new(),
+          state: Default::default(),
+        }
+      }
+    }
+    impl_indicator_without_period!(@default $indicator);
+  };
+  (@default $indicator: ident) => {
     impl Default for $indicator {
       fn default() -> Self {
         Self::new()
@@ -42,12 +55,12 @@ macro_rules! impl_indicator_with_period {
     impl $indicator {
       pub fn new(period: usize) -> Self {
         if period < $min_period {
-          panic!("sma period must gte {}", $min_period);
+          panic!("{} period must gte {}", stringify!($indicator), $min_period);
         }
         Self {
-          data: Vec::new(),
+          line: IndicatorLine::new(),
+          state: Default::default(),
           period,
-          start_pos: 0,
         }
       }
     }
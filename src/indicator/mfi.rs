@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::TA_MFI;
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  impl_indicator_trait, impl_indicator_with_period,
+  indicator::base::{check_inputs, check_update},
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// 资金流量指标：最近 period 个 bar 中典型价格上涨的资金流量占总资金流量的百分比，
+/// 资金流量为典型价格 (high + low + close) / 3 乘以 volume，总资金流量小于 1 时为 0
+pub struct MFIIndicator {
+  period: usize,
+  line: IndicatorLine,
+  state: MFIState,
+}
+impl_indicator_with_period!(MFIIndicator);
+impl_indicator_trait!(MFIIndicator);
+
+#[derive(Default)]
+struct MFIState {
+  prev: Option<f64>,
+  /// 最近 period 个 bar 的正负资金流量
+  flows: VecDeque<(f64, f64)>,
+  positive: f64,
+  negative: f64,
+}
+
+impl Indicator for MFIIndicator {
+  fn name(&self) -> &'static str {
+    "MFI"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["high", "low", "close", "volume"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    self.period
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_MFI(
+          0, end, ins[0], ins[1], ins[2], ins[3], period, out_begin, out_size, outs[0],
+        )
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let period = self.period;
+    let value = check_update(self, inputs).and_then(|[high, low, close, volume]| {
+      let state = &mut self.state;
+      let typical = (high + low + close) / 3.;
+      let prev = state.prev.replace(typical)?;
+      let flow = typical * volume;
+      let (positive, negative) = if typical < prev {
+        (0., flow)
+      } else if typical > prev {
+        (flow, 0.)
+      } else {
+        (0., 0.)
+      };
+      if state.flows.len() == period {
+        let (p, n) = state.flows.pop_front().unwrap();
+        state.positive -= p;
+        state.negative -= n;
+      }
+      state.positive += positive;
+      state.negative += negative;
+      state.flows.push_back((positive, negative));
+      if state.flows.len() < period {
+        return None;
+      }
+      let total = state.positive + state.negative;
+      Some(if total < 1. {
+        0.
+      } else {
+        100. * (state.positive / total)
+      })
+    });
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_mfi_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let mut ind = MFIIndicator::new(2);
+  for [h, l, c, v] in [
+    [3., 1., 2., 10.],
+    [4., 2., 3., 10.],
+    [3., 1., 2., 30.],
+    [3., 1., 2., 10.],
+    [3., 1., 2., 0.1],
+  ] {
+    ind.update(&[Some(h), Some(l), Some(c), Some(v)]);
+  }
+  // 资金流量为 +30、-60、0、0
+  assert_eq!(ind.line.start_pos(), 2);
+  assert!((ind.at(2).unwrap() - 100. / 3.).abs() < 1e-9);
+  assert_eq!(ind.at(3), Some(0.));
+  // 总资金流量小于 1
+  assert_eq!(ind.at(4), Some(0.));
+  let high = D(vec![5., 6., 7., 6., 8., 9., 8., 7., 6., 8., 10., 9.], 0);
+  let low = D(vec![3., 4., 5., 4., 5., 7., 6., 5., 4., 5., 7., 7.], 0);
+  let close = D(
+    vec![4.5, 5., 6.5, 4.5, 7.5, 8., 6.5, 6., 4.5, 7., 9.5, 8.],
+    0,
+  );
+  let volume = D(
+    vec![
+      100., 200., 150., 300., 250., 100., 200., 150., 300., 250., 100., 200.,
+    ],
+    1,
+  );
+  crate::indicator::base::assert_outputs(
+    || MFIIndicator::new(3),
+    &[&high, &low, &close, &volume],
+    &[(
+      4,
+      &[
+        64.48979591836735,
+        63.36842105263158,
+        64.73118279569893,
+        26.086956521739133,
+        0.,
+        41.49377593360996,
+        63.75,
+        61.44578313253012,
+      ],
+    )],
+  );
+}
@@ -1,42 +1,45 @@
-use crate::{impl_indicator_trait, impl_indicator_without_period, DataLine, DataLineFeed};
+use crate::{
+  impl_indicator_trait, impl_indicator_without_period, DataLine, DataLineFeed, Indicator,
+  IndicatorLine,
+};
 
-use super::util::get_vec_at;
+use super::base::{check_inputs, check_update};
 
+/// 两个数据列逐个取最小值
 pub struct MinIndicator {
-  start_pos: usize,
-  data: Vec<f64>,
+  line: IndicatorLine,
 }
 impl_indicator_without_period!(MinIndicator);
 impl_indicator_trait!(MinIndicator);
 
-impl MinIndicator {
-  pub fn feed<DA: DataLineFeed, DB: DataLineFeed>(&mut self, data_line_a: &DA, data_line_b: &DB) {
-    let (buf_a, start_pos_a) = data_line_a.inner();
-    let (buf_b, start_pos_b) = data_line_b.inner();
-
-    let buf_a_len = buf_a.len();
-    assert_eq!(buf_a_len, buf_b.len());
-    let data_len = self.data.len();
-    if data_len == 0 {
-      self.data = Vec::with_capacity(buf_a_len)
-    } else {
-      assert_eq!(buf_a_len, data_len);
-    }
-    let start_pos = start_pos_a.max(start_pos_b);
-    self.start_pos = start_pos;
-    unsafe {
-      let mut pa = buf_a.as_ptr().add(start_pos);
-      let mut pb = buf_b.as_ptr().add(start_pos);
-      let mut pd = self.data.as_mut_ptr().add(start_pos);
-      for _ in start_pos..buf_a_len {
-        *pd = (*pa).min(*pb);
-        pa = pa.add(1);
-        pb = pb.add(1);
-        pd = pd.add(1);
-      }
-      self.data.set_len(buf_a_len);
+impl Indicator for MinIndicator {
+  fn name(&self) -> &'static str {
+    "MIN"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["a", "b"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    Vec::new()
+  }
+  fn lookback(&self) -> usize {
+    0
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let (buf_a, buf_b) = (inputs[0].inner().0, inputs[1].inner().0);
+    let data = self.line.reset(len, start_pos);
+    for i in start_pos..len {
+      data[i] = buf_a[i].min(buf_b[i]);
     }
   }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let value = check_update(self, inputs).map(|[a, b]| a.min(b));
+    self.line.push(value);
+  }
 }
 
 #[test]
@@ -50,10 +53,11 @@ fn test_min_indicator() {
   let d1 = D(vec![1.; 4], 1);
   let d2 = D(vec![2.; 4], 0);
   let mut ind = MinIndicator::new();
-  ind.feed(&d1, &d2);
-  assert_eq!(ind.data.len(), 4);
+  ind.compute(&[&d1, &d2]);
+  assert_eq!(ind.line.len(), 4);
   assert_eq!(ind.inner().1, 1);
   assert_eq!(ind.at(0), None);
   assert_eq!(ind.at(1), Some(1.));
-  assert_eq!(ind.inner().0, vec![0., 1., 1., 1.])
+  assert_eq!(ind.inner().0, vec![0., 1., 1., 1.]);
+  crate::indicator::base::assert_outputs(MinIndicator::new, &[&d1, &d2], &[(1, &[1.; 3])]);
 }
@@ -1,15 +1,62 @@
+mod ad;
+mod adx;
+mod atr;
+mod bar;
+mod base;
+mod bbands;
+mod cci;
+mod cdl;
 mod cross_over;
+mod graph;
 mod linearreg_slop;
 mod ma;
+mod macd;
 pub mod r#macro;
 mod max;
+mod mfi;
 mod min;
 mod mom;
+mod obv;
+#[cfg(all(test, feature = "ta-lib"))]
+mod parity;
+#[cfg(test)]
+mod reference;
+mod roc;
+mod rsi;
+mod sar;
+mod state;
+mod stoch;
+mod stoch_rsi;
+#[cfg(feature = "ta-lib")]
+mod talib;
+mod trix;
 pub mod util;
+mod vwap;
+mod willr;
 
+pub use ad::*;
+pub use adx::*;
+pub use atr::*;
+pub use bar::*;
+pub use base::*;
+pub use bbands::*;
+pub use cci::*;
+pub use cdl::*;
 pub use cross_over::*;
+pub use graph::*;
 pub use linearreg_slop::*;
 pub use ma::*;
+pub use macd::*;
 pub use max::*;
+pub use mfi::*;
 pub use min::*;
 pub use mom::*;
+pub use obv::*;
+pub use roc::*;
+pub use rsi::*;
+pub use sar::*;
+pub use stoch::*;
+pub use stoch_rsi::*;
+pub use trix::*;
+pub use vwap::*;
+pub use willr::*;
@@ -1,73 +1,72 @@
-extern crate ta_lib_wrapper;
+use std::collections::VecDeque;
 
-use ta_lib_wrapper::{TA_Integer, TA_RetCode, TA_MOM};
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::TA_MOM;
 
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
 use crate::{
-  impl_indicator_trait, impl_indicator_with_period, indicator::util::get_vec_at, DataLine,
-  DataLineFeed,
+  impl_indicator_trait, impl_indicator_with_period,
+  indicator::base::{check_inputs, check_update},
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
 };
 
 pub struct MOMIndicator {
   period: usize,
-  start_pos: usize,
-  data: Vec<f64>,
+  line: IndicatorLine,
+  /// 增量计算时最近的 period + 1 个输入
+  state: VecDeque<f64>,
 }
 impl_indicator_with_period!(MOMIndicator);
 impl_indicator_trait!(MOMIndicator);
 
-impl MOMIndicator {
-  pub fn feed<D: DataLineFeed>(&mut self, data_line: &D) {
-    let (src_data, src_start_pos) = data_line.inner();
-    self.start_pos = src_start_pos + self.period;
-    // println!("xx: {} {} {}", src_start_pos, self.period, self.start_pos);
-
-    let src_len = src_data.len();
-    let data_len = self.data.len();
-    if data_len == 0 {
-      self.data = Vec::with_capacity(src_len)
-    } else {
-      assert_eq!(src_len, data_len);
-    }
-
-    let mut out_begin: TA_Integer = 0;
-    let mut out_size: TA_Integer = 0;
-
-    unsafe {
-      let ret_code = TA_MOM(
-        0,
-        (src_len - src_start_pos - 1) as i32,
-        src_data.as_ptr().add(src_start_pos),
-        self.period as i32,
-        &mut out_begin,
-        &mut out_size,
-        self.data.as_mut_ptr().add(self.start_pos),
-      );
-      // println!("yy: {} {} {}", src_len - src_start_pos - 1, out_begin, out_size);
-      match ret_code {
-        // Indicator was computed correctly, since the vector was filled by TA-lib C library,
-        // Rust doesn't know what is the new length of the vector, so we set it manually
-        // to the number of values returned by the TA_MA call
-        TA_RetCode::TA_SUCCESS => {
-          assert_eq!(src_start_pos + out_begin as usize, self.start_pos);
-          assert_eq!(src_start_pos + (out_begin + out_size) as usize, src_len);
-          // 注意 prepare_opacity 函数只分配空间并不进行数据初始化（没有初始化的空间也可以被 c 函数使用），因此需要强行 set_len 指定长度。
-          self.data.set_len(src_len);
-        }
-        // An error occured
-        _ => panic!("Could not compute indicator, err: {:?}", ret_code),
+impl Indicator for MOMIndicator {
+  fn name(&self) -> &'static str {
+    "MOM"
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    self.period
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_MOM(0, end, ins[0], period, out_begin, out_size, outs[0])
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let value = check_update(self, inputs).and_then(|[v]| {
+      self.state.push_back(v);
+      if self.state.len() <= self.period {
+        return None;
       }
-      // println!(
-      //   "sma: {} {} {} {}",
-      //   self.period,
-      //   out_begin,
-      //   out_size,
-      //   self.data.len()
-      // );
-      // println!("{:?}", self.data);
-    }
+      Some(v - self.state.pop_front().unwrap())
+    });
+    self.line.push(value);
   }
 }
-// impl Indicator for SMAIndicator {}
 
 #[test]
 fn test_mom_indicator() {
@@ -79,6 +78,12 @@ fn test_mom_indicator() {
   }
   let d1 = D(vec![1., 1., 2., 3., 5., 8., 13.], 0);
   let mut ind = MOMIndicator::new(2);
-  ind.feed(&d1);
-  assert_eq!(ind.data, vec![0., 0., 1., 2., 3., 5., 8.]);
+  ind.compute(&[&d1]);
+  assert_eq!(ind.line.as_slice(), &[0., 0., 1., 2., 3., 5., 8.]);
+  // 有效输入从 2 开始，8 - 2，13 - 3
+  crate::indicator::base::assert_outputs(
+    || MOMIndicator::new(3),
+    &[&D(d1.0.clone(), 2)],
+    &[(5, &[6., 10.])],
+  );
 }
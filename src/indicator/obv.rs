@@ -0,0 +1,108 @@
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::TA_OBV;
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  impl_indicator_trait, impl_indicator_without_period,
+  indicator::base::{check_inputs, check_update},
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// 能量潮：close 上涨时累加 volume，下跌时减去 volume，第一个值为第一个 bar 的 volume
+pub struct OBVIndicator {
+  line: IndicatorLine,
+  /// 前一个 bar 的 close 和 OBV
+  state: Option<(f64, f64)>,
+}
+impl_indicator_without_period!(OBVIndicator, state);
+impl_indicator_trait!(OBVIndicator);
+
+impl Indicator for OBVIndicator {
+  fn name(&self) -> &'static str {
+    "OBV"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["close", "volume"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    Vec::new()
+  }
+  fn lookback(&self) -> usize {
+    0
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_OBV(0, end, ins[0], ins[1], out_begin, out_size, outs[0])
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let value = check_update(self, inputs).map(|[close, volume]| {
+      let obv = match self.state {
+        None => volume,
+        Some((prev_close, obv)) if close > prev_close => obv + volume,
+        Some((prev_close, obv)) if close < prev_close => obv - volume,
+        Some((_, obv)) => obv,
+      };
+      self.state = Some((close, obv));
+      obv
+    });
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_obv_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let mut ind = OBVIndicator::new();
+  for [c, v] in [[1., 10.], [2., 20.], [2., 5.], [1., 40.]] {
+    ind.update(&[Some(c), Some(v)]);
+  }
+  assert_eq!(ind.line.start_pos(), 0);
+  assert_eq!(ind.line.as_slice(), &[10., 30., 30., -10.]);
+  let close = D(
+    vec![4.5, 5., 6.5, 4.5, 7.5, 8., 6.5, 6., 4.5, 7., 9.5, 8.],
+    2,
+  );
+  let volume = D(
+    vec![
+      100., 200., 150., 300., 250., 100., 200., 150., 300., 250., 100., 200.,
+    ],
+    0,
+  );
+  crate::indicator::base::assert_outputs(
+    OBVIndicator::new,
+    &[&close, &volume],
+    &[(
+      2,
+      &[
+        150., -150., 100., 200., 0., -150., -450., -200., -100., -300.,
+      ],
+    )],
+  );
+}
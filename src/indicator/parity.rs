@@ -0,0 +1,106 @@
+//! ta-lib 和纯 Rust 实现的一致性检查：开启 ta-lib 时 compute 调用 ta-lib，update 为纯 Rust 实现，
+//! 纯 Rust 的 compute 也是逐个 bar 调用 update，因此两者一致即两种实现一致。
+//! 运行 cargo test --features ta-lib，需要安装 ta-lib。
+
+use super::reference::{bars, select, DATASETS};
+use crate::{
+  indicator::base::replay_update, ADIndicator, ADOSCIndicator, ADXIndicator, ATRIndicator,
+  BBANDSIndicator, CCIIndicator, CDLIndicator, CandlePattern, DEMAIndicator, DMIIndicator,
+  DataLineFeed, EMAIndicator, Indicator, KAMAIndicator, LinearregSlopeIndicator, MACDIndicator,
+  MFIIndicator, MOMIndicator, NATRIndicator, OBVIndicator, ROCIndicator, RSIIndicator,
+  SARIndicator, SMAIndicator, STOCHIndicator, STOCHRSIIndicator, T3Indicator, TEMAIndicator,
+  TRANGEIndicator, TRIXIndicator, WILLRIndicator, WMAIndicator,
+};
+
+/// 逐个 bar 调用 update 并检查结果和 compute 一致
+fn assert_update_eq_compute<I: Indicator>(
+  mut batch: I,
+  mut stream: I,
+  inputs: &[&dyn DataLineFeed],
+) {
+  batch.compute(inputs);
+  replay_update(&mut stream, inputs);
+  for k in 0..batch.outputs().len() {
+    let (expected, actual) = (batch.output(k), stream.output(k));
+    assert_eq!(actual.start_pos(), expected.start_pos(), "{}", batch.name());
+    assert_eq!(actual.len(), expected.len(), "{}", batch.name());
+    for i in expected.start_pos()..expected.len() {
+      let (e, a) = (expected.as_slice()[i], actual.as_slice()[i]);
+      assert!(
+        (e - a).abs() <= 1e-9 * e.abs().max(1.),
+        "{} output {} at {}: {} != {}",
+        batch.name(),
+        k,
+        i,
+        e,
+        a
+      );
+    }
+  }
+}
+
+fn assert_parity<I: Indicator>(new: impl Fn() -> I) {
+  for (seed, tick, start_pos) in DATASETS {
+    let data = bars(seed, tick, start_pos, 2000);
+    let indicator = new();
+    let inputs = select(&indicator, &data);
+    assert_update_eq_compute(indicator, new(), &inputs);
+  }
+}
+
+#[test]
+fn test_parity_overlap() {
+  for period in [2, 14, 30] {
+    assert_parity(|| SMAIndicator::new(period));
+    assert_parity(|| EMAIndicator::new(period));
+    assert_parity(|| WMAIndicator::new(period));
+    assert_parity(|| DEMAIndicator::new(period));
+    assert_parity(|| TEMAIndicator::new(period));
+    assert_parity(|| KAMAIndicator::new(period));
+  }
+  assert_parity(|| T3Indicator::new(5, 0.7));
+  assert_parity(|| BBANDSIndicator::new(20, 2., 2.));
+  assert_parity(|| BBANDSIndicator::new(5, 1.5, 2.5));
+  assert_parity(|| SARIndicator::new(0.02, 0.2));
+}
+
+#[test]
+fn test_parity_momentum() {
+  for period in [2, 14] {
+    assert_parity(|| RSIIndicator::new(period));
+    assert_parity(|| ADXIndicator::new(period));
+    assert_parity(|| DMIIndicator::new(period));
+    assert_parity(|| CCIIndicator::new(period));
+    assert_parity(|| WILLRIndicator::new(period));
+    assert_parity(|| ROCIndicator::new(period));
+    assert_parity(|| MOMIndicator::new(period));
+    assert_parity(|| TRIXIndicator::new(period));
+    assert_parity(|| LinearregSlopeIndicator::new(period));
+  }
+  assert_parity(|| MACDIndicator::new(12, 26, 9));
+  assert_parity(|| STOCHIndicator::new(5, 3, 3));
+  assert_parity(|| STOCHRSIIndicator::new(14, 5, 3));
+}
+
+#[test]
+fn test_parity_volume() {
+  for period in [2, 14] {
+    assert_parity(|| ATRIndicator::new(period));
+    assert_parity(|| NATRIndicator::new(period));
+    assert_parity(|| MFIIndicator::new(period));
+  }
+  assert_parity(TRANGEIndicator::new);
+  assert_parity(OBVIndicator::new);
+  assert_parity(ADIndicator::new);
+  assert_parity(|| ADOSCIndicator::new(3, 10));
+}
+
+#[test]
+fn test_parity_candle() {
+  for pattern in CandlePattern::ALL {
+    assert_parity(|| CDLIndicator::new(*pattern));
+    if pattern.default_penetration().is_some() {
+      assert_parity(|| CDLIndicator::new(*pattern).penetration(0.5));
+    }
+  }
+}
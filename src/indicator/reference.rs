@@ -0,0 +1,299 @@
+//! 和 ta-lib 的参考输出比较。参考输出保存在 talib_reference.json，包括 bars() 生成的输入数据
+//! 和 ta-lib 基于这些数据计算的输出，默认的测试比较 compute 的结果和参考输出，文件不存在时跳过。
+//! 更新 ta-lib 或者修改指标列表之后需要在安装了 ta-lib 的环境中重新生成：
+//! cargo test --lib --features ta-lib -- --ignored test_write_talib_reference
+
+use std::{fs::read_to_string, io::ErrorKind};
+
+use serde_json::Value;
+
+use crate::{
+  ADIndicator, ADOSCIndicator, ADXIndicator, ATRIndicator, BBANDSIndicator, CCIIndicator,
+  CDLIndicator, CandlePattern, DEMAIndicator, DMIIndicator, DataLineFeed, EMAIndicator, Indicator,
+  KAMAIndicator, LinearregSlopeIndicator, MACDIndicator, MFIIndicator, MOMIndicator, NATRIndicator,
+  OBVIndicator, ROCIndicator, RSIIndicator, SARIndicator, SMAIndicator, STOCHIndicator,
+  STOCHRSIIndicator, SyntheticRng, T3Indicator, TEMAIndicator, TRANGEIndicator, TRIXIndicator,
+  WILLRIndicator, WMAIndicator,
+};
+
+/// 测试数据的 seed、tick 和 close 的有效初始位置
+pub(super) const DATASETS: [(u64, f64, usize); 3] = [(1, 0., 0), (2, 0.5, 0), (3, 0., 7)];
+/// 参考输出使用的 bar 数量
+const REFERENCE_LEN: usize = 500;
+const REFERENCE_PATH: &str = concat!(
+  env!("CARGO_MANIFEST_DIR"),
+  "/src/indicator/talib_reference.json"
+);
+
+pub(super) struct D(pub(super) Vec<f64>, pub(super) usize);
+impl DataLineFeed for D {
+  fn inner(&self) -> (&[f64], usize) {
+    (&self.0, self.1)
+  }
+}
+
+/// 随机生成 len 个 bar 的 open、high、low、close 和 volume。open 相对前一个 bar 的 close 有跳空，
+/// 实体和影线的长度随机变化，以覆盖各种 K 线形态。tick 不为 0 时价格按 tick 取整，
+/// 会出现十字星、相等的最高价等边界情况；close 从 start_pos 开始有效。
+pub(super) fn bars(seed: u64, tick: f64, start_pos: usize, len: usize) -> [D; 5] {
+  let mut rng = SyntheticRng::new(seed);
+  let round = |v: f64| {
+    if tick > 0. {
+      (v / tick).round() * tick
+    } else {
+      v
+    }
+  };
+  let mut columns: [Vec<f64>; 5] = Default::default();
+  let mut close = 100.;
+  for _ in 0..len {
+    let open: f64 = close * (0.005 * rng.normal()).exp();
+    close = open * (0.02 * rng.uniform().powi(2) * rng.normal()).exp();
+    let high = open.max(close) * (0.01 * rng.uniform().powi(2)).exp();
+    let low = open.min(close) * (-0.01 * rng.uniform().powi(2)).exp();
+    let volume = 1000. * (0.5 * rng.normal()).exp();
+    for (column, v) in columns.iter_mut().zip([open, high, low, close]) {
+      column.push(round(v));
+    }
+    columns[4].push(volume);
+  }
+  let [open, high, low, close, volume] = columns;
+  [
+    D(open, 0),
+    D(high, 0),
+    D(low, 0),
+    D(close, start_pos),
+    D(volume, 0),
+  ]
+}
+
+/// 按指标的输入名称选择 bars() 的数据列
+pub(super) fn select<'a>(indicator: &dyn Indicator, data: &'a [D; 5]) -> Vec<&'a dyn DataLineFeed> {
+  indicator
+    .inputs()
+    .iter()
+    .map(|name| match *name {
+      "open" => &data[0] as &dyn DataLineFeed,
+      "high" => &data[1],
+      "low" => &data[2],
+      "close" | "real" => &data[3],
+      "volume" => &data[4],
+      _ => unreachable!(),
+    })
+    .collect()
+}
+
+/// 参与比较的指标，和 ta-lib 一致性检查的参数相同
+fn indicators() -> Vec<Box<dyn Indicator>> {
+  let mut list: Vec<Box<dyn Indicator>> = Vec::new();
+  for period in [2, 14, 30] {
+    list.push(Box::new(SMAIndicator::new(period)));
+    list.push(Box::new(EMAIndicator::new(period)));
+    list.push(Box::new(WMAIndicator::new(period)));
+    list.push(Box::new(DEMAIndicator::new(period)));
+    list.push(Box::new(TEMAIndicator::new(period)));
+    list.push(Box::new(KAMAIndicator::new(period)));
+  }
+  list.push(Box::new(T3Indicator::new(5, 0.7)));
+  list.push(Box::new(BBANDSIndicator::new(20, 2., 2.)));
+  list.push(Box::new(BBANDSIndicator::new(5, 1.5, 2.5)));
+  list.push(Box::new(SARIndicator::new(0.02, 0.2)));
+  for period in [2, 14] {
+    list.push(Box::new(RSIIndicator::new(period)));
+    list.push(Box::new(ADXIndicator::new(period)));
+    list.push(Box::new(DMIIndicator::new(period)));
+    list.push(Box::new(CCIIndicator::new(period)));
+    list.push(Box::new(WILLRIndicator::new(period)));
+    list.push(Box::new(ROCIndicator::new(period)));
+    list.push(Box::new(MOMIndicator::new(period)));
+    list.push(Box::new(TRIXIndicator::new(period)));
+    list.push(Box::new(LinearregSlopeIndicator::new(period)));
+    list.push(Box::new(ATRIndicator::new(period)));
+    list.push(Box::new(NATRIndicator::new(period)));
+    list.push(Box::new(MFIIndicator::new(period)));
+  }
+  list.push(Box::new(MACDIndicator::new(12, 26, 9)));
+  list.push(Box::new(STOCHIndicator::new(5, 3, 3)));
+  list.push(Box::new(STOCHRSIIndicator::new(14, 5, 3)));
+  list.push(Box::new(TRANGEIndicator::new()));
+  list.push(Box::new(OBVIndicator::new()));
+  list.push(Box::new(ADIndicator::new()));
+  list.push(Box::new(ADOSCIndicator::new(3, 10)));
+  for pattern in CandlePattern::ALL {
+    list.push(Box::new(CDLIndicator::new(*pattern)));
+    // 默认值为 0.5 的形态不重复比较
+    if pattern.default_penetration().is_some_and(|p| p != 0.5) {
+      list.push(Box::new(CDLIndicator::new(*pattern).penetration(0.5)));
+    }
+  }
+  list
+}
+
+/// 参考输出中指标的名称，比如 BBANDS(timeperiod=5,nbdevup=1.5,nbdevdn=2.5)
+fn key(indicator: &dyn Indicator) -> String {
+  let params: Vec<String> = indicator
+    .params()
+    .iter()
+    .map(|(name, value)| format!("{}={}", name, value))
+    .collect();
+  format!("{}({})", indicator.name(), params.join(","))
+}
+
+fn floats(value: &Value) -> Vec<f64> {
+  value
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|v| v.as_f64().unwrap())
+    .collect()
+}
+
+/// 参考输出尚未生成时跳过
+#[test]
+fn test_talib_reference() {
+  let content = match read_to_string(REFERENCE_PATH) {
+    Ok(content) => content,
+    Err(e) if e.kind() == ErrorKind::NotFound => {
+      eprintln!(
+        "skip: {} not found, generate it with ta-lib installed: cargo test --lib --features \
+         ta-lib -- --ignored test_write_talib_reference",
+        REFERENCE_PATH
+      );
+      return;
+    }
+    Err(e) => panic!("{}: {}", REFERENCE_PATH, e),
+  };
+  let reference: Value = serde_json::from_str(&content).unwrap();
+  let datasets: Vec<[D; 5]> = reference["datasets"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|d| {
+      let start_pos = d["start_pos"].as_u64().unwrap() as usize;
+      ["open", "high", "low", "close", "volume"].map(|name| {
+        D(
+          floats(&d[name]),
+          if name == "close" { start_pos } else { 0 },
+        )
+      })
+    })
+    .collect();
+  // 参考输出的输入数据就是 bars() 生成的数据
+  assert_eq!(datasets.len(), DATASETS.len());
+  for (data, &(seed, tick, start_pos)) in datasets.iter().zip(&DATASETS) {
+    for (d, b) in data.iter().zip(bars(seed, tick, start_pos, REFERENCE_LEN)) {
+      assert_eq!(d.1, b.1);
+      assert_eq!(d.0.len(), b.0.len());
+      assert!(d
+        .0
+        .iter()
+        .zip(&b.0)
+        .all(|(x, y)| (x - y).abs() <= 1e-12 * x.abs()));
+    }
+  }
+  let outputs = reference["outputs"].as_object().unwrap();
+  let mut indicators = indicators();
+  assert_eq!(outputs.len(), indicators.len(), "reference is out of date");
+  for indicator in indicators.iter_mut() {
+    let key = key(indicator.as_ref());
+    let expected = outputs
+      .get(&key)
+      .unwrap_or_else(|| panic!("{} not found in reference", key))
+      .as_array()
+      .unwrap();
+    for (n, (data, expected)) in datasets.iter().zip(expected).enumerate() {
+      let inputs = select(indicator.as_ref(), data);
+      indicator.compute(&inputs);
+      for (k, expected) in expected.as_array().unwrap().iter().enumerate() {
+        let actual = indicator.output(k);
+        let start_pos = expected[0].as_u64().unwrap() as usize;
+        let values = floats(&expected[1]);
+        assert_eq!(actual.start_pos(), start_pos, "{} dataset {}", key, n);
+        assert_eq!(
+          actual.len(),
+          start_pos + values.len(),
+          "{} dataset {}",
+          key,
+          n
+        );
+        for (i, &e) in values.iter().enumerate() {
+          let a = actual.as_slice()[start_pos + i];
+          assert!(
+            (e - a).abs() <= 1e-9 * e.abs().max(1.),
+            "{} dataset {} output {} at {}: {} != {}",
+            key,
+            n,
+            k,
+            start_pos + i,
+            e,
+            a
+          );
+        }
+      }
+    }
+  }
+}
+
+/// 用 ta-lib 计算参考输出并写入 talib_reference.json，每个指标一行
+#[cfg(feature = "ta-lib")]
+#[test]
+#[ignore]
+fn test_write_talib_reference() {
+  use serde_json::json;
+
+  let datasets: Vec<[D; 5]> = DATASETS
+    .iter()
+    .map(|&(seed, tick, start_pos)| bars(seed, tick, start_pos, REFERENCE_LEN))
+    .collect();
+  let mut lines = Vec::new();
+  for indicator in indicators().iter_mut() {
+    let outputs: Vec<Value> = datasets
+      .iter()
+      .map(|data| {
+        let inputs = select(indicator.as_ref(), data);
+        indicator.compute(&inputs);
+        let outputs: Vec<Value> = (0..indicator.outputs().len())
+          .map(|k| {
+            let line = indicator.output(k);
+            json!([line.start_pos(), &line.as_slice()[line.start_pos()..]])
+          })
+          .collect();
+        json!(outputs)
+      })
+      .collect();
+    lines.push(format!(
+      "{}: {}",
+      json!(key(indicator.as_ref())),
+      json!(outputs)
+    ));
+  }
+  assert_eq!(
+    lines.len(),
+    indicators()
+      .iter()
+      .map(|i| key(i.as_ref()))
+      .collect::<std::collections::HashSet<_>>()
+      .len(),
+    "indicator names must be unique"
+  );
+  let datasets: Vec<String> = datasets
+    .iter()
+    .map(|[open, high, low, close, volume]| {
+      json!({
+        "start_pos": close.1,
+        "open": open.0,
+        "high": high.0,
+        "low": low.0,
+        "close": close.0,
+        "volume": volume.0,
+      })
+      .to_string()
+    })
+    .collect();
+  let content = format!(
+    "{{\n\"datasets\": [\n{}\n],\n\"outputs\": {{\n{}\n}}\n}}\n",
+    datasets.join(",\n"),
+    lines.join(",\n")
+  );
+  std::fs::write(REFERENCE_PATH, content).unwrap();
+}
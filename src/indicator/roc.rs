@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::TA_ROC;
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  impl_indicator_trait, impl_indicator_with_period,
+  indicator::base::{check_inputs, check_update},
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// 变动率：(输入 / period 个 bar 之前的输入 - 1) * 100，之前的输入为 0 时为 0
+pub struct ROCIndicator {
+  period: usize,
+  line: IndicatorLine,
+  /// 增量计算时最近的 period + 1 个输入
+  state: VecDeque<f64>,
+}
+impl_indicator_with_period!(ROCIndicator, 1);
+impl_indicator_trait!(ROCIndicator);
+
+impl Indicator for ROCIndicator {
+  fn name(&self) -> &'static str {
+    "ROC"
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    self.period
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_ROC(0, end, ins[0], period, out_begin, out_size, outs[0])
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let value = check_update(self, inputs).and_then(|[v]| {
+      self.state.push_back(v);
+      if self.state.len() <= self.period {
+        return None;
+      }
+      let prev = self.state.pop_front().unwrap();
+      Some(if prev != 0. {
+        (v / prev - 1.) * 100.
+      } else {
+        0.
+      })
+    });
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_roc_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let mut ind = ROCIndicator::new(2);
+  for v in [0., 2., 5., 3.] {
+    ind.update(&[Some(v)]);
+  }
+  assert_eq!(ind.line.as_slice(), &[0., 0., 0., 50.]);
+  crate::indicator::base::assert_outputs(
+    || ROCIndicator::new(3),
+    &[&D(vec![0., 1., 3., 2., 5., 4., 4., 8., 7.], 1)],
+    &[(4, &[400., 33.33333333333333, 100., 60., 75.])],
+  );
+}
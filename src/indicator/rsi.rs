@@ -0,0 +1,138 @@
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::TA_RSI;
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  impl_indicator_trait, impl_indicator_with_period,
+  indicator::{
+    base::{check_inputs, check_update},
+    state::is_zero,
+  },
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// 相对强弱指标，涨跌幅按 Wilder 的方式平滑
+pub struct RSIIndicator {
+  period: usize,
+  line: IndicatorLine,
+  state: RSIState,
+}
+impl_indicator_with_period!(RSIIndicator);
+impl_indicator_trait!(RSIIndicator);
+
+/// RSI 的增量计算状态，STOCHRSI 也会用到
+#[derive(Default)]
+pub(crate) struct RSIState {
+  count: usize,
+  prev: f64,
+  gain: f64,
+  loss: f64,
+}
+
+impl RSIState {
+  pub(crate) fn next(&mut self, period: usize, v: f64) -> Option<f64> {
+    self.count += 1;
+    let diff = v - self.prev;
+    self.prev = v;
+    if self.count == 1 {
+      return None;
+    }
+    let n = period as f64;
+    if self.count > period + 1 {
+      self.gain *= n - 1.;
+      self.loss *= n - 1.;
+    }
+    if diff < 0. {
+      self.loss -= diff;
+    } else {
+      self.gain += diff;
+    }
+    if self.count <= period {
+      return None;
+    }
+    self.gain /= n;
+    self.loss /= n;
+    let total = self.gain + self.loss;
+    Some(if is_zero(total) {
+      0.
+    } else {
+      100. * (self.gain / total)
+    })
+  }
+}
+
+impl Indicator for RSIIndicator {
+  fn name(&self) -> &'static str {
+    "RSI"
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    self.period
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_RSI(0, end, ins[0], period, out_begin, out_size, outs[0])
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let value = check_update(self, inputs).and_then(|[v]| self.state.next(self.period, v));
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_rsi_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let mut ind = RSIIndicator::new(2);
+  for v in [1., 2., 4., 1., 1.] {
+    ind.update(&[Some(v)]);
+  }
+  assert_eq!(ind.line.start_pos(), 2);
+  // 平均涨幅 1.5，平均跌幅 0；之后涨幅 0.75、跌幅 1.5；之后涨幅 0.375、跌幅 0.75
+  assert_eq!(ind.at(2), Some(100.));
+  assert!((ind.at(3).unwrap() - 100. / 3.).abs() < 1e-9);
+  assert!((ind.at(4).unwrap() - 100. / 3.).abs() < 1e-9);
+  crate::indicator::base::assert_outputs(
+    || RSIIndicator::new(3),
+    &[&D(vec![0., 1., 3., 2., 5., 4., 4., 8., 7.], 1)],
+    &[(
+      4,
+      &[
+        83.33333333333334,
+        66.66666666666667,
+        66.66666666666667,
+        88.0952380952381,
+        70.9832134292566,
+      ],
+    )],
+  );
+}
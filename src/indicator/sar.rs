@@ -0,0 +1,220 @@
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::TA_SAR;
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  impl_indicator_trait,
+  indicator::base::{check_inputs, check_update},
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// 抛物线转向指标。和 ta-lib 一致，初始方向由前两个 bar 的 -DM 决定：-DM 大于 0 时为空头，否则为多头。
+pub struct SARIndicator {
+  acceleration: f64,
+  maximum: f64,
+  line: IndicatorLine,
+  state: SARState,
+}
+impl_indicator_trait!(SARIndicator);
+
+#[derive(Default)]
+struct SARState {
+  /// 第一个 bar 的 high 和 low
+  first: Option<(f64, f64)>,
+  trend: Option<SARTrend>,
+}
+
+struct SARTrend {
+  is_long: bool,
+  sar: f64,
+  /// 极值点
+  ep: f64,
+  af: f64,
+  /// 上一个 bar 的 high 和 low
+  prev_high: f64,
+  prev_low: f64,
+}
+
+impl SARIndicator {
+  /// 常用的参数为 (0.02, 0.2)，acceleration 大于 maximum 时取 maximum
+  pub fn new(acceleration: f64, maximum: f64) -> Self {
+    if acceleration.is_nan() || acceleration < 0. || maximum.is_nan() || maximum < 0. {
+      panic!("SARIndicator acceleration and maximum must gte 0");
+    }
+    Self {
+      acceleration: acceleration.min(maximum),
+      maximum,
+      line: IndicatorLine::new(),
+      state: Default::default(),
+    }
+  }
+}
+
+impl SARTrend {
+  fn next(&mut self, high: f64, low: f64, acceleration: f64, maximum: f64) -> f64 {
+    let (prev_high, prev_low) = (self.prev_high, self.prev_low);
+    (self.prev_high, self.prev_low) = (high, low);
+    let output;
+    if self.is_long {
+      if low <= self.sar {
+        // 转为空头，SAR 取极值点并且不低于最近两个 bar 的最高价
+        self.is_long = false;
+        output = self.ep.max(prev_high).max(high);
+        self.af = acceleration;
+        self.ep = low;
+        self.sar = output + self.af * (self.ep - output);
+        self.sar = self.sar.max(prev_high).max(high);
+      } else {
+        output = self.sar;
+        if high > self.ep {
+          self.ep = high;
+          self.af = (self.af + acceleration).min(maximum);
+        }
+        self.sar += self.af * (self.ep - self.sar);
+        self.sar = self.sar.min(prev_low).min(low);
+      }
+    } else if high >= self.sar {
+      // 转为多头，SAR 取极值点并且不高于最近两个 bar 的最低价
+      self.is_long = true;
+      output = self.ep.min(prev_low).min(low);
+      self.af = acceleration;
+      self.ep = high;
+      self.sar = output + self.af * (self.ep - output);
+      self.sar = self.sar.min(prev_low).min(low);
+    } else {
+      output = self.sar;
+      if low < self.ep {
+        self.ep = low;
+        self.af = (self.af + acceleration).min(maximum);
+      }
+      self.sar += self.af * (self.ep - self.sar);
+      self.sar = self.sar.max(prev_high).max(high);
+    }
+    output
+  }
+}
+
+impl Indicator for SARIndicator {
+  fn name(&self) -> &'static str {
+    "SAR"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["high", "low"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![
+      ("acceleration", self.acceleration),
+      ("maximum", self.maximum),
+    ]
+  }
+  fn lookback(&self) -> usize {
+    1
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let (acceleration, maximum) = (self.acceleration, self.maximum);
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_SAR(
+          0,
+          end,
+          ins[0],
+          ins[1],
+          acceleration,
+          maximum,
+          out_begin,
+          out_size,
+          outs[0],
+        )
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let (acceleration, maximum) = (self.acceleration, self.maximum);
+    let value = check_update(self, inputs).and_then(|[high, low]| {
+      let state = &mut self.state;
+      let trend = match (&mut state.trend, state.first) {
+        (Some(trend), _) => trend,
+        (None, None) => {
+          state.first = Some((high, low));
+          return None;
+        }
+        (None, Some((first_high, first_low))) => {
+          let (diff_plus, diff_minus) = (high - first_high, first_low - low);
+          let is_long = !(diff_minus > 0. && diff_plus < diff_minus);
+          // 第二个 bar 同时作为上一个 bar
+          state.trend.insert(SARTrend {
+            is_long,
+            sar: if is_long { first_low } else { first_high },
+            ep: if is_long { high } else { low },
+            af: acceleration,
+            prev_high: high,
+            prev_low: low,
+          })
+        }
+      };
+      Some(trend.next(high, low, acceleration, maximum))
+    });
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_sar_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let mut ind = SARIndicator::new(0.1, 0.2);
+  for [h, l] in [[2., 1.], [3., 2.], [4., 3.], [3.5, 1.]] {
+    ind.update(&[Some(h), Some(l)]);
+  }
+  assert_eq!(ind.line.start_pos(), 1);
+  // 多头：SAR 从第一个 bar 的最低价开始，1 + 0.1 * (3 - 1) = 1.2，之后极值点为 4，af 为 0.2
+  assert_eq!(ind.at(1), Some(1.));
+  assert!((ind.at(2).unwrap() - 1.2).abs() < 1e-9);
+  // 1.2 + 0.2 * (4 - 1.2) = 1.76，最低价 1 跌破 SAR 转为空头，SAR 取极值点 4
+  assert_eq!(ind.at(3), Some(4.));
+  let high = D(vec![5., 6., 7., 6., 8., 9., 8., 7., 6., 8., 10., 9.], 0);
+  let low = D(vec![3., 4., 5., 4., 5., 7., 6., 5., 4., 5., 7., 7.], 0);
+  crate::indicator::base::assert_outputs(
+    || SARIndicator::new(0.02, 0.2),
+    &[&high, &low],
+    &[(
+      1,
+      &[
+        3.,
+        3.06,
+        3.2176,
+        3.368896,
+        3.64676224,
+        4.0750212608,
+        4.469019559936,
+        9.,
+        8.9,
+        4.,
+        4.12,
+      ],
+    )],
+  );
+}
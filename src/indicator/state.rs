@@ -0,0 +1,107 @@
+//! 多个指标共用的增量计算状态，计算顺序和 ta-lib 一致。
+
+use std::collections::VecDeque;
+
+/// ta-lib 的 TA_IS_ZERO
+#[inline(always)]
+pub(crate) fn is_zero(v: f64) -> bool {
+  -0.00000001 < v && v < 0.00000001
+}
+
+/// 真实波幅，prev_close 为前一个 bar 的 close
+#[inline(always)]
+pub(crate) fn true_range(high: f64, low: f64, prev_close: f64) -> f64 {
+  (high - low)
+    .max((high - prev_close).abs())
+    .max((low - prev_close).abs())
+}
+
+/// 随机指标的 %K：close 在最高价和最低价区间中的位置，区间为 0 时为 0
+#[inline(always)]
+pub(crate) fn stoch_k(close: f64, highest: f64, lowest: f64) -> f64 {
+  let diff = (highest - lowest) / 100.;
+  if diff != 0. {
+    (close - lowest) / diff
+  } else {
+    0.
+  }
+}
+
+/// SMA：最近 period 个输入以及其中除最早一个之外的和
+#[derive(Default)]
+pub(crate) struct SmaState {
+  window: VecDeque<f64>,
+  sum: f64,
+}
+
+impl SmaState {
+  pub(crate) fn next(&mut self, period: usize, v: f64) -> Option<f64> {
+    self.window.push_back(v);
+    let total = self.sum + v;
+    if self.window.len() < period {
+      self.sum = total;
+      return None;
+    }
+    self.sum = total - self.window.pop_front().unwrap();
+    Some(total / period as f64)
+  }
+}
+
+/// EMA：以前 period 个输入的均值作为第一个值，之后 k = 2 / (period + 1)
+#[derive(Default)]
+pub(crate) struct EmaState {
+  count: usize,
+  sum: f64,
+  prev: f64,
+}
+
+impl EmaState {
+  pub(crate) fn next(&mut self, period: usize, v: f64) -> Option<f64> {
+    self.count += 1;
+    if self.count < period {
+      self.sum += v;
+      None
+    } else if self.count == period {
+      self.prev = (self.sum + v) / period as f64;
+      Some(self.prev)
+    } else {
+      let k = 2. / (period + 1) as f64;
+      self.prev += (v - self.prev) * k;
+      Some(self.prev)
+    }
+  }
+}
+
+/// 最近 period 个 bar 的最高价和最低价，单调队列保存候选值及其序号，均摊 O(1)
+#[derive(Default)]
+pub(crate) struct ExtremeState {
+  count: usize,
+  highs: VecDeque<(usize, f64)>,
+  lows: VecDeque<(usize, f64)>,
+}
+
+impl ExtremeState {
+  pub(crate) fn next(&mut self, period: usize, high: f64, low: f64) -> Option<(f64, f64)> {
+    let i = self.count;
+    self.count += 1;
+    while matches!(self.highs.back(), Some((_, h)) if *h <= high) {
+      self.highs.pop_back();
+    }
+    self.highs.push_back((i, high));
+    while matches!(self.lows.back(), Some((_, l)) if *l >= low) {
+      self.lows.pop_back();
+    }
+    self.lows.push_back((i, low));
+    if self.count < period {
+      return None;
+    }
+    let first = self.count - period;
+    while matches!(self.highs.front(), Some((j, _)) if *j < first) {
+      self.highs.pop_front();
+    }
+    while matches!(self.lows.front(), Some((j, _)) if *j < first) {
+      self.lows.pop_front();
+    }
+    Some((self.highs[0].1, self.lows[0].1))
+  }
+}
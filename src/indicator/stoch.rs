@@ -0,0 +1,193 @@
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::{TA_MAType, TA_STOCH};
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  indicator::{
+    base::{check_inputs, check_update},
+    state::{stoch_k, ExtremeState, SmaState},
+  },
+  DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// 慢速随机指标，输出依次为 slow_k（%K 的 SMA）和 slow_d（slow_k 的 SMA）。
+/// %K 为 close 在最近 fast_k_period 个 bar 的最高价和最低价区间中的位置（0 到 100）。
+pub struct STOCHIndicator {
+  fast_k_period: usize,
+  slow_k_period: usize,
+  slow_d_period: usize,
+  lines: [IndicatorLine; 2],
+  state: STOCHState,
+}
+
+#[derive(Default)]
+struct STOCHState {
+  extreme: ExtremeState,
+  slow_k: SmaState,
+  slow_d: SmaState,
+}
+
+impl STOCHIndicator {
+  /// 常用的参数为 (5, 3, 3)
+  pub fn new(fast_k_period: usize, slow_k_period: usize, slow_d_period: usize) -> Self {
+    if fast_k_period < 1 || slow_k_period < 1 || slow_d_period < 1 {
+      panic!("STOCHIndicator periods must gte 1");
+    }
+    Self {
+      fast_k_period,
+      slow_k_period,
+      slow_d_period,
+      lines: Default::default(),
+      state: Default::default(),
+    }
+  }
+  #[inline]
+  pub fn slow_k(&self) -> &IndicatorLine {
+    &self.lines[0]
+  }
+  #[inline]
+  pub fn slow_d(&self) -> &IndicatorLine {
+    &self.lines[1]
+  }
+}
+
+impl Indicator for STOCHIndicator {
+  fn name(&self) -> &'static str {
+    "STOCH"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["high", "low", "close"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![
+      ("fast_k_period", self.fast_k_period as f64),
+      ("slow_k_period", self.slow_k_period as f64),
+      ("slow_d_period", self.slow_d_period as f64),
+    ]
+  }
+  fn lookback(&self) -> usize {
+    (self.fast_k_period - 1) + (self.slow_k_period - 1) + (self.slow_d_period - 1)
+  }
+  fn outputs(&self) -> &'static [&'static str] {
+    &["slow_k", "slow_d"]
+  }
+  fn output(&self, index: usize) -> &IndicatorLine {
+    &self.lines[index]
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let (fast_k, slow_k, slow_d) = (
+      self.fast_k_period as i32,
+      self.slow_k_period as i32,
+      self.slow_d_period as i32,
+    );
+    let [k, d] = &mut self.lines;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [k, d],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_STOCH(
+          0,
+          end,
+          ins[0],
+          ins[1],
+          ins[2],
+          fast_k,
+          slow_k,
+          TA_MAType::TA_MAType_SMA,
+          slow_d,
+          TA_MAType::TA_MAType_SMA,
+          out_begin,
+          out_size,
+          outs[0],
+          outs[1],
+        )
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.lines = Default::default();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let (fast_k, slow_k, slow_d) = (self.fast_k_period, self.slow_k_period, self.slow_d_period);
+    let values = check_update(self, inputs).and_then(|[high, low, close]| {
+      let state = &mut self.state;
+      let (highest, lowest) = state.extreme.next(fast_k, high, low)?;
+      let k = state.slow_k.next(slow_k, stoch_k(close, highest, lowest))?;
+      let d = state.slow_d.next(slow_d, k)?;
+      Some([k, d])
+    });
+    for (k, line) in self.lines.iter_mut().enumerate() {
+      line.push(values.map(|values| values[k]));
+    }
+  }
+}
+
+#[test]
+fn test_stoch_indicator() {
+  use crate::DataLine;
+
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let mut ind = STOCHIndicator::new(2, 1, 2);
+  for [h, l, c] in [[2., 0., 1.], [4., 1., 3.], [3., 2., 2.], [6., 2., 6.]] {
+    ind.update(&[Some(h), Some(l), Some(c)]);
+  }
+  // %K: 75, 100 * 1 / 3, 100；%D 为相邻两个 %K 的均值
+  assert_eq!(ind.slow_k().start_pos(), 2);
+  assert!((ind.slow_k().at(2).unwrap() - 100. / 3.).abs() < 1e-9);
+  assert!((ind.slow_d().at(2).unwrap() - (75. + 100. / 3.) / 2.).abs() < 1e-9);
+  assert_eq!(ind.slow_k().at(3), Some(100.));
+  let high = D(vec![5., 6., 7., 6., 8., 9., 8., 7., 6., 8., 10., 9.], 0);
+  let low = D(vec![3., 4., 5., 4., 5., 7., 6., 5., 4., 5., 7., 7.], 0);
+  let close = D(
+    vec![4.5, 5., 6.5, 4.5, 7.5, 8., 6.5, 6., 4.5, 7., 9.5, 8.],
+    0,
+  );
+  crate::indicator::base::assert_outputs(
+    || STOCHIndicator::new(3, 2, 2),
+    &[&high, &low, &close],
+    &[
+      (
+        4,
+        &[
+          52.08333333333333,
+          83.75,
+          58.75,
+          31.25,
+          18.75,
+          43.75,
+          83.33333333333333,
+          75.83333333333333,
+        ],
+      ),
+      (
+        4,
+        &[
+          52.08333333333333,
+          67.91666666666666,
+          71.25,
+          45.,
+          25.,
+          31.25,
+          63.541666666666664,
+          79.58333333333333,
+        ],
+      ),
+    ],
+  );
+}
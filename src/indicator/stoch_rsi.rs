@@ -0,0 +1,197 @@
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::{TA_MAType, TA_STOCHRSI};
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  indicator::{
+    base::{check_inputs, check_update},
+    rsi::RSIState,
+    state::{stoch_k, ExtremeState, SmaState},
+  },
+  DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// RSI 的快速随机指标，输出依次为 fast_k 和 fast_d（fast_k 的 SMA）
+pub struct STOCHRSIIndicator {
+  period: usize,
+  fast_k_period: usize,
+  fast_d_period: usize,
+  lines: [IndicatorLine; 2],
+  state: STOCHRSIState,
+}
+
+#[derive(Default)]
+struct STOCHRSIState {
+  rsi: RSIState,
+  extreme: ExtremeState,
+  fast_d: SmaState,
+}
+
+impl STOCHRSIIndicator {
+  /// 常用的参数为 (14, 5, 3)
+  pub fn new(period: usize, fast_k_period: usize, fast_d_period: usize) -> Self {
+    if period < 2 {
+      panic!("STOCHRSIIndicator period must gte 2");
+    }
+    if fast_k_period < 1 || fast_d_period < 1 {
+      panic!("STOCHRSIIndicator fast k and fast d period must gte 1");
+    }
+    Self {
+      period,
+      fast_k_period,
+      fast_d_period,
+      lines: Default::default(),
+      state: Default::default(),
+    }
+  }
+  #[inline]
+  pub fn fast_k(&self) -> &IndicatorLine {
+    &self.lines[0]
+  }
+  #[inline]
+  pub fn fast_d(&self) -> &IndicatorLine {
+    &self.lines[1]
+  }
+}
+
+impl Indicator for STOCHRSIIndicator {
+  fn name(&self) -> &'static str {
+    "STOCHRSI"
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![
+      ("period", self.period as f64),
+      ("fast_k_period", self.fast_k_period as f64),
+      ("fast_d_period", self.fast_d_period as f64),
+    ]
+  }
+  fn lookback(&self) -> usize {
+    self.period + (self.fast_k_period - 1) + (self.fast_d_period - 1)
+  }
+  fn outputs(&self) -> &'static [&'static str] {
+    &["fast_k", "fast_d"]
+  }
+  fn output(&self, index: usize) -> &IndicatorLine {
+    &self.lines[index]
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let (period, fast_k, fast_d) = (
+      self.period as i32,
+      self.fast_k_period as i32,
+      self.fast_d_period as i32,
+    );
+    let [k, d] = &mut self.lines;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [k, d],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_STOCHRSI(
+          0,
+          end,
+          ins[0],
+          period,
+          fast_k,
+          fast_d,
+          TA_MAType::TA_MAType_SMA,
+          out_begin,
+          out_size,
+          outs[0],
+          outs[1],
+        )
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.lines = Default::default();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let (period, fast_k, fast_d) = (self.period, self.fast_k_period, self.fast_d_period);
+    let values = check_update(self, inputs).and_then(|[v]| {
+      let state = &mut self.state;
+      let rsi = state.rsi.next(period, v)?;
+      let (highest, lowest) = state.extreme.next(fast_k, rsi, rsi)?;
+      let k = stoch_k(rsi, highest, lowest);
+      let d = state.fast_d.next(fast_d, k)?;
+      Some([k, d])
+    });
+    for (k, line) in self.lines.iter_mut().enumerate() {
+      line.push(values.map(|values| values[k]));
+    }
+  }
+}
+
+#[test]
+fn test_stoch_rsi_indicator() {
+  use crate::{DataLine, RSIIndicator};
+
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let data: Vec<f64> = (0..40).map(|i| (i as f64 * 0.7).sin() + 10.).collect();
+  let mut ind = STOCHRSIIndicator::new(3, 4, 2);
+  let mut rsi = RSIIndicator::new(3);
+  for v in data.iter() {
+    ind.update(&[Some(*v)]);
+    rsi.update(&[Some(*v)]);
+  }
+  assert_eq!(ind.fast_k().start_pos(), 3 + 3 + 1);
+  let window: Vec<f64> = (4..=7).map(|i| rsi.at(i).unwrap()).collect();
+  let highest = window.iter().cloned().fold(f64::MIN, f64::max);
+  let lowest = window.iter().cloned().fold(f64::MAX, f64::min);
+  let k = (window[3] - lowest) / (highest - lowest) * 100.;
+  assert!((ind.fast_k().at(7).unwrap() - k).abs() < 1e-9);
+  let data = D(
+    vec![
+      0., 1., 3., 2., 5., 4., 4., 8., 7., 9., 6., 10., 12., 11., 13., 12.,
+    ],
+    1,
+  );
+  crate::indicator::base::assert_outputs(
+    || STOCHRSIIndicator::new(3, 3, 2),
+    &[&data],
+    &[
+      (
+        7,
+        &[
+          100.,
+          20.1438848920863,
+          62.432432432432435,
+          0.,
+          71.09253874357047,
+          100.,
+          0.,
+          87.7360521082962,
+          0.,
+        ],
+      ),
+      (
+        7,
+        &[
+          50.,
+          60.07194244604315,
+          41.28815866225937,
+          31.216216216216218,
+          35.546269371785236,
+          85.54626937178523,
+          50.,
+          43.8680260541481,
+          43.8680260541481,
+        ],
+      ),
+    ],
+  );
+}
@@ -0,0 +1,53 @@
+use ta_lib_wrapper::{TA_Integer, TA_RetCode};
+
+use crate::DataLineFeed;
+
+use super::base::IndicatorLine;
+
+/// 调用 ta-lib 函数计算指标，start_pos 为输出的有效初始位置。
+/// f 的参数依次为 endIdx、各个输入从有效初始位置开始的指针、outBegIdx、outNBElement 以及
+/// 各个输出从 start_pos 开始的指针，startIdx 总是 0。
+pub(crate) fn ta_call<F>(
+  inputs: &[&dyn DataLineFeed],
+  len: usize,
+  start_pos: usize,
+  outputs: &mut [&mut IndicatorLine],
+  f: F,
+) where
+  F: FnOnce(TA_Integer, &[*const f64], &mut TA_Integer, &mut TA_Integer, &[*mut f64]) -> TA_RetCode,
+{
+  let src_start_pos = inputs.iter().map(|i| i.inner().1).max().unwrap_or(0);
+  let out_ptrs: Vec<*mut f64> = outputs
+    .iter_mut()
+    .map(|line| line.reset(len, start_pos).as_mut_ptr())
+    .collect();
+  // 输入的数据不足以计算出任何有效值
+  if start_pos >= len {
+    return;
+  }
+  let in_ptrs: Vec<*const f64> = inputs
+    .iter()
+    .map(|i| unsafe { i.inner().0.as_ptr().add(src_start_pos) })
+    .collect();
+  let out_ptrs: Vec<*mut f64> = out_ptrs
+    .into_iter()
+    .map(|p| unsafe { p.add(start_pos) })
+    .collect();
+  let mut out_begin: TA_Integer = 0;
+  let mut out_size: TA_Integer = 0;
+  let ret_code = f(
+    (len - src_start_pos - 1) as TA_Integer,
+    &in_ptrs,
+    &mut out_begin,
+    &mut out_size,
+    &out_ptrs,
+  );
+  match ret_code {
+    // ta-lib 从 outBegIdx 对应的输入开始输出，因此输出的第一个值就是有效初始位置上的值
+    TA_RetCode::TA_SUCCESS => {
+      assert_eq!(src_start_pos + out_begin as usize, start_pos);
+      assert_eq!(start_pos + out_size as usize, len);
+    }
+    _ => panic!("Could not compute indicator, err: {:?}", ret_code),
+  }
+}
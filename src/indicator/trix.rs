@@ -0,0 +1,125 @@
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::TA_TRIX;
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  impl_indicator_trait, impl_indicator_with_period,
+  indicator::{
+    base::{check_inputs, check_update},
+    state::EmaState,
+  },
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// 三重 EMA 的单周期变动率（百分比）
+pub struct TRIXIndicator {
+  period: usize,
+  line: IndicatorLine,
+  state: TRIXState,
+}
+impl_indicator_with_period!(TRIXIndicator, 1);
+impl_indicator_trait!(TRIXIndicator);
+
+#[derive(Default)]
+struct TRIXState {
+  emas: [EmaState; 3],
+  prev: Option<f64>,
+}
+
+impl Indicator for TRIXIndicator {
+  fn name(&self) -> &'static str {
+    "TRIX"
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    3 * (self.period - 1) + 1
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_TRIX(0, end, ins[0], period, out_begin, out_size, outs[0])
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let period = self.period;
+    let value = check_update(self, inputs).and_then(|[v]| {
+      let [ema1, ema2, ema3] = &mut self.state.emas;
+      let e3 = ema1
+        .next(period, v)
+        .and_then(|e1| ema2.next(period, e1))
+        .and_then(|e2| ema3.next(period, e2))?;
+      let prev = self.state.prev.replace(e3)?;
+      Some(if prev != 0. {
+        (e3 / prev - 1.) * 100.
+      } else {
+        0.
+      })
+    });
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_trix_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  // 周期为 1 时 EMA 等于输入，TRIX 即单周期变动率
+  let mut ind = TRIXIndicator::new(1);
+  for v in [2., 3., 6.] {
+    ind.update(&[Some(v)]);
+  }
+  assert_eq!(ind.line.as_slice(), &[0., 50., 100.]);
+  let data = D(
+    vec![
+      0., 1., 3., 2., 5., 4., 4., 8., 7., 9., 6., 10., 12., 11., 13., 12.,
+    ],
+    1,
+  );
+  crate::indicator::base::assert_outputs(
+    || TRIXIndicator::new(2),
+    &[&data],
+    &[(
+      5,
+      &[
+        27.777777777777786,
+        10.14492753623189,
+        35.526315789473664,
+        18.662351672060417,
+        18.393939393939384,
+        -0.6398771435884316,
+        11.33150151697293,
+        18.387879099502115,
+        9.481860034166253,
+        10.006516199035726,
+        4.3207469132752045,
+      ],
+    )],
+  );
+}
@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+
+use crate::{
+  impl_indicator_trait, impl_indicator_with_period,
+  indicator::base::{check_inputs, check_update, replay_update},
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// 成交量加权平均价：最近 period 个 bar 的典型价格 (high + low + close) / 3 按 volume 加权的均值，
+/// 总成交量为 0 时为最近一个 bar 的典型价格。ta-lib 没有提供 VWAP，compute 逐个 bar 调用 update。
+pub struct VWAPIndicator {
+  period: usize,
+  line: IndicatorLine,
+  state: VWAPState,
+}
+impl_indicator_with_period!(VWAPIndicator, 1);
+impl_indicator_trait!(VWAPIndicator);
+
+#[derive(Default)]
+struct VWAPState {
+  /// 最近 period 个 bar 的典型价格乘以 volume 以及 volume
+  window: VecDeque<(f64, f64)>,
+  amount: f64,
+  volume: f64,
+}
+
+impl Indicator for VWAPIndicator {
+  fn name(&self) -> &'static str {
+    "VWAP"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["high", "low", "close", "volume"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    self.period - 1
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let period = self.period;
+    let value = check_update(self, inputs).and_then(|[high, low, close, volume]| {
+      let state = &mut self.state;
+      let typical = (high + low + close) / 3.;
+      if state.window.len() == period {
+        let (amount, volume) = state.window.pop_front().unwrap();
+        state.amount -= amount;
+        state.volume -= volume;
+      }
+      state.amount += typical * volume;
+      state.volume += volume;
+      state.window.push_back((typical * volume, volume));
+      if state.window.len() < period {
+        return None;
+      }
+      Some(if state.volume != 0. {
+        state.amount / state.volume
+      } else {
+        typical
+      })
+    });
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_vwap_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let mut ind = VWAPIndicator::new(2);
+  for [h, l, c, v] in [
+    [3., 1., 2., 10.],
+    [4., 2., 3., 30.],
+    [6., 3., 6., 0.],
+    [6., 3., 6., 0.],
+  ] {
+    ind.update(&[Some(h), Some(l), Some(c), Some(v)]);
+  }
+  assert_eq!(ind.line.start_pos(), 1);
+  assert_eq!(ind.at(1), Some(2.75));
+  assert_eq!(ind.at(2), Some(3.));
+  // 总成交量为 0
+  assert_eq!(ind.at(3), Some(5.));
+
+  let high = D(vec![5., 6., 7., 6., 8., 9., 8., 7., 6., 8., 10., 9.], 0);
+  let low = D(vec![3., 4., 5., 4., 5., 7., 6., 5., 4., 5., 7., 7.], 2);
+  let close = D(
+    vec![4.5, 5., 6.5, 4.5, 7.5, 8., 6.5, 6., 4.5, 7., 9.5, 8.],
+    0,
+  );
+  let volume = D(
+    vec![
+      100., 200., 150., 300., 250., 100., 200., 150., 300., 250., 100., 200.,
+    ],
+    0,
+  );
+  let inputs: [&dyn DataLineFeed; 4] = [&high, &low, &close, &volume];
+  let mut batch = VWAPIndicator::new(3);
+  batch.compute(&inputs);
+  // 重复计算的结果一致
+  let first = batch.line.clone();
+  batch.compute(&inputs);
+  assert_eq!(batch.line.as_slice(), first.as_slice());
+  crate::indicator::base::assert_outputs(
+    || VWAPIndicator::new(3),
+    &inputs,
+    &[(
+      4,
+      &[
+        5.833333333333333,
+        6.089743589743589,
+        7.045454545454545,
+        6.814814814814815,
+        5.717948717948718,
+        5.738095238095238,
+        6.153846153846154,
+        7.545454545454546,
+      ],
+    )],
+  );
+}
@@ -0,0 +1,121 @@
+#[cfg(feature = "ta-lib")]
+use ta_lib_wrapper::TA_WILLR;
+
+#[cfg(not(feature = "ta-lib"))]
+use crate::indicator::base::replay_update;
+#[cfg(feature = "ta-lib")]
+use crate::indicator::talib::ta_call;
+use crate::{
+  impl_indicator_trait, impl_indicator_with_period,
+  indicator::{
+    base::{check_inputs, check_update},
+    state::ExtremeState,
+  },
+  DataLine, DataLineFeed, Indicator, IndicatorLine,
+};
+
+/// 威廉指标：close 距离最近 period 个 bar 的最高价的位置（-100 到 0），区间为 0 时为 0
+pub struct WILLRIndicator {
+  period: usize,
+  line: IndicatorLine,
+  state: ExtremeState,
+}
+impl_indicator_with_period!(WILLRIndicator);
+impl_indicator_trait!(WILLRIndicator);
+
+impl Indicator for WILLRIndicator {
+  fn name(&self) -> &'static str {
+    "WILLR"
+  }
+  fn inputs(&self) -> &'static [&'static str] {
+    &["high", "low", "close"]
+  }
+  fn params(&self) -> Vec<(&'static str, f64)> {
+    vec![("period", self.period as f64)]
+  }
+  fn lookback(&self) -> usize {
+    self.period - 1
+  }
+  fn output(&self, _index: usize) -> &IndicatorLine {
+    &self.line
+  }
+  #[cfg(feature = "ta-lib")]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    let (len, start_pos) = check_inputs(self, inputs);
+    let period = self.period as i32;
+    ta_call(
+      inputs,
+      len,
+      start_pos,
+      &mut [&mut self.line],
+      |end, ins, out_begin, out_size, outs| unsafe {
+        TA_WILLR(
+          0, end, ins[0], ins[1], ins[2], period, out_begin, out_size, outs[0],
+        )
+      },
+    );
+  }
+  #[cfg(not(feature = "ta-lib"))]
+  fn compute(&mut self, inputs: &[&dyn DataLineFeed]) {
+    check_inputs(self, inputs);
+    self.line = IndicatorLine::new();
+    self.state = Default::default();
+    replay_update(self, inputs);
+  }
+  fn update(&mut self, inputs: &[Option<f64>]) {
+    let period = self.period;
+    let value = check_update(self, inputs).and_then(|[high, low, close]| {
+      let (highest, lowest) = self.state.next(period, high, low)?;
+      let diff = (highest - lowest) / -100.;
+      Some(if diff != 0. {
+        (highest - close) / diff
+      } else {
+        0.
+      })
+    });
+    self.line.push(value);
+  }
+}
+
+#[test]
+fn test_willr_indicator() {
+  struct D(Vec<f64>, usize);
+  impl DataLineFeed for D {
+    fn inner(&self) -> (&[f64], usize) {
+      (&self.0, self.1)
+    }
+  }
+  let mut ind = WILLRIndicator::new(2);
+  for [h, l, c] in [[2., 0., 1.], [4., 1., 3.], [3., 2., 1.]] {
+    ind.update(&[Some(h), Some(l), Some(c)]);
+  }
+  assert_eq!(ind.line.start_pos(), 1);
+  assert_eq!(ind.at(1), Some(-25.));
+  // close 等于最低价
+  assert!((ind.at(2).unwrap() + 100.).abs() < 1e-9);
+  let high = D(vec![5., 6., 7., 6., 8., 9., 8., 7., 6., 8., 10., 9.], 0);
+  let low = D(vec![3., 4., 5., 4., 5., 7., 6., 5., 4., 5., 7., 7.], 0);
+  let close = D(
+    vec![4.5, 5., 6.5, 4.5, 7.5, 8., 6.5, 6., 4.5, 7., 9.5, 8.],
+    0,
+  );
+  crate::indicator::base::assert_outputs(
+    || WILLRIndicator::new(3),
+    &[&high, &low, &close],
+    &[(
+      2,
+      &[
+        -12.5,
+        -83.33333333333334,
+        -12.5,
+        -20.,
+        -62.5,
+        -75.,
+        -87.5,
+        -25.,
+        -8.333333333333332,
+        -40.,
+      ],
+    )],
+  );
+}
@@ -1,13 +1,19 @@
 mod broker;
+mod calendar;
 mod csv;
 mod data;
 mod engine;
 mod indicator;
+mod portfolio;
 mod strategy;
+mod synthetic;
 
 pub use broker::*;
+pub use calendar::*;
 pub use csv::*;
 pub use data::*;
 pub use engine::*;
 pub use indicator::*;
+pub use portfolio::*;
 pub use strategy::*;
+pub use synthetic::*;
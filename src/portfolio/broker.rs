@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+  broker::Broker, Order, OrderStatus, PortfolioDataSource, Position, QuoteSide, Strategy, Trade,
+};
+
+/// 多品种的 broker，每个品种有独立的仓位和交易记录，共享同一个现金账户。
+pub struct PortfolioBroker {
+  pub(crate) cash: f64,
+  pub(crate) positions: HashMap<String, Position>,
+  pub(crate) trades: HashMap<String, Trade>,
+}
+
+impl Broker for PortfolioBroker {
+  type DS = PortfolioDataSource;
+
+  /// 获取当前剩余现金
+  #[inline]
+  fn cash(&self) -> f64 {
+    self.cash
+  }
+  /// 和 SingleDataSource::calc_position_value 一致，按各个品种在最近一个已经处理的时间点上
+  /// 最近一个 bar 的 close 价计算所有仓位的现金价值，有 bid、ask 报价时多仓按 bid 价、空仓按 ask 价计算
+  fn position_value(&self, data: &PortfolioDataSource) -> f64 {
+    if data.is_empty() {
+      return 0.;
+    }
+    let index = data.offset.clamp(1, data.len()) - 1;
+    self
+      .positions
+      .iter()
+      .filter(|(_, position)| position.size != 0)
+      .map(|(symbol, position)| match data.view(symbol, index) {
+        Some(view) => {
+          let side = if position.size > 0 {
+            QuoteSide::Bid
+          } else {
+            QuoteSide::Ask
+          };
+          position.size as f64 * view.data.quote_close(side, view.index).unwrap()
+        }
+        None => 0.,
+      })
+      .sum()
+  }
+}
+
+impl PortfolioBroker {
+  pub fn new(cash: f64) -> Self {
+    Self {
+      cash,
+      positions: HashMap::new(),
+      trades: HashMap::new(),
+    }
+  }
+  /// 获取品种的交易记录
+  #[inline]
+  pub fn trade(&self, symbol: &str) -> Option<&Trade> {
+    self.trades.get(symbol)
+  }
+  /// 获取品种的仓位
+  #[inline]
+  pub fn position(&self, symbol: &str) -> Option<&Position> {
+    self.positions.get(symbol)
+  }
+  /// 获取所有有过交易的品种的仓位
+  #[inline]
+  pub fn positions(&self) -> &HashMap<String, Position> {
+    &self.positions
+  }
+  /// 获取品种的当前持仓量
+  #[inline]
+  pub fn position_size(&self, symbol: &str) -> isize {
+    self.position(symbol).map_or(0, |position| position.size)
+  }
+  /// 品种是否是空仓位，等价于 position_size(symbol) == 0
+  #[inline]
+  pub fn is_position_empty(&self, symbol: &str) -> bool {
+    self.position_size(symbol) == 0
+  }
+  /// 建买仓（多仓）
+  #[inline]
+  pub fn buy<S: Strategy<BK = Self, DS = PortfolioDataSource>>(
+    &mut self,
+    symbol: &str,
+    size: isize,
+    data: &PortfolioDataSource,
+    strat: &S,
+  ) {
+    let order = Order::buy(size, data.timestamp[data.offset]);
+    self.submit_order(symbol, order, data, strat);
+  }
+  /// 建卖仓（空仓）
+  #[inline]
+  pub fn sell<S: Strategy<BK = Self, DS = PortfolioDataSource>>(
+    &mut self,
+    symbol: &str,
+    size: isize,
+    data: &PortfolioDataSource,
+    strat: &S,
+  ) {
+    let order = Order::sell(size, data.timestamp[data.offset]);
+    self.submit_order(symbol, order, data, strat);
+  }
+  fn submit_order<S: Strategy<BK = Self, DS = PortfolioDataSource>>(
+    &mut self,
+    symbol: &str,
+    mut order: Order,
+    data: &PortfolioDataSource,
+    strat: &S,
+  ) -> Order {
+    if order.size <= 0 {
+      panic!("order size must be greater than zero");
+    }
+    let view = data
+      .current(symbol)
+      .unwrap_or_else(|| panic!("symbol {} has no data at current time", symbol));
+    // 和 CsvBroker 一样以该品种下一个 bar 的 open 价作为成交价，已经是最后一个 bar 时以 close 价成交，
+    // 有 bid、ask 报价时买入以 ask 价成交，卖出以 bid 价成交
+    let feed = view.data;
+    let side = if order.is_buy() {
+      QuoteSide::Ask
+    } else {
+      QuoteSide::Bid
+    };
+    let exe_price = feed
+      .quote_open(side, view.index + 1)
+      .unwrap_or_else(|| feed.quote_close(side, view.index).unwrap());
+    let exe_time = feed
+      .timestamp
+      .at(view.index + 1)
+      .unwrap_or_else(|| view.timestamp());
+    order.symbol = Some(symbol.to_string());
+    order.exe_price = exe_price;
+    order.exe_size = order.size;
+
+    self.complete_order(symbol, &mut order, strat, exe_time);
+    order
+  }
+  fn complete_order<S: Strategy<BK = Self, DS = PortfolioDataSource>>(
+    &mut self,
+    symbol: &str,
+    order: &mut Order,
+    strat: &S,
+    completed_at: DateTime<Utc>,
+  ) {
+    order.settle(strat.calc_commission(order.exe_size, order.exe_price));
+    self.cash -= order.cost + order.comm;
+    let position = self.positions.entry(symbol.to_string()).or_default();
+    let pre_s = position.deal(order);
+    let post_s = position.size;
+
+    order.status = OrderStatus::Completed(completed_at);
+    strat.on_order(order, self);
+
+    let trade = self.trades.entry(symbol.to_string()).or_insert_with(|| {
+      let mut trade = Trade::new();
+      trade.symbol = Some(symbol.to_string());
+      trade
+    });
+    for trade in trade.deal(order, pre_s, post_s, completed_at) {
+      strat.on_trade(&trade, self);
+    }
+  }
+}
+
+#[test]
+fn test_portfolio_broker() {
+  use crate::{CsvDataSource, CsvTimeType, Engine};
+
+  let load = |content: &str| {
+    CsvDataSource::builder()
+      .time_field("date")
+      .time_type(CsvTimeType::Date("%Y-%m-%d"))
+      .load_from_string(content)
+      .unwrap()
+  };
+  let a = load("date,open,close\n2022-01-03,10,11\n2022-01-04,12,13\n2022-01-05,14,15");
+  // b 缺失 2022-01-04 的数据，并且从 2022-01-04 才开始
+  let b = load("date,open,close\n2022-01-05,20,21\n2022-01-06,22,23");
+  let data = PortfolioDataSource::new().add_feed("a", a).add_feed("b", b);
+  assert_eq!(data.symbols(), &["a".to_string(), "b".to_string()]);
+  assert_eq!(data.len(), 4);
+  assert!(data.view("b", 1).is_none());
+  assert_eq!(data.views(1).count(), 1);
+  let view = data.view("a", 3).unwrap();
+  assert_eq!((view.index, view.updated, view.close()), (2, false, 15.));
+  assert!(data.view("b", 2).unwrap().updated);
+
+  struct Strat;
+  impl Strategy for Strat {
+    type DS = PortfolioDataSource;
+    type BK = PortfolioBroker;
+    fn feed(&mut self, _data: &Self::DS) {}
+    fn next(&mut self, index: usize, data: &Self::DS, broker: &mut Self::BK) {
+      match index {
+        0 => broker.buy("a", 10, data, self),
+        2 => broker.sell("b", 5, data, self),
+        3 => broker.sell("a", 10, data, self),
+        _ => {}
+      }
+    }
+    fn calc_commission(&self, _size: isize, _price: f64) -> f64 {
+      1.
+    }
+    fn on_finish(&self, data: &Self::DS, broker: &Self::BK) {
+      // a 以 12 买入，最后一个 bar 以 close 15 卖出；b 以 22 卖空，按 close 23 计算价值
+      assert_eq!(broker.position_size("a"), 0);
+      assert_eq!(broker.position_size("b"), -5);
+      assert_eq!(broker.cash(), 1000. - 120. - 1. + 110. - 1. + 150. - 1.);
+      assert_eq!(broker.value(data), broker.cash() - 5. * 23.);
+      assert_eq!(broker.trade("a").unwrap().symbol.as_deref(), Some("a"));
+    }
+  }
+  let mut engine = Engine::new(data, Strat, PortfolioBroker::new(1000.));
+  engine.run();
+}
+
+#[test]
+fn test_portfolio_value_matches_single() {
+  use crate::{CsvBroker, CsvDataSource, CsvTimeType, Engine, SingleBroker};
+  use std::{cell::RefCell, rc::Rc};
+
+  // 同一个品种的同一个仓位，在 CsvDataSource 和 PortfolioDataSource 上的估值一致
+  let content = "date,open,close\n2022-01-03,10,11\n2022-01-04,12,13\n2022-01-05,14,15";
+  let load = || {
+    CsvDataSource::builder()
+      .time_field("date")
+      .time_type(CsvTimeType::Date("%Y-%m-%d"))
+      .load_from_string(content)
+      .unwrap()
+  };
+  let values = Rc::new(RefCell::new(Vec::new()));
+
+  struct Single(Rc<RefCell<Vec<f64>>>);
+  impl Strategy for Single {
+    type DS = CsvDataSource;
+    type BK = CsvBroker;
+    fn feed(&mut self, _data: &Self::DS) {}
+    fn next(&mut self, index: usize, data: &Self::DS, broker: &mut Self::BK) {
+      self.0.borrow_mut().push(broker.value(data));
+      if index == 0 {
+        broker.buy(10, data, self);
+      }
+    }
+    fn calc_commission(&self, _size: isize, _price: f64) -> f64 {
+      0.
+    }
+    fn on_finish(&self, data: &Self::DS, broker: &Self::BK) {
+      self.0.borrow_mut().push(broker.value(data));
+    }
+  }
+  Engine::new(load(), Single(values.clone()), CsvBroker::new(1000.)).run();
+  let single = values.take();
+  // 以 12 买入，next 中按上一个 bar 的 close 估值，结束后按最后一个 bar 的 close 估值
+  assert_eq!(single, vec![1000., 880. + 110., 880. + 130., 880. + 150.]);
+
+  struct Portfolio(Rc<RefCell<Vec<f64>>>);
+  impl Strategy for Portfolio {
+    type DS = PortfolioDataSource;
+    type BK = PortfolioBroker;
+    fn feed(&mut self, _data: &Self::DS) {}
+    fn next(&mut self, index: usize, data: &Self::DS, broker: &mut Self::BK) {
+      self.0.borrow_mut().push(broker.value(data));
+      if index == 0 {
+        broker.buy("a", 10, data, self);
+      }
+    }
+    fn calc_commission(&self, _size: isize, _price: f64) -> f64 {
+      0.
+    }
+    fn on_finish(&self, data: &Self::DS, broker: &Self::BK) {
+      self.0.borrow_mut().push(broker.value(data));
+    }
+  }
+  let data = PortfolioDataSource::new().add_feed("a", load());
+  Engine::new(data, Portfolio(values.clone()), PortfolioBroker::new(1000.)).run();
+  assert_eq!(values.take(), single);
+}
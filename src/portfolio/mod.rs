@@ -0,0 +1,5 @@
+mod broker;
+mod source;
+
+pub use broker::*;
+pub use source::*;
@@ -0,0 +1,168 @@
+use chrono::{DateTime, Utc};
+
+use crate::data::DataSource;
+use crate::{Broker, CsvDataSource, CsvTimeLine, DataLine, Strategy};
+
+/// 多品种的数据源，将多个品种的数据按时间对齐到同一条时间线上。
+/// 时间线是所有品种的时间的并集，Strategy::next 的 index 是时间线上的下标，
+/// 可以通过 view 或者 views 获取各个品种在当前时间点上最近的 bar。
+pub struct PortfolioDataSource {
+  pub(crate) offset: usize,
+  symbols: Vec<String>,
+  feeds: Vec<CsvDataSource>,
+  pub timestamp: CsvTimeLine,
+  /// 每个品种在时间线的每个时间点上已经出现的 bar 数量
+  counts: Vec<Vec<usize>>,
+}
+
+/// 某个品种在时间线某个时间点上的视图。
+pub struct FeedView<'a> {
+  pub symbol: &'a str,
+  pub data: &'a CsvDataSource,
+  /// 最近一个 bar 在该品种自己的数据源中的下标
+  pub index: usize,
+  /// 该品种在当前时间点上是否有新的 bar
+  pub updated: bool,
+}
+
+impl<'a> FeedView<'a> {
+  #[inline]
+  pub fn timestamp(&self) -> DateTime<Utc> {
+    self.data.timestamp[self.index]
+  }
+  #[inline]
+  pub fn open(&self) -> f64 {
+    self.data.open.at(self.index).unwrap()
+  }
+  #[inline]
+  pub fn close(&self) -> f64 {
+    self.data.close.at(self.index).unwrap()
+  }
+}
+
+impl PortfolioDataSource {
+  pub fn new() -> Self {
+    Self {
+      offset: 0,
+      symbols: Vec::new(),
+      feeds: Vec::new(),
+      timestamp: CsvTimeLine::new(Vec::new()),
+      counts: Vec::new(),
+    }
+  }
+  /// 添加一个品种的数据，已经存在的同名品种会被替换。
+  pub fn add_feed(mut self, symbol: &str, data: CsvDataSource) -> Self {
+    match self.symbols.iter().position(|s| s == symbol) {
+      Some(i) => self.feeds[i] = data,
+      None => {
+        self.symbols.push(symbol.to_string());
+        self.feeds.push(data);
+      }
+    }
+    self.align();
+    self
+  }
+  /// 重新计算所有品种时间的并集以及各个品种在时间线上的位置。
+  fn align(&mut self) {
+    let mut timestamps: Vec<DateTime<Utc>> = self
+      .feeds
+      .iter()
+      .flat_map(|feed| feed.timestamp.as_slice().iter().copied())
+      .collect();
+    timestamps.sort();
+    timestamps.dedup();
+    self.counts = self
+      .feeds
+      .iter()
+      .map(|feed| {
+        let feed_ts = feed.timestamp.as_slice();
+        let mut count = 0;
+        timestamps
+          .iter()
+          .map(|t| {
+            while count < feed_ts.len() && feed_ts[count] <= *t {
+              count += 1;
+            }
+            count
+          })
+          .collect()
+      })
+      .collect();
+    self.timestamp = CsvTimeLine::new(timestamps);
+  }
+  #[inline]
+  pub fn symbols(&self) -> &[String] {
+    &self.symbols
+  }
+  /// 获取品种的数据源，可以用于计算该品种的指标，指标的下标对应 FeedView::index。
+  #[inline]
+  pub fn feed(&self, symbol: &str) -> Option<&CsvDataSource> {
+    self
+      .symbols
+      .iter()
+      .position(|s| s == symbol)
+      .map(|i| &self.feeds[i])
+  }
+  /// 时间线的长度
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.timestamp.len()
+  }
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.timestamp.is_empty()
+  }
+  #[inline]
+  fn view_of(&self, i: usize, index: usize) -> Option<FeedView<'_>> {
+    let count = *self.counts[i].get(index)?;
+    if count == 0 {
+      return None;
+    }
+    let data = &self.feeds[i];
+    Some(FeedView {
+      symbol: &self.symbols[i],
+      data,
+      index: count - 1,
+      updated: data.timestamp[count - 1] == self.timestamp[index],
+    })
+  }
+  /// 获取品种在时间线第 index 个时间点上最近一个 bar 的视图，该品种还没有数据时返回 None。
+  pub fn view(&self, symbol: &str, index: usize) -> Option<FeedView<'_>> {
+    let i = self.symbols.iter().position(|s| s == symbol)?;
+    self.view_of(i, index)
+  }
+  /// 获取所有已经有数据的品种在时间线第 index 个时间点上的视图。
+  pub fn views(&self, index: usize) -> impl Iterator<Item = FeedView<'_>> {
+    (0..self.feeds.len()).filter_map(move |i| self.view_of(i, index))
+  }
+  /// 品种在当前时间点上最近一个 bar 的视图
+  #[inline]
+  pub(crate) fn current(&self, symbol: &str) -> Option<FeedView<'_>> {
+    if self.is_empty() {
+      return None;
+    }
+    self.view(symbol, self.offset.min(self.len() - 1))
+  }
+}
+
+impl Default for PortfolioDataSource {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl DataSource for PortfolioDataSource {
+  fn read<B: Broker<DS = Self>, S: Strategy<DS = Self, BK = B>>(
+    &mut self,
+    strat: &mut S,
+    broker: &mut B,
+  ) -> bool {
+    strat.feed(self);
+    let len = self.len();
+    while self.offset < len {
+      strat.next(self.offset, self, broker);
+      self.offset += 1;
+    }
+    false
+  }
+}
@@ -1,4 +1,4 @@
-use crate::{Broker, DataSource, Order, Trade};
+use crate::{Broker, DataSource, Order, TimerEvent, Trade};
 
 pub trait Strategy {
   type DS: DataSource;
@@ -12,6 +12,10 @@ pub trait Strategy {
   fn on_trade(&self, _trade: &Trade, _broker: &Self::BK) {
     // do nothing by default
   }
+  /// 通过 Engine::add_timer 注册的定时器的计划触发时间被 bar 跨过时调用，在该 bar 的 next 之前执行。
+  fn on_timer(&mut self, _event: &TimerEvent, _data: &Self::DS, _broker: &mut Self::BK) {
+    // do nothing by default
+  }
   fn on_start(&self, _data: &Self::DS, _broker: &Self::BK) {
     // do nothing by default
   }
@@ -0,0 +1,430 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+use crate::{CsvDataSource, CsvDataSourceBuilder};
+
+use super::SyntheticRng;
+
+/// 状态切换模型中的一个状态，drift 和 volatility 是每个 bar 的对数收益率的均值和标准差。
+#[derive(Debug, Clone, Copy)]
+pub struct Regime {
+  pub drift: f64,
+  pub volatility: f64,
+}
+
+impl Regime {
+  pub fn new(drift: f64, volatility: f64) -> Self {
+    Self { drift, volatility }
+  }
+}
+
+/// 生成价格的随机过程，所有参数都以一个 bar 为时间单位。
+#[derive(Debug, Clone)]
+pub enum PriceProcess {
+  /// 算术随机游走，每个 bar 的价格变化服从 N(0, step²)，价格在 0 处反射，始终为正数。
+  RandomWalk { step: f64 },
+  /// 几何布朗运动，每个 bar 的对数收益率服从 N(drift - volatility² / 2, volatility²)。
+  Gbm { drift: f64, volatility: f64 },
+  /// GARCH(1,1) 波动率模型：σ²(t) = omega + alpha * ε²(t-1) + beta * σ²(t-1)，
+  /// 其中 ε 是去掉 drift 之后的对数收益率。要求 alpha + beta < 1，初始方差为长期方差 omega / (1 - alpha - beta)。
+  Garch {
+    drift: f64,
+    omega: f64,
+    alpha: f64,
+    beta: f64,
+  },
+  /// 马尔可夫状态切换模型，每个状态内按几何布朗运动生成价格，
+  /// transition[i][j] 是当前 bar 处于状态 i 时下一个 bar 切换到状态 j 的概率。从第一个状态开始。
+  RegimeSwitching {
+    regimes: Vec<Regime>,
+    transition: Vec<Vec<f64>>,
+  },
+}
+
+impl PriceProcess {
+  fn check(&self) {
+    match self {
+      PriceProcess::RandomWalk { step } => assert!(*step > 0., "step must be greater than zero"),
+      PriceProcess::Gbm { volatility, .. } => {
+        assert!(*volatility >= 0., "volatility must not be negative")
+      }
+      PriceProcess::Garch {
+        omega, alpha, beta, ..
+      } => {
+        assert!(*omega > 0., "omega must be greater than zero");
+        assert!(
+          *alpha >= 0. && *beta >= 0. && alpha + beta < 1.,
+          "alpha and beta must not be negative and alpha + beta must be less than 1"
+        );
+      }
+      PriceProcess::RegimeSwitching {
+        regimes,
+        transition,
+      } => {
+        assert!(!regimes.is_empty(), "regimes must not be empty");
+        assert!(
+          regimes.iter().all(|r| r.volatility >= 0.),
+          "volatility must not be negative"
+        );
+        assert!(
+          transition.len() == regimes.len()
+            && transition.iter().all(|row| row.len() == regimes.len()
+              && row.iter().all(|p| *p >= 0.)
+              && (row.iter().sum::<f64>() - 1.).abs() < 1e-9),
+          "transition must be a square matrix of probabilities with rows summing to 1"
+        );
+      }
+    }
+  }
+}
+
+/// 随机过程在生成过程中的状态
+struct ProcessState {
+  /// GARCH 的条件方差
+  variance: f64,
+  /// 状态切换模型的当前状态
+  regime: usize,
+}
+
+/// 模拟数据源的构建器，按指定的随机过程生成 OHLCV 数据，相同的配置和 seed 总是生成相同的数据。
+/// 每个 bar 内部按 intrabar_steps 个子步骤模拟价格路径，open 为上一个 bar 的 close，
+/// high、low 为路径上的最高价和最低价，volume 服从对数正态分布。
+pub struct SyntheticDataSourceBuilder {
+  seed: u64,
+  start: DateTime<Utc>,
+  interval: Duration,
+  initial_price: f64,
+  intrabar_steps: usize,
+  volume: f64,
+}
+
+impl SyntheticDataSourceBuilder {
+  pub fn new() -> Self {
+    Self {
+      seed: 0,
+      start: Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap(),
+      interval: Duration::days(1),
+      initial_price: 100.,
+      intrabar_steps: 4,
+      volume: 1000.,
+    }
+  }
+  /// 随机数种子，默认为 0
+  pub fn seed(mut self, seed: u64) -> Self {
+    self.seed = seed;
+    self
+  }
+  /// 第一个 bar 的时间，默认为 2000-01-01 00:00:00 UTC
+  pub fn start(mut self, start: DateTime<Utc>) -> Self {
+    self.start = start;
+    self
+  }
+  /// bar 的周期，默认为 1 天
+  pub fn interval(mut self, interval: Duration) -> Self {
+    self.interval = interval;
+    self
+  }
+  /// 初始价格，默认为 100
+  pub fn initial_price(mut self, price: f64) -> Self {
+    self.initial_price = price;
+    self
+  }
+  /// 每个 bar 内部模拟的子步骤数量，用于生成 high、low，默认为 4
+  pub fn intrabar_steps(mut self, steps: usize) -> Self {
+    self.intrabar_steps = steps;
+    self
+  }
+  /// 平均成交量，默认为 1000
+  pub fn volume(mut self, volume: f64) -> Self {
+    self.volume = volume;
+    self
+  }
+  /// 生成 bars 个 bar 的数据源。状态切换模型会额外生成 regime 列（状态的下标），可以通过 CsvDataSource::extra 获取。
+  /// 参数不合法时 panic。
+  pub fn generate(self, process: &PriceProcess, bars: usize) -> CsvDataSource {
+    process.check();
+    assert!(
+      self.initial_price > 0.,
+      "initial_price must be greater than zero"
+    );
+    assert!(
+      self.intrabar_steps > 0,
+      "intrabar_steps must be greater than zero"
+    );
+    assert!(
+      self.interval > Duration::zero(),
+      "interval must be greater than zero"
+    );
+
+    let mut rng = SyntheticRng::new(self.seed);
+    let mut state = ProcessState {
+      variance: match process {
+        PriceProcess::Garch {
+          omega, alpha, beta, ..
+        } => omega / (1. - alpha - beta),
+        _ => 0.,
+      },
+      regime: 0,
+    };
+    let steps = self.intrabar_steps as f64;
+    let mut timestamp = Vec::with_capacity(bars);
+    let mut columns: [Vec<f64>; 6] = Default::default();
+    let mut price = self.initial_price;
+    for i in 0..bars {
+      let open = price;
+      let (mut high, mut low) = (open, open);
+      match process {
+        PriceProcess::RandomWalk { step } => {
+          for _ in 0..self.intrabar_steps {
+            price = (price + step / steps.sqrt() * rng.normal())
+              .abs()
+              .max(f64::MIN_POSITIVE);
+            high = high.max(price);
+            low = low.min(price);
+          }
+        }
+        _ => {
+          let (drift, volatility) = match process {
+            PriceProcess::Gbm { drift, volatility } => (*drift, *volatility),
+            PriceProcess::Garch { drift, .. } => (*drift, state.variance.sqrt()),
+            PriceProcess::RegimeSwitching { regimes, .. } => {
+              let regime = regimes[state.regime];
+              (regime.drift, regime.volatility)
+            }
+            PriceProcess::RandomWalk { .. } => unreachable!(),
+          };
+          let mu = (drift - volatility * volatility / 2.) / steps;
+          let sigma = volatility / steps.sqrt();
+          for _ in 0..self.intrabar_steps {
+            price *= (mu + sigma * rng.normal()).exp();
+            high = high.max(price);
+            low = low.min(price);
+          }
+        }
+      }
+      columns[5].push(state.regime as f64);
+      match process {
+        PriceProcess::Garch {
+          drift,
+          omega,
+          alpha,
+          beta,
+        } => {
+          let eps = (price / open).ln() - drift;
+          state.variance = omega + alpha * eps * eps + beta * state.variance;
+        }
+        PriceProcess::RegimeSwitching { transition, .. } => {
+          let u = rng.uniform();
+          let row = &transition[state.regime];
+          let mut acc = 0.;
+          state.regime = row
+            .iter()
+            .position(|p| {
+              acc += p;
+              u < acc
+            })
+            .unwrap_or(row.len() - 1);
+        }
+        _ => {}
+      }
+      timestamp.push(self.start + self.interval * i as i32);
+      columns[0].push(open);
+      columns[1].push(price);
+      columns[2].push(high);
+      columns[3].push(low);
+      columns[4].push((self.volume * (0.25 * rng.normal()).exp()).round());
+    }
+
+    let [open, close, high, low, volume, regime] = columns;
+    let mut vecs = vec![
+      ("open", open),
+      ("close", close),
+      ("high", high),
+      ("low", low),
+      ("volume", volume),
+    ];
+    let mut builder = CsvDataSourceBuilder::new();
+    if matches!(process, PriceProcess::RegimeSwitching { .. }) {
+      builder = builder.extra_field("regime");
+      vecs.push(("regime", regime));
+    }
+    builder.load_from_vecs(timestamp, vecs).unwrap()
+  }
+}
+
+impl Default for SyntheticDataSourceBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[test]
+fn test_synthetic_generators() {
+  let log_returns = |data: &CsvDataSource| -> Vec<f64> {
+    let close = data.close.as_slice();
+    close.windows(2).map(|w| (w[1] / w[0]).ln()).collect()
+  };
+  let std = |v: &[f64]| {
+    let mean = v.iter().sum::<f64>() / v.len() as f64;
+    (v.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / v.len() as f64).sqrt()
+  };
+
+  let gbm = PriceProcess::Gbm {
+    drift: 0.0005,
+    volatility: 0.01,
+  };
+  let a = SyntheticDataSourceBuilder::new()
+    .seed(7)
+    .generate(&gbm, 2000);
+  let b = SyntheticDataSourceBuilder::new()
+    .seed(7)
+    .generate(&gbm, 2000);
+  let c = SyntheticDataSourceBuilder::new()
+    .seed(8)
+    .generate(&gbm, 2000);
+  assert_eq!(a.close.as_slice(), b.close.as_slice());
+  assert_ne!(a.close.as_slice(), c.close.as_slice());
+  assert_eq!(a.len(), 2000);
+  assert_eq!(a.timestamp[1] - a.timestamp[0], Duration::days(1));
+  assert!((std(&log_returns(&a)) - 0.01).abs() < 0.001);
+  let (open, close) = (a.open.as_slice(), a.close.as_slice());
+  let (high, low) = (a.high.as_slice(), a.low.as_slice());
+  for i in 0..a.len() {
+    assert!(high[i] >= open[i].max(close[i]) && low[i] <= open[i].min(close[i]) && low[i] > 0.);
+    if i > 0 {
+      assert_eq!(open[i], close[i - 1]);
+    }
+  }
+  assert!(a.volume.as_slice().iter().all(|v| *v > 0.));
+
+  let walk = SyntheticDataSourceBuilder::new()
+    .initial_price(1.)
+    .generate(&PriceProcess::RandomWalk { step: 0.5 }, 500);
+  assert!(walk.low.as_slice().iter().all(|v| *v > 0.));
+
+  // GARCH 的收益率平方存在正的自相关（波动率聚集）
+  let garch = PriceProcess::Garch {
+    drift: 0.,
+    omega: 0.000002,
+    alpha: 0.1,
+    beta: 0.88,
+  };
+  let data = SyntheticDataSourceBuilder::new()
+    .seed(3)
+    .generate(&garch, 5000);
+  let sq: Vec<f64> = log_returns(&data).iter().map(|r| r * r).collect();
+  let mean = sq.iter().sum::<f64>() / sq.len() as f64;
+  let cov = sq
+    .windows(2)
+    .map(|w| (w[0] - mean) * (w[1] - mean))
+    .sum::<f64>();
+  let var = sq.iter().map(|v| (v - mean).powi(2)).sum::<f64>();
+  assert!(cov / var > 0.05, "autocorrelation {}", cov / var);
+
+  let regime = PriceProcess::RegimeSwitching {
+    regimes: vec![Regime::new(0., 0.005), Regime::new(0., 0.03)],
+    transition: vec![vec![0.98, 0.02], vec![0.05, 0.95]],
+  };
+  let data = SyntheticDataSourceBuilder::new()
+    .seed(11)
+    .generate(&regime, 3000);
+  let regimes = data.extra("regime").unwrap().as_slice();
+  let returns = log_returns(&data);
+  let by_regime = |k: f64| -> Vec<f64> {
+    returns
+      .iter()
+      .zip(&regimes[1..])
+      .filter(|(_, r)| **r == k)
+      .map(|(v, _)| *v)
+      .collect()
+  };
+  let (calm, wild) = (by_regime(0.), by_regime(1.));
+  assert!(!calm.is_empty() && !wild.is_empty());
+  assert!(std(&wild) > 3. * std(&calm));
+}
+
+#[test]
+fn test_broker_conservation() {
+  use crate::{Broker, CsvBroker, DataLine, Engine, Order, SingleBroker, Strategy};
+  use std::cell::{Cell, RefCell};
+
+  // 对随机生成的数据和随机的交易，检查 broker 的资金守恒：
+  // 现金 = 初始资金 - Σ(成交量 × 成交价) - Σ 手续费，
+  // 最终价值 = 初始资金 + Σ 成交量 × (最终价格 - 成交价) - Σ 手续费
+  struct Strat {
+    rng: SyntheticRng,
+    cash: f64,
+    fills: RefCell<Vec<(isize, f64)>>,
+    comm: Cell<f64>,
+  }
+  impl Strat {
+    fn expected_cash(&self) -> f64 {
+      let fills = self.fills.borrow();
+      self.cash - fills.iter().map(|(s, p)| *s as f64 * p).sum::<f64>() - self.comm.get()
+    }
+  }
+  impl Strategy for Strat {
+    type DS = CsvDataSource;
+    type BK = CsvBroker;
+    fn feed(&mut self, _data: &Self::DS) {}
+    fn next(&mut self, _index: usize, data: &Self::DS, broker: &mut Self::BK) {
+      assert!((broker.cash() - self.expected_cash()).abs() < 1e-6);
+      let size = (self.rng.uniform() * 20.) as isize + 1;
+      match self.rng.uniform() {
+        u if u < 0.2 => broker.buy(size, data, self),
+        u if u < 0.4 => broker.sell(size, data, self),
+        _ => {}
+      }
+    }
+    fn calc_commission(&self, size: isize, price: f64) -> f64 {
+      size.abs() as f64 * price * 0.001
+    }
+    fn on_order(&self, order: &Order, _broker: &Self::BK) {
+      self
+        .fills
+        .borrow_mut()
+        .push((order.deal_size(), order.exe_price));
+      self.comm.set(self.comm.get() + order.comm);
+    }
+    fn on_finish(&self, data: &Self::DS, broker: &Self::BK) {
+      let fills = self.fills.borrow();
+      let size: isize = fills.iter().map(|(s, _)| s).sum();
+      assert_eq!(broker.position_size(), size);
+      assert!((broker.cash() - self.expected_cash()).abs() < 1e-6);
+      let last = data.close.at(data.len() - 1).unwrap();
+      let pnl = fills
+        .iter()
+        .map(|(s, p)| *s as f64 * (last - p))
+        .sum::<f64>();
+      let expected = self.cash + pnl - self.comm.get();
+      assert!((broker.value(data) - expected).abs() < 1e-6);
+    }
+  }
+
+  let processes = [
+    PriceProcess::RandomWalk { step: 1. },
+    PriceProcess::Gbm {
+      drift: 0.,
+      volatility: 0.02,
+    },
+    PriceProcess::Garch {
+      drift: 0.,
+      omega: 0.00001,
+      alpha: 0.1,
+      beta: 0.85,
+    },
+  ];
+  for seed in 0..20 {
+    for process in &processes {
+      let data = SyntheticDataSourceBuilder::new()
+        .seed(seed)
+        .generate(process, 200);
+      let strat = Strat {
+        rng: SyntheticRng::new(seed + 1000),
+        cash: 100000.,
+        fills: RefCell::new(Vec::new()),
+        comm: Cell::new(0.),
+      };
+      Engine::new(data, strat, CsvBroker::new(100000.)).run();
+    }
+  }
+}
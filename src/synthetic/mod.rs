@@ -0,0 +1,5 @@
+mod generator;
+mod rng;
+
+pub use generator::*;
+pub use rng::*;
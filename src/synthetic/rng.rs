@@ -0,0 +1,62 @@
+/// 确定性的伪随机数生成器（SplitMix64），相同的 seed 总是生成相同的序列。
+/// 只用于生成测试数据，不适用于任何安全相关的场景。
+#[derive(Debug, Clone)]
+pub struct SyntheticRng {
+  state: u64,
+  /// Box-Muller 变换每次生成两个正态分布的随机数，缓存其中一个
+  spare: Option<f64>,
+}
+
+impl SyntheticRng {
+  pub fn new(seed: u64) -> Self {
+    Self {
+      state: seed,
+      spare: None,
+    }
+  }
+  #[inline]
+  pub fn next_u64(&mut self) -> u64 {
+    self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = self.state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+  }
+  /// [0, 1) 区间上均匀分布的随机数
+  #[inline]
+  pub fn uniform(&mut self) -> f64 {
+    (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+  }
+  /// 标准正态分布的随机数
+  pub fn normal(&mut self) -> f64 {
+    if let Some(v) = self.spare.take() {
+      return v;
+    }
+    // 1 - uniform 的取值范围是 (0, 1]，避免 ln(0)
+    let r = (-2. * (1. - self.uniform()).ln()).sqrt();
+    let theta = 2. * std::f64::consts::PI * self.uniform();
+    self.spare = Some(r * theta.sin());
+    r * theta.cos()
+  }
+}
+
+#[test]
+fn test_synthetic_rng() {
+  let mut a = SyntheticRng::new(42);
+  let mut b = SyntheticRng::new(42);
+  assert!((0..100).all(|_| a.next_u64() == b.next_u64()));
+  assert_ne!(
+    SyntheticRng::new(1).next_u64(),
+    SyntheticRng::new(2).next_u64()
+  );
+
+  let n = 20000;
+  let samples: Vec<f64> = (0..n).map(|_| a.normal()).collect();
+  let mean = samples.iter().sum::<f64>() / n as f64;
+  let var = samples.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n as f64;
+  assert!(mean.abs() < 0.03, "mean {}", mean);
+  assert!((var - 1.).abs() < 0.05, "var {}", var);
+  assert!((0..1000)
+    .map(|_| a.uniform())
+    .all(|v| (0. ..1.).contains(&v)));
+}